@@ -1,16 +1,27 @@
 import { ref } from 'vue';
-import { getCookie, setCookie, deleteCookie } from '../utils/cookies';
-import { api } from '../utils/api';
+import { getCookie, setCookie } from '../utils/cookies';
+import { api, saveTokens, clearTokens, refreshTokens } from '../utils/api';
 import { useSettings } from './useSettings';
 
 interface User {
   id?: string;
   username: string;
   email: string;
+  emailVerified?: boolean;
+}
+
+export interface Session {
+  id: string;
+  user_agent: string | null;
+  ip_address: string | null;
+  created_at: string;
+  last_seen_at: string;
+  current: boolean;
 }
 
 interface ApiResponse {
   token: string;
+  refresh_token: string;
   id?: string;
   user_id?: string;
   username?: string;
@@ -22,8 +33,16 @@ interface ApiResponse {
   };
 }
 
+export interface SsoProvider {
+  id: string;
+  name: string;
+}
+
 const API_BASE = '/api';
 const TOKEN_KEY = 'auth_token';
+const SSO_STATE_KEY = 'sso_state';
+// localStorage, а не sessionStorage: ссылку из письма обычно открывают в новой вкладке
+const MAGIC_LINK_KEY = 'magic_link_browser_token';
 
 const user = ref<User | null>(null);
 const isAuthenticated = ref(false);
@@ -38,7 +57,7 @@ const initPromise = (async () => {
     } catch {
       // Если токен невалидный, очищаем
       token.value = null;
-      deleteCookie(TOKEN_KEY);
+      clearTokens();
     }
   }
   isInitializing.value = false;
@@ -61,7 +80,8 @@ async function verifyToken(): Promise<void> {
   user.value = {
     id: data.id || data.user_id,
     username: data.username || 'User',
-    email: data.email || ''
+    email: data.email || '',
+    emailVerified: data.email_verified
   };
   isAuthenticated.value = true;
   
@@ -74,6 +94,10 @@ export function useAuth() {
   const login = async (email: string, password: string): Promise<void> => {
     const response = await api.post(`${API_BASE}/auth/login`, { email, password }, { skipAuth: true });
 
+    if (response.status === 429) {
+      throw new Error('Слишком много попыток входа, попробуйте позже');
+    }
+
     if (!response.ok) {
       const error = await response.json().catch(() => ({ message: 'Ошибка входа' }));
       throw new Error(error.message || 'Неверный email или пароль');
@@ -86,9 +110,9 @@ export function useAuth() {
       throw new Error('Токен не получен от сервера');
     }
     
-    // Сохраняем токен в cookie (7 дней)
+    // Сохраняем пару токенов в cookie
     token.value = data.token;
-    setCookie(TOKEN_KEY, data.token, 7);
+    saveTokens(data.token, data.refresh_token);
     
     // Обрабатываем разные структуры ответа
     user.value = {
@@ -119,9 +143,9 @@ export function useAuth() {
       throw new Error('Токен не получен от сервера');
     }
     
-    // Сохраняем токен в cookie (7 дней)
+    // Сохраняем пару токенов в cookie
     token.value = data.token;
-    setCookie(TOKEN_KEY, data.token, 7);
+    saveTokens(data.token, data.refresh_token);
     
     // Обрабатываем разные структуры ответа
     user.value = {
@@ -137,9 +161,134 @@ export function useAuth() {
     await fetchSettings();
   };
 
-  const logout = () => {
+  const requestMagicLink = async (email: string): Promise<void> => {
+    const response = await api.post(`${API_BASE}/auth/magic-link`, { email }, { skipAuth: true });
+
+    if (response.status === 429) {
+      throw new Error('Слишком много запросов, попробуйте позже');
+    }
+
+    if (!response.ok) {
+      throw new Error('Не удалось отправить ссылку');
+    }
+
+    const data = await response.json();
+    localStorage.setItem(MAGIC_LINK_KEY, data.browser_token);
+  };
+
+  const consumeMagicLink = async (linkToken: string): Promise<void> => {
+    const browserToken = localStorage.getItem(MAGIC_LINK_KEY);
+    if (!browserToken) {
+      throw new Error('Откройте ссылку в том браузере, где вы ее запросили');
+    }
+
+    const response = await api.post(
+      `${API_BASE}/auth/magic-link/consume`,
+      { token: linkToken, browser_token: browserToken },
+      { skipAuth: true }
+    );
+
+    if (!response.ok) {
+      throw new Error('Ссылка недействительна или устарела, запросите новую');
+    }
+
+    const data: ApiResponse = await response.json();
+
+    if (!data.token) {
+      throw new Error('Токен не получен от сервера');
+    }
+
+    localStorage.removeItem(MAGIC_LINK_KEY);
+    token.value = data.token;
+    saveTokens(data.token, data.refresh_token);
+
+    user.value = {
+      id: data.user?.id || data.id || data.user_id,
+      username: data.user?.username || data.username || 'User',
+      email: data.user?.email || data.email || ''
+    };
+    isAuthenticated.value = true;
+    isInitializing.value = false;
+
+    const { fetchSettings } = useSettings();
+    await fetchSettings();
+  };
+
+  const fetchSsoProviders = async (): Promise<SsoProvider[]> => {
+    const response = await api.get(`${API_BASE}/auth/oidc/providers`, { skipAuth: true });
+
+    if (!response.ok) {
+      return [];
+    }
+
+    return response.json();
+  };
+
+  // Уводит браузер на страницу входа провайдера; вернется он на /sso/callback
+  const startSso = async (providerId: string): Promise<void> => {
+    const response = await api.post(`${API_BASE}/auth/oidc/${providerId}/authorize`, {}, { skipAuth: true });
+
+    if (!response.ok) {
+      throw new Error('Провайдер входа недоступен');
+    }
+
+    const data = await response.json();
+    sessionStorage.setItem(SSO_STATE_KEY, JSON.stringify({ provider: providerId, state: data.state }));
+    window.location.href = data.authorization_url;
+  };
+
+  const completeSso = async (code: string, state: string): Promise<void> => {
+    const saved = sessionStorage.getItem(SSO_STATE_KEY);
+    sessionStorage.removeItem(SSO_STATE_KEY);
+
+    // state должен совпасть с тем, что выдали этому браузеру, иначе это чужой вход
+    const pending = saved ? JSON.parse(saved) : null;
+    if (!pending || pending.state !== state) {
+      throw new Error('Попытка входа устарела, начните заново');
+    }
+
+    const response = await api.post(
+      `${API_BASE}/auth/oidc/${pending.provider}/callback`,
+      { code, state },
+      { skipAuth: true }
+    );
+
+    if (response.status === 409) {
+      throw new Error('Аккаунт с этим email уже есть, войдите по паролю');
+    }
+
+    if (!response.ok) {
+      const error = await response.json().catch(() => ({ message: 'Ошибка входа' }));
+      throw new Error(error.message || 'Не удалось войти через SSO');
+    }
+
+    const data: ApiResponse = await response.json();
+
+    if (!data.token) {
+      throw new Error('Токен не получен от сервера');
+    }
+
+    token.value = data.token;
+    saveTokens(data.token, data.refresh_token);
+
+    user.value = {
+      id: data.user?.id || data.id || data.user_id,
+      username: data.user?.username || data.username || 'User',
+      email: data.user?.email || data.email || ''
+    };
+    isAuthenticated.value = true;
+    isInitializing.value = false;
+
+    const { fetchSettings } = useSettings();
+    await fetchSettings();
+  };
+
+  const logout = async (): Promise<void> => {
+    // Отзываем токен на сервере; локально выходим в любом случае
+    await api.post(`${API_BASE}/auth/logout`).catch(() => undefined);
+
     token.value = null;
-    deleteCookie(TOKEN_KEY);
+    clearTokens();
     user.value = null;
     isAuthenticated.value = false;
     isInitializing.value = false;
@@ -158,7 +307,7 @@ export function useAuth() {
     // Обновляем токен с новым username
     if (data.token) {
       token.value = data.token;
-      setCookie(TOKEN_KEY, data.token, 7);
+      setCookie(TOKEN_KEY, data.token, 30);
     }
     
     if (user.value) {
@@ -172,10 +321,38 @@ export function useAuth() {
     }
   };
 
-  const changePassword = async (currentPassword: string, newPassword: string): Promise<void> => {
+  // Подтверждение принимается только из сессии того же пользователя
+  const confirmEmailChange = async (confirmationToken: string): Promise<void> => {
+    const response = await api.post(`${API_BASE}/auth/email/confirm`, { token: confirmationToken });
+
+    if (response.status === 409) {
+      throw new Error('Этот адрес уже занят другим аккаунтом');
+    }
+    if (!response.ok) {
+      throw new Error('Ссылка недействительна или устарела');
+    }
+
+    const data = await response.json();
+
+    // Новый access-токен несет новый email
+    token.value = data.token;
+    setCookie(TOKEN_KEY, data.token, 30);
+
+    if (user.value) {
+      user.value.email = data.email;
+      user.value.emailVerified = true;
+    }
+  };
+
+  const changePassword = async (
+    currentPassword: string,
+    newPassword: string,
+    revokeOtherSessions: boolean = false
+  ): Promise<void> => {
     const response = await api.put(`${API_BASE}/auth/password`, { 
       current_password: currentPassword, 
-      new_password: newPassword 
+      new_password: newPassword,
+      revoke_other_sessions: revokeOtherSessions
     });
 
     if (!response.ok) {
@@ -184,7 +361,84 @@ export function useAuth() {
     }
   };
 
+  const fetchSessions = async (): Promise<Session[]> => {
+    const response = await api.get(`${API_BASE}/auth/sessions`);
+
+    if (!response.ok) {
+      throw new Error('Не удалось загрузить список сессий');
+    }
+
+    return response.json();
+  };
+
+  const revokeSession = async (sessionId: string): Promise<void> => {
+    const response = await api.delete(`${API_BASE}/auth/sessions/${sessionId}`);
+
+    if (!response.ok) {
+      throw new Error('Не удалось завершить сессию');
+    }
+  };
+
+  // Ответ одинаковый для любых адресов, поэтому ошибок кроме сетевых не бывает
+  const requestPasswordReset = async (email: string): Promise<void> => {
+    const response = await api.post(`${API_BASE}/auth/password/forgot`, { email }, { skipAuth: true });
+
+    if (!response.ok) {
+      throw new Error('Не удалось отправить письмо');
+    }
+  };
+
+  // Сброс завершает все сессии, включая текущую
+  const resetPassword = async (resetToken: string, newPassword: string): Promise<void> => {
+    const response = await api.post(
+      `${API_BASE}/auth/password/reset`,
+      { token: resetToken, new_password: newPassword },
+      { skipAuth: true }
+    );
+
+    if (!response.ok) {
+      const message = await response.text().catch(() => '');
+      throw new Error(message || 'Ссылка недействительна или устарела');
+    }
+
+    clearTokens();
+    token.value = null;
+    user.value = null;
+    isAuthenticated.value = false;
+  };
+
+  // Токен из письма не требует входа: ссылку могут открыть в другом браузере
+  const verifyEmail = async (verificationToken: string): Promise<void> => {
+    const response = await api.post(`${API_BASE}/auth/verify-email`, { token: verificationToken }, { skipAuth: true });
+
+    if (!response.ok) {
+      throw new Error('Ссылка недействительна или устарела');
+    }
+
+    // Признак подтверждения лежит в access-токене, поэтому обновляем его
+    if (isAuthenticated.value && await refreshTokens()) {
+      token.value = getCookie(TOKEN_KEY);
+      await verifyToken();
+    }
+  };
+
+  const resendVerification = async (): Promise<void> => {
+    const response = await api.post(`${API_BASE}/auth/verify-email/resend`);
+
+    if (response.status === 409) {
+      throw new Error('Почта уже подтверждена');
+    }
+    if (response.status === 429) {
+      throw new Error('Письмо уже отправлено, попробуйте чуть позже');
+    }
+    if (!response.ok) {
+      throw new Error('Не удалось отправить письмо');
+    }
+  };
+
   const getToken = (): string | null => {
+    // Токен в cookie мог обновиться через refresh
+    token.value = getCookie(TOKEN_KEY);
     return token.value;
   };
 
@@ -195,10 +449,22 @@ export function useAuth() {
     token,
     login,
     register,
+    requestMagicLink,
+    consumeMagicLink,
+    fetchSsoProviders,
+    startSso,
+    completeSso,
     logout,
     updateUserName,
     updateUserEmail,
+    confirmEmailChange,
     changePassword,
+    fetchSessions,
+    revokeSession,
+    requestPasswordReset,
+    resetPassword,
+    verifyEmail,
+    resendVerification,
     getToken,
     verifyToken,
     initPromise
@@ -0,0 +1,112 @@
+import { ref } from 'vue';
+import { api } from '../utils/api';
+import type { TaskLabel } from '../types/task';
+
+const API_BASE = '/api';
+
+export interface Label extends TaskLabel {
+  created_at: string;
+  updated_at: string;
+}
+
+// Как сочетать выбранные метки в фильтре задач
+export type LabelMatch = 'any' | 'all';
+
+const labels = ref<Label[]>([]);
+const loading = ref(false);
+const error = ref<string | null>(null);
+
+const sortByName = (list: Label[]) => [...list].sort((a, b) => a.name.localeCompare(b.name, 'ru'));
+
+const fetchLabels = async () => {
+  loading.value = true;
+  error.value = null;
+
+  try {
+    const response = await api.get(`${API_BASE}/labels`);
+
+    if (!response.ok) {
+      throw new Error('Ошибка загрузки меток');
+    }
+
+    labels.value = await response.json();
+  } catch (e: any) {
+    error.value = e.message;
+    console.error('Error fetching labels:', e);
+  } finally {
+    loading.value = false;
+  }
+};
+
+const createLabel = async (name: string, color: string) => {
+  const response = await api.post(`${API_BASE}/labels`, { name, color });
+  if (response.status === 409) {
+    throw new Error('Метка с таким именем уже есть');
+  }
+  if (!response.ok) {
+    throw new Error('Ошибка создания метки');
+  }
+
+  labels.value = sortByName([...labels.value, await response.json()]);
+};
+
+const updateLabel = async (id: string, data: { name?: string; color?: string }) => {
+  const response = await api.put(`${API_BASE}/labels/${id}`, data);
+  if (response.status === 409) {
+    throw new Error('Метка с таким именем уже есть — объедините их');
+  }
+  if (!response.ok) {
+    throw new Error('Ошибка обновления метки');
+  }
+
+  const updated: Label = await response.json();
+  labels.value = sortByName(labels.value.map(l => (l.id === id ? updated : l)));
+};
+
+// Задачи метки `id` получают метку `into`, сама `id` удаляется
+const mergeLabel = async (id: string, into: string) => {
+  const response = await api.post(`${API_BASE}/labels/${id}/merge`, { into });
+  if (!response.ok) {
+    throw new Error('Ошибка объединения меток');
+  }
+
+  labels.value = labels.value.filter(l => l.id !== id);
+};
+
+const deleteLabel = async (id: string) => {
+  const response = await api.delete(`${API_BASE}/labels/${id}`);
+  if (!response.ok) {
+    throw new Error('Ошибка удаления метки');
+  }
+
+  labels.value = labels.value.filter(l => l.id !== id);
+};
+
+const attachLabels = async (taskId: string, labelIds: string[]) => {
+  const response = await api.post(`${API_BASE}/tasks/${taskId}/labels`, { label_ids: labelIds });
+  if (!response.ok) {
+    throw new Error('Ошибка добавления меток');
+  }
+};
+
+const detachLabel = async (taskId: string, labelId: string) => {
+  const response = await api.delete(`${API_BASE}/tasks/${taskId}/labels/${labelId}`);
+  if (!response.ok) {
+    throw new Error('Ошибка снятия метки');
+  }
+};
+
+export function useLabels() {
+  return {
+    labels,
+    loading,
+    error,
+    fetchLabels,
+    createLabel,
+    updateLabel,
+    mergeLabel,
+    deleteLabel,
+    attachLabels,
+    detachLabel
+  };
+}
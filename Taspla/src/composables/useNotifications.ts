@@ -0,0 +1,105 @@
+import { ref } from 'vue';
+import { api } from '../utils/api';
+
+const API_BASE = '/api';
+
+export interface Notification {
+  id: string;
+  category: 'reminder' | 'overdue' | 'share';
+  title: string;
+  body: string;
+  task_id: string | null;
+  created_at: string;
+  read_at: string | null;
+}
+
+interface NotificationPage {
+  items: Notification[];
+  unread_count: number;
+  next_cursor: string | null;
+}
+
+const notifications = ref<Notification[]>([]);
+const unreadCount = ref(0);
+const nextCursor = ref<string | null>(null);
+const loading = ref(false);
+const error = ref<string | null>(null);
+
+// Первая страница заменяет список, следующие дописываются в конец
+const fetchNotifications = async (more = false) => {
+  loading.value = true;
+  error.value = null;
+
+  try {
+    const query = more && nextCursor.value ? `?cursor=${encodeURIComponent(nextCursor.value)}` : '';
+    const response = await api.get(`${API_BASE}/notifications${query}`);
+
+    if (!response.ok) {
+      throw new Error('Ошибка загрузки уведомлений');
+    }
+
+    const page: NotificationPage = await response.json();
+    notifications.value = more ? [...notifications.value, ...page.items] : page.items;
+    unreadCount.value = page.unread_count;
+    nextCursor.value = page.next_cursor;
+  } catch (e: any) {
+    error.value = e.message;
+    console.error('Error fetching notifications:', e);
+  } finally {
+    loading.value = false;
+  }
+};
+
+const markRead = async (id: string) => {
+  const response = await api.patch(`${API_BASE}/notifications/${id}/read`);
+  if (!response.ok) {
+    return;
+  }
+
+  const updated: Notification = await response.json();
+  const index = notifications.value.findIndex(n => n.id === id);
+  if (index !== -1 && !notifications.value[index].read_at) {
+    unreadCount.value = Math.max(0, unreadCount.value - 1);
+  }
+  if (index !== -1) {
+    notifications.value[index] = updated;
+  }
+};
+
+const markAllRead = async () => {
+  const response = await api.post(`${API_BASE}/notifications/read-all`);
+  if (!response.ok) {
+    return;
+  }
+
+  const now = new Date().toISOString();
+  notifications.value = notifications.value.map(n => ({ ...n, read_at: n.read_at || now }));
+  unreadCount.value = 0;
+};
+
+const deleteNotification = async (id: string) => {
+  const response = await api.delete(`${API_BASE}/notifications/${id}`);
+  if (!response.ok) {
+    return;
+  }
+
+  const removed = notifications.value.find(n => n.id === id);
+  if (removed && !removed.read_at) {
+    unreadCount.value = Math.max(0, unreadCount.value - 1);
+  }
+  notifications.value = notifications.value.filter(n => n.id !== id);
+};
+
+export function useNotifications() {
+  return {
+    notifications,
+    unreadCount,
+    nextCursor,
+    loading,
+    error,
+    fetchNotifications,
+    markRead,
+    markAllRead,
+    deleteNotification
+  };
+}
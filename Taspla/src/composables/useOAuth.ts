@@ -0,0 +1,115 @@
+import { api } from '../utils/api';
+
+const API_BASE = '/api';
+
+export interface AuthorizeInfo {
+  client_id: string;
+  client_name: string;
+  scopes: string[];
+  consented: boolean;
+}
+
+export interface DeviceInfo {
+  client_id: string;
+  client_name: string;
+  scopes: string[];
+}
+
+export interface OAuthConsent {
+  client_id: string;
+  client_name: string;
+  scopes: string[];
+  created_at: string;
+  updated_at: string;
+}
+
+// Человекочитаемые названия прав для экранов согласия
+export const SCOPE_LABELS: Record<string, string> = {
+  'tasks:read': 'Просмотр задач',
+  'tasks:write': 'Создание и изменение задач',
+  'settings:read': 'Просмотр настроек',
+  'settings:write': 'Изменение настроек'
+};
+
+async function errorMessage(response: Response, fallback: string): Promise<string> {
+  const text = await response.text().catch(() => '');
+  return text || fallback;
+}
+
+export function useOAuth() {
+  // Параметры запроса авторизации передаются на сервер как есть
+  const fetchAuthorizeInfo = async (params: Record<string, string>): Promise<AuthorizeInfo> => {
+    const query = new URLSearchParams(params).toString();
+    const response = await api.get(`${API_BASE}/auth/oauth/authorize?${query}`);
+
+    if (!response.ok) {
+      throw new Error(await errorMessage(response, 'Неверный запрос авторизации'));
+    }
+
+    return response.json();
+  };
+
+  // Возвращает адрес приложения, куда нужно отправить браузер
+  const decideAuthorize = async (params: Record<string, string>, approve: boolean): Promise<string> => {
+    const response = await api.post(`${API_BASE}/auth/oauth/authorize`, { ...params, approve });
+
+    if (!response.ok) {
+      throw new Error(await errorMessage(response, 'Не удалось выдать доступ'));
+    }
+
+    const data = await response.json();
+    return data.redirect_url;
+  };
+
+  const fetchDeviceInfo = async (userCode: string): Promise<DeviceInfo> => {
+    const query = new URLSearchParams({ user_code: userCode }).toString();
+    const response = await api.get(`${API_BASE}/auth/oauth/device?${query}`);
+
+    if (response.status === 404) {
+      throw new Error('Код не найден или истек');
+    }
+    if (!response.ok) {
+      throw new Error(await errorMessage(response, 'Не удалось проверить код'));
+    }
+
+    return response.json();
+  };
+
+  const decideDevice = async (userCode: string, approve: boolean): Promise<void> => {
+    const response = await api.post(`${API_BASE}/auth/oauth/device`, { user_code: userCode, approve });
+
+    if (response.status === 404) {
+      throw new Error('Код не найден или истек');
+    }
+    if (!response.ok) {
+      throw new Error(await errorMessage(response, 'Не удалось подтвердить вход'));
+    }
+  };
+
+  const fetchConsents = async (): Promise<OAuthConsent[]> => {
+    const response = await api.get(`${API_BASE}/auth/oauth/consents`);
+
+    if (!response.ok) {
+      throw new Error('Не удалось загрузить список приложений');
+    }
+
+    return response.json();
+  };
+
+  const revokeConsent = async (clientId: string): Promise<void> => {
+    const response = await api.delete(`${API_BASE}/auth/oauth/consents/${clientId}`);
+
+    if (!response.ok && response.status !== 404) {
+      throw new Error('Не удалось отозвать доступ');
+    }
+  };
+
+  return {
+    fetchAuthorizeInfo,
+    decideAuthorize,
+    fetchDeviceInfo,
+    decideDevice,
+    fetchConsents,
+    revokeConsent
+  };
+}
@@ -0,0 +1,56 @@
+import { api } from '../utils/api';
+
+const API_BASE = '/api';
+
+export interface Organization {
+  id: string;
+  name: string;
+  role: 'owner' | 'admin' | 'member';
+  created_at: string;
+}
+
+export interface InvitePreview {
+  organization_id: string;
+  organization_name: string;
+  email: string;
+  role: 'admin' | 'member';
+  expires_at: string;
+}
+
+export const ORG_ROLE_LABELS: Record<string, string> = {
+  owner: 'Владелец',
+  admin: 'Администратор',
+  member: 'Участник'
+};
+
+async function errorMessage(response: Response, fallback: string): Promise<string> {
+  const text = await response.text().catch(() => '');
+  return text || fallback;
+}
+
+export function useOrganizations() {
+  const fetchInvite = async (token: string): Promise<InvitePreview> => {
+    const response = await api.get(`${API_BASE}/invites?token=${encodeURIComponent(token)}`);
+
+    if (!response.ok) {
+      throw new Error(await errorMessage(response, 'Приглашение не найдено или истекло'));
+    }
+
+    return response.json();
+  };
+
+  const acceptInvite = async (token: string): Promise<Organization> => {
+    const response = await api.post(`${API_BASE}/invites/accept`, { token });
+
+    if (!response.ok) {
+      throw new Error(await errorMessage(response, 'Не удалось принять приглашение'));
+    }
+
+    return response.json();
+  };
+
+  return {
+    fetchInvite,
+    acceptInvite
+  };
+}
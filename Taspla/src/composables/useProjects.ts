@@ -0,0 +1,108 @@
+import { computed, ref } from 'vue';
+import { api } from '../utils/api';
+
+const API_BASE = '/api';
+
+export interface Project {
+  id: string;
+  name: string;
+  color: string;
+  position: number;
+  archived_at: string | null;
+}
+
+// Что сделать с задачами удаляемого проекта
+export type ProjectTasksAction = 'move' | 'delete';
+
+export const PROJECT_COLORS = ['#7C3AED', '#3B82F6', '#10B981', '#F59E0B', '#EF4444', '#EC4899', '#6B7280'];
+
+const projects = ref<Project[]>([]);
+const loading = ref(false);
+const error = ref<string | null>(null);
+
+// В задачи можно класть только активные проекты
+const activeProjects = computed(() => projects.value.filter(p => !p.archived_at));
+
+// Загружаем и архивные: порядок сервер принимает только для полного списка
+const fetchProjects = async () => {
+  loading.value = true;
+  error.value = null;
+
+  try {
+    const response = await api.get(`${API_BASE}/projects?include_archived=true`);
+
+    if (!response.ok) {
+      throw new Error('Ошибка загрузки проектов');
+    }
+
+    projects.value = await response.json();
+  } catch (e: any) {
+    error.value = e.message;
+    console.error('Error fetching projects:', e);
+  } finally {
+    loading.value = false;
+  }
+};
+
+const createProject = async (name: string, color: string) => {
+  const response = await api.post(`${API_BASE}/projects`, { name, color });
+  if (!response.ok) {
+    throw new Error('Ошибка создания проекта');
+  }
+
+  projects.value.push(await response.json());
+};
+
+const updateProject = async (id: string, data: { name?: string; color?: string; archived?: boolean }) => {
+  const response = await api.put(`${API_BASE}/projects/${id}`, data);
+  if (!response.ok) {
+    throw new Error('Ошибка обновления проекта');
+  }
+
+  const updated: Project = await response.json();
+  const index = projects.value.findIndex(p => p.id === id);
+  if (index !== -1) {
+    projects.value[index] = updated;
+  }
+};
+
+// Сдвигает проект на одну позицию вверх (-1) или вниз (+1)
+const moveProject = async (id: string, offset: -1 | 1) => {
+  const ids = projects.value.map(p => p.id);
+  const from = ids.indexOf(id);
+  const to = from + offset;
+  if (from === -1 || to < 0 || to >= ids.length) {
+    return;
+  }
+  [ids[from], ids[to]] = [ids[to], ids[from]];
+
+  const response = await api.put(`${API_BASE}/projects/order`, { project_ids: ids });
+  if (!response.ok) {
+    throw new Error('Ошибка изменения порядка');
+  }
+
+  projects.value = await response.json();
+};
+
+const deleteProject = async (id: string, tasks: ProjectTasksAction) => {
+  const response = await api.delete(`${API_BASE}/projects/${id}?tasks=${tasks}`);
+  if (!response.ok) {
+    throw new Error('Ошибка удаления проекта');
+  }
+
+  projects.value = projects.value.filter(p => p.id !== id);
+};
+
+export function useProjects() {
+  return {
+    projects,
+    activeProjects,
+    loading,
+    error,
+    fetchProjects,
+    createProject,
+    updateProject,
+    moveProject,
+    deleteProject
+  };
+}
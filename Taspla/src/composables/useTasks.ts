@@ -2,6 +2,7 @@ import { ref, computed } from 'vue';
 import { api } from '../utils/api';
 import type { Task, CreateTaskData } from '../types/task';
 import { TaskStatus, Priority } from '../types/task';
+import { useLabels } from './useLabels';
 
 const API_BASE = '/api';
 
@@ -16,7 +17,12 @@ const transformTask = (backendTask: any): Task => ({
   reminderHours: backendTask.reminder_hours,
   status: backendTask.status,
   createdAt: backendTask.created_at,
-  completedAt: backendTask.completed_at
+  completedAt: backendTask.completed_at,
+  seriesId: backendTask.series_id ?? undefined,
+  recurrenceRule: backendTask.recurrence_rule ?? undefined,
+  recurrenceFromCompletion: backendTask.recurrence_from_completion ?? false,
+  projectId: backendTask.project_id ?? undefined,
+  labels: backendTask.labels ?? []
 });
 const tasks = ref<Task[]>([]);
 const loading = ref(false);
@@ -24,6 +30,8 @@ const error = ref<string | null>(null);
 const initialized = ref(false);
 
 export function useTasks() {
+  const { attachLabels, detachLabel } = useLabels();
+
   // Загрузка задач с сервера
   const fetchTasks = async () => {
     loading.value = true;
@@ -140,6 +148,32 @@ export function useTasks() {
     tasks.value.filter(task => task.status === TaskStatus.Completed)
   );
 
+  // Приводит метки задачи к списку labelIds: добавляет недостающие, снимает лишние
+  const setTaskLabels = async (taskId: string, labelIds: string[]): Promise<void> => {
+    const task = tasks.value.find(t => t.id === taskId);
+    const current = task?.labels.map(l => l.id) ?? [];
+    const added = labelIds.filter(id => !current.includes(id));
+    const removed = current.filter(id => !labelIds.includes(id));
+
+    if (added.length > 0) {
+      await attachLabels(taskId, added);
+    }
+    for (const labelId of removed) {
+      await detachLabel(taskId, labelId);
+    }
+    if (added.length === 0 && removed.length === 0) {
+      return;
+    }
+
+    const response = await api.get(`${API_BASE}/tasks/${taskId}`);
+    if (response.ok) {
+      const index = tasks.value.findIndex(t => t.id === taskId);
+      if (index !== -1) {
+        tasks.value[index] = transformTask(await response.json());
+      }
+    }
+  };
+
   const createTask = async (data: CreateTaskData): Promise<void> => {
     try {
       const payload = {
@@ -148,7 +182,10 @@ export function useTasks() {
         priority: data.priority,
         due_date: data.dueDate,
         reminder_days: data.reminderDays || null,
-        reminder_hours: data.reminderHours || null
+        reminder_hours: data.reminderHours || null,
+        recurrence_rule: data.recurrenceRule || null,
+        recurrence_from_completion: data.recurrenceFromCompletion ?? false,
+        project_id: data.projectId || null
       };
       
       console.log('Creating task with payload:', payload);
@@ -163,6 +200,9 @@ export function useTasks() {
       const newTask = await response.json();
       console.log('Task created:', newTask);
       tasks.value.unshift(transformTask(newTask));
+      if (data.labelIds?.length) {
+        await setTaskLabels(newTask.id, data.labelIds);
+      }
     } catch (e: any) {
       error.value = e.message;
       console.error('Error creating task:', e);
@@ -183,6 +223,10 @@ export function useTasks() {
       if (index !== -1) {
         tasks.value[index] = transformTask(updatedTask);
       }
+      // Для повторяющейся задачи сервер создал следующий экземпляр
+      if (updatedTask.series_id) {
+        await fetchTasks();
+      }
     } catch (e: any) {
       error.value = e.message;
       console.error('Error completing task:', e);
@@ -190,14 +234,37 @@ export function useTasks() {
     }
   };
 
-  const deleteTask = async (taskId: string): Promise<void> => {
+  // Пропуск одного экземпляра повторяющейся задачи
+  const skipTask = async (taskId: string): Promise<void> => {
     try {
-      const response = await api.delete(`${API_BASE}/tasks/${taskId}`);
+      const response = await api.patch(`${API_BASE}/tasks/${taskId}/skip`);
+      
+      if (!response.ok) {
+        throw new Error('Ошибка пропуска задачи');
+      }
+      
+      await fetchTasks();
+    } catch (e: any) {
+      error.value = e.message;
+      console.error('Error skipping task:', e);
+      throw e;
+    }
+  };
+
+  // scope = 'future' удаляет и все следующие экземпляры повторяющейся задачи
+  const deleteTask = async (taskId: string, scope: 'this' | 'future' = 'this'): Promise<void> => {
+    try {
+      const task = tasks.value.find(t => t.id === taskId);
+      const response = await api.delete(`${API_BASE}/tasks/${taskId}?scope=${scope}`);
       
       if (!response.ok) {
         throw new Error('Ошибка удаления задачи');
       }
       
+      if (task?.seriesId && task.status === TaskStatus.Active && scope === 'this') {
+        await fetchTasks();
+        return;
+      }
       const index = tasks.value.findIndex(t => t.id === taskId);
       if (index !== -1) {
         tasks.value.splice(index, 1);
@@ -229,19 +296,28 @@ export function useTasks() {
     }
   };
 
-  const updateTask = async (taskId: string, data: CreateTaskData): Promise<void> => {
+  // scope = 'future' применяет изменения ко всем следующим экземплярам повторяющейся задачи
+  const updateTask = async (taskId: string, data: CreateTaskData, scope: 'this' | 'future' = 'this'): Promise<void> => {
     try {
-      const payload = {
+      const current = tasks.value.find(t => t.id === taskId);
+      const payload: Record<string, unknown> = {
         title: data.title,
         description: data.description,
         priority: data.priority,
         due_date: data.dueDate,
         reminder_days: data.reminderDays || null,
-        reminder_hours: data.reminderHours || null
+        reminder_hours: data.reminderHours || null,
+        // null переносит задачу во «Входящие»
+        project_id: data.projectId || null
       };
+      // Правило повторения экземпляра меняется только вместе с серией
+      if (!current?.seriesId || scope === 'future') {
+        payload.recurrence_rule = data.recurrenceRule ?? '';
+        payload.recurrence_from_completion = data.recurrenceFromCompletion ?? false;
+      }
       
       console.log('Updating task with payload:', payload);
-      const response = await api.put(`${API_BASE}/tasks/${taskId}`, payload);
+      const response = await api.put(`${API_BASE}/tasks/${taskId}?scope=${scope}`, payload);
       
       if (!response.ok) {
         const errorData = await response.json().catch(() => ({}));
@@ -255,6 +331,9 @@ export function useTasks() {
       if (index !== -1) {
         tasks.value[index] = transformTask(updatedTask);
       }
+      if (data.labelIds) {
+        await setTaskLabels(taskId, data.labelIds);
+      }
     } catch (e: any) {
       error.value = e.message;
       console.error('Error updating task:', e);
@@ -272,6 +351,8 @@ export function useTasks() {
     createTask,
     completeTask,
     deleteTask,
+    skipTask,
+    setTaskLabels,
     restoreTask,
     updateTask
   };
@@ -3,9 +3,19 @@ import { useAuth } from '../composables/useAuth';
 
 import Login from '../views/Login.vue';
 import Register from '../views/Register.vue';
+import SsoCallback from '../views/SsoCallback.vue';
+import MagicLink from '../views/MagicLink.vue';
+import VerifyEmail from '../views/VerifyEmail.vue';
+import ResetPassword from '../views/ResetPassword.vue';
+import ConfirmEmailChange from '../views/ConfirmEmailChange.vue';
+import OAuthAuthorize from '../views/OAuthAuthorize.vue';
+import Device from '../views/Device.vue';
+import InviteAccept from '../views/InviteAccept.vue';
 import Home from '../views/Home.vue';
 import Profile from '../views/Profile.vue';
 import Settings from '../views/Settings.vue';
+import Notifications from '../views/Notifications.vue';
+import Projects from '../views/Projects.vue';
 
 const routes: RouteRecordRaw[] = [
   {
@@ -20,6 +30,55 @@ const routes: RouteRecordRaw[] = [
     component: Register,
     meta: { requiresGuest: true }
   },
+  {
+    path: '/sso/callback',
+    name: 'sso-callback',
+    component: SsoCallback,
+    meta: { requiresGuest: true }
+  },
+  {
+    path: '/magic-link',
+    name: 'magic-link',
+    component: MagicLink,
+    meta: { requiresGuest: true }
+  },
+  {
+    // Без requiresGuest: сбросить пароль можно и из открытой сессии
+    path: '/reset-password',
+    name: 'reset-password',
+    component: ResetPassword
+  },
+  {
+    // Без requiresAuth/requiresGuest: ссылку из письма открывают и с входом, и без
+    path: '/verify-email',
+    name: 'verify-email',
+    component: VerifyEmail
+  },
+  {
+    // Сервер принимает токен только из сессии того же пользователя
+    path: '/confirm-email-change',
+    name: 'confirm-email-change',
+    component: ConfirmEmailChange,
+    meta: { requiresAuth: true }
+  },
+  {
+    path: '/oauth/authorize',
+    name: 'oauth-authorize',
+    component: OAuthAuthorize,
+    meta: { requiresAuth: true }
+  },
+  {
+    path: '/device',
+    name: 'device',
+    component: Device,
+    meta: { requiresAuth: true }
+  },
+  {
+    path: '/invites/accept',
+    name: 'invite-accept',
+    component: InviteAccept,
+    meta: { requiresAuth: true }
+  },
   {
     path: '/',
     name: 'home',
@@ -37,6 +96,18 @@ const routes: RouteRecordRaw[] = [
     name: 'settings',
     component: Settings,
     meta: { requiresAuth: true }
+  },
+  {
+    path: '/notifications',
+    name: 'notifications',
+    component: Notifications,
+    meta: { requiresAuth: true }
+  },
+  {
+    path: '/projects',
+    name: 'projects',
+    component: Projects,
+    meta: { requiresAuth: true }
   }
 ];
 
@@ -55,8 +126,9 @@ router.beforeEach(async (to, _from, next) => {
   }
 
   // Если роут требует авторизации и пользователь не авторизован
+  // После входа вернемся туда, куда шли (например, на экран согласия OAuth)
   if (to.meta.requiresAuth && !isAuthenticated.value) {
-    next('/login');
+    next(to.fullPath === '/' ? '/login' : { path: '/login', query: { redirect: to.fullPath } });
     return;
   }
 
@@ -7,7 +7,14 @@ export enum Priority {
 
 export enum TaskStatus {
   Active = 'active',
-  Completed = 'completed'
+  Completed = 'completed',
+  Skipped = 'skipped'
+}
+
+export interface TaskLabel {
+  id: string;
+  name: string;
+  color: string;
 }
 
 export interface Task {
@@ -21,6 +28,11 @@ export interface Task {
   status: TaskStatus;
   createdAt: string;
   completedAt?: string;
+  seriesId?: string;
+  recurrenceRule?: string;
+  recurrenceFromCompletion: boolean;
+  projectId?: string;
+  labels: TaskLabel[];
 }
 
 export interface CreateTaskData {
@@ -30,4 +42,8 @@ export interface CreateTaskData {
   dueDate: string;
   reminderDays?: number;
   reminderHours?: number;
+  recurrenceRule?: string;
+  recurrenceFromCompletion?: boolean;
+  projectId?: string;
+  labelIds?: string[];
 }
@@ -1,14 +1,70 @@
 // Утилита для выполнения API запросов с автоматическим добавлением токена
 
-import { getCookie } from './cookies';
+import { getCookie, setCookie, deleteCookie } from './cookies';
 
 const TOKEN_KEY = 'auth_token';
+const REFRESH_TOKEN_KEY = 'refresh_token';
+const REFRESH_TOKEN_DAYS = 30;
 
 interface FetchOptions extends RequestInit {
   skipAuth?: boolean;
 }
 
+export function saveTokens(token: string, refreshToken: string): void {
+  // Access-токен короткоживущий, но cookie держим столько же, сколько refresh
+  setCookie(TOKEN_KEY, token, REFRESH_TOKEN_DAYS);
+  setCookie(REFRESH_TOKEN_KEY, refreshToken, REFRESH_TOKEN_DAYS);
+}
+
+export function clearTokens(): void {
+  deleteCookie(TOKEN_KEY);
+  deleteCookie(REFRESH_TOKEN_KEY);
+}
+
+// Один общий запрос на обновление, даже если 401 пришел сразу нескольким запросам
+let refreshPromise: Promise<boolean> | null = null;
+
+export function refreshTokens(): Promise<boolean> {
+  if (!refreshPromise) {
+    refreshPromise = (async () => {
+      const refreshToken = getCookie(REFRESH_TOKEN_KEY);
+      if (!refreshToken) {
+        return false;
+      }
+
+      const response = await fetch('/api/auth/refresh', {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ refresh_token: refreshToken })
+      });
+
+      if (!response.ok) {
+        clearTokens();
+        return false;
+      }
+
+      const data = await response.json();
+      saveTokens(data.token, data.refresh_token);
+      return true;
+    })().finally(() => {
+      refreshPromise = null;
+    });
+  }
+  return refreshPromise;
+}
+
 export async function apiFetch(url: string, options: FetchOptions = {}): Promise<Response> {
+  const response = await doFetch(url, options);
+
+  // Access-токен истек — пробуем обновить его и повторить запрос один раз
+  if (response.status === 401 && !options.skipAuth && await refreshTokens()) {
+    return doFetch(url, options);
+  }
+
+  return response;
+}
+
+async function doFetch(url: string, options: FetchOptions): Promise<Response> {
   const { skipAuth, ...fetchOptions } = options;
   
   const headers = new Headers(fetchOptions.headers);
@@ -1,7 +1,7 @@
 use axum::{
     body::Body,
     extract::{Request, State},
-    http::{HeaderMap, StatusCode, Uri},
+    http::{HeaderMap, StatusCode},
     response::Response,
     Router,
     routing::any,
@@ -28,10 +28,10 @@ async fn proxy(
     tracing::info!(method = %method_str, path = %path, "Incoming request");
 
     // Роутинг: определяем куда идёт запрос
-    let target_url = if path.starts_with("/api/auth") || path.starts_with("/api/users") {
+    let target_url = if path.starts_with("/api/auth") || path.starts_with("/api/users") || path.starts_with("/api/admin/users") || path.starts_with("/api/admin/oauth") || path.starts_with("/api/admin/auth-events") || path.starts_with("/api/orgs") || path.starts_with("/api/invites") {
         let stripped = path.strip_prefix("/api").unwrap_or(&path);
         format!("{}{}{}", state.auth_service_url, stripped, query)
-    } else if path.starts_with("/api/tasks") || path.starts_with("/api/settings") {
+    } else if path.starts_with("/api/tasks") || path.starts_with("/api/settings") || path.starts_with("/api/admin/tasks") || path.starts_with("/api/notifications") || path.starts_with("/api/projects") || path.starts_with("/api/labels") {
         let stripped = path.strip_prefix("/api").unwrap_or(&path);
         format!("{}{}{}", state.tasks_service_url, stripped, query)
     } else {
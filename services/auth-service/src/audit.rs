@@ -0,0 +1,102 @@
+use chrono::{Duration, Utc};
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::session::ClientInfo;
+
+const DEFAULT_RETENTION_DAYS: i64 = 90;
+
+/// Тип события в журнале безопасности, хранится в `auth_events.event_type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventType {
+    Register,
+    LoginSucceeded,
+    LoginFailed,
+    PasswordChanged,
+    PasswordReset,
+    ProfileUpdated,
+    TokenRejected,
+    SessionRevoked,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 8] = [
+        EventType::Register,
+        EventType::LoginSucceeded,
+        EventType::LoginFailed,
+        EventType::PasswordChanged,
+        EventType::PasswordReset,
+        EventType::ProfileUpdated,
+        EventType::TokenRejected,
+        EventType::SessionRevoked,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Register => "register",
+            Self::LoginSucceeded => "login_succeeded",
+            Self::LoginFailed => "login_failed",
+            Self::PasswordChanged => "password_changed",
+            Self::PasswordReset => "password_reset",
+            Self::ProfileUpdated => "profile_updated",
+            Self::TokenRejected => "token_rejected",
+            Self::SessionRevoked => "session_revoked",
+        }
+    }
+
+    pub fn parse(event_type: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|e| e.as_str() == event_type)
+    }
+}
+
+/// Пишет событие в журнал. Ошибка записи только логируется: из-за журнала
+/// пользователь не должен получить отказ во входе или смене пароля.
+pub async fn record(
+    pool: &PgPool,
+    event_type: EventType,
+    user_id: Option<Uuid>,
+    client: &ClientInfo,
+    details: Value,
+) {
+    let result = sqlx::query(
+        "INSERT INTO auth_events (id, user_id, event_type, ip_address, user_agent, details, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(event_type.as_str())
+    .bind(&client.ip_address)
+    .bind(&client.user_agent)
+    .bind(details)
+    .bind(Utc::now())
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        tracing::error!(error = %e, event_type = event_type.as_str(), user_id = ?user_id, "Failed to record auth event");
+    }
+}
+
+/// Раз в сутки удаляет события старше `AUTH_EVENTS_RETENTION_DAYS` (по умолчанию 90 дней)
+pub fn spawn_purge(pool: PgPool) {
+    let retention_days = std::env::var("AUTH_EVENTS_RETENTION_DAYS")
+        .map(|days| days.parse::<i64>().expect("AUTH_EVENTS_RETENTION_DAYS must be a number"))
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
+    let retention = Duration::days(retention_days);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(24 * 3600));
+        loop {
+            interval.tick().await;
+            match sqlx::query("DELETE FROM auth_events WHERE created_at < $1")
+                .bind(Utc::now() - retention)
+                .execute(&pool)
+                .await
+            {
+                Ok(result) => tracing::info!(purged = result.rows_affected(), retention_days, "Old auth events purged"),
+                Err(e) => tracing::error!(error = %e, "Failed to purge auth events"),
+            }
+        }
+    });
+}
@@ -0,0 +1,208 @@
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::throttle;
+
+/// Канал LISTEN/NOTIFY, будящий потребителей outbox в других сервисах
+const OUTBOX_CHANNEL: &str = "outbox";
+/// Событие outbox: аккаунт удален, сервисы должны стереть его данные.
+/// У `outbox_events` один `processed_at`, поэтому у топика может быть только один
+/// потребитель (сейчас — tasks-service); второму сервису понадобится своя отметка обработки.
+pub const ACCOUNT_DELETED_TOPIC: &str = "account_deleted";
+const WORKER_INTERVAL: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// Сколько дней аккаунт ждет удаления (ACCOUNT_DELETION_GRACE_DAYS, по умолчанию 30)
+pub fn grace_period_days() -> i64 {
+    std::env::var("ACCOUNT_DELETION_GRACE_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(30)
+}
+
+/// Периодически удаляет аккаунты, у которых истек срок ожидания
+pub fn spawn_worker(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(WORKER_INTERVAL);
+        loop {
+            interval.tick().await;
+            match erase_due_accounts(&pool).await {
+                Ok(0) => {}
+                Ok(erased) => tracing::info!(erased, "Scheduled account deletions completed"),
+                Err(e) => tracing::error!(error = %e, "Failed to erase scheduled accounts"),
+            }
+        }
+    });
+}
+
+async fn erase_due_accounts(pool: &PgPool) -> Result<usize, sqlx::Error> {
+    let mut erased = 0;
+
+    loop {
+        let mut tx = pool.begin().await?;
+
+        // SKIP LOCKED — несколько реплик auth-service не удалят один аккаунт дважды
+        let user = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT id, email FROM users
+             WHERE deletion_scheduled_at <= $1
+             ORDER BY deletion_scheduled_at
+             LIMIT 1
+             FOR UPDATE SKIP LOCKED"
+        )
+        .bind(Utc::now())
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((user_id, email)) = user else {
+            return Ok(erased);
+        };
+
+        erase_account(&mut tx, user_id, &email).await?;
+        tx.commit().await?;
+
+        tracing::info!(user_id = %user_id, event = "account_erased", "Account erased");
+        erased += 1;
+    }
+}
+
+/// Стирает данные auth-service и в той же транзакции публикует событие для остальных
+/// сервисов: либо исчезнет аккаунт и появится событие, либо ничего.
+async fn erase_account(conn: &mut PgConnection, user_id: Uuid, email: &str) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+
+    sqlx::query(
+        "INSERT INTO outbox_events (id, topic, payload, created_at)
+         VALUES ($1, $2, $3, $4)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(ACCOUNT_DELETED_TOPIC)
+    .bind(serde_json::json!({ "user_id": user_id }))
+    .bind(now)
+    .execute(&mut *conn)
+    .await?;
+
+    let mut erased = serde_json::Map::new();
+    let mut count = |table: &str, rows: u64| {
+        let total = erased.get(table).and_then(|v| v.as_u64()).unwrap_or(0) + rows;
+        erased.insert(table.to_string(), total.into());
+    };
+
+    count("organization_members", hand_over_organizations(conn, user_id).await?);
+
+    let invites = sqlx::query("DELETE FROM organization_invites WHERE lower(email) = lower($1)")
+        .bind(email)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+    count("organization_invites", invites);
+
+    // Всё, что удалилось бы каскадом вместе с пользователем, удаляем явно, чтобы
+    // записать объемы; список берется из внешних ключей и не устаревает с новыми таблицами
+    for (table, column) in cascading_references(conn).await? {
+        let rows = sqlx::query(&format!("DELETE FROM {table} WHERE {column} = $1"))
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+        count(&table, rows);
+    }
+
+    let users = sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+    count("users", users);
+
+    count("login_throttle", throttle::clear(conn, &throttle::account_key(email)).await?);
+
+    log_erasure(conn, user_id, "auth-service", serde_json::Value::Object(erased)).await?;
+
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(OUTBOX_CHANNEL)
+        .bind(ACCOUNT_DELETED_TOPIC)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Таблицы и колонки, ссылающиеся на `users` с `ON DELETE CASCADE`.
+/// Имена приходят из каталога уже в экранированном виде.
+async fn cascading_references(conn: &mut PgConnection) -> Result<Vec<(String, String)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, String)>(
+        "SELECT c.conrelid::regclass::text, quote_ident(a.attname)
+         FROM pg_constraint c
+         JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = c.conkey[1]
+         WHERE c.contype = 'f'
+           AND c.confrelid = 'users'::regclass
+           AND c.confdeltype = 'c'
+           AND cardinality(c.conkey) = 1
+         ORDER BY 1, 2"
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// Организации удаляемого владельца переходят к старейшему администратору, а если
+/// администраторов нет — к старейшему участнику. Организация без участников удаляется.
+/// Возвращает, сколько записей о владении удалено.
+async fn hand_over_organizations(conn: &mut PgConnection, user_id: Uuid) -> Result<u64, sqlx::Error> {
+    let owned = sqlx::query_scalar::<_, Uuid>(
+        "DELETE FROM organization_members WHERE user_id = $1 AND role = 'owner' RETURNING organization_id"
+    )
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if owned.is_empty() {
+        return Ok(0);
+    }
+
+    sqlx::query(
+        "UPDATE organization_members SET role = 'owner'
+         WHERE (organization_id, user_id) IN (
+            SELECT DISTINCT ON (organization_id) organization_id, user_id
+            FROM organization_members
+            WHERE organization_id = ANY($1) AND user_id <> $2
+            ORDER BY organization_id, role = 'admin' DESC, joined_at
+         )"
+    )
+    .bind(&owned)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM organizations o WHERE o.id = ANY($1)
+         AND NOT EXISTS (SELECT 1 FROM organization_members m WHERE m.organization_id = o.id)"
+    )
+    .bind(&owned)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(owned.len() as u64)
+}
+
+/// Запись для GDPR-отчетности: когда и какие данные пользователя стерты. Email и другие
+/// персональные данные сюда не пишутся, только id.
+async fn log_erasure(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    service: &str,
+    details: serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO data_erasure_log (id, user_id, service, details, erased_at)
+         VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(service)
+    .bind(details)
+    .bind(Utc::now())
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,286 @@
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::io::Write;
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::internal;
+use crate::models::audit::AuthEvent;
+use crate::models::oidc::UserIdentity;
+use crate::models::organization::OrganizationResponse;
+use crate::models::session::Session;
+use crate::models::token::PersonalAccessToken;
+use crate::models::user::User;
+
+/// Сколько архив хранится после сборки
+pub const ARCHIVE_TTL_HOURS: i64 = 24;
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+/// Задание в статусе running дольше этого считается брошенным (рестарт сервиса)
+const STALE_JOB_MINUTES: i64 = 10;
+
+/// Фоновая сборка архивов и очистка истекших
+pub fn spawn_worker(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = run_pending(&pool).await {
+                tracing::error!(error = %e, "Data export worker failed");
+            }
+            if let Err(e) = expire_archives(&pool).await {
+                tracing::error!(error = %e, "Failed to expire data exports");
+            }
+        }
+    });
+}
+
+async fn run_pending(pool: &PgPool) -> Result<(), sqlx::Error> {
+    loop {
+        let now = Utc::now();
+        let job = sqlx::query_as::<_, (Uuid, Uuid)>(
+            "UPDATE data_exports SET status = 'running', started_at = $1
+             WHERE id = (
+                 SELECT id FROM data_exports
+                 WHERE status = 'pending' OR (status = 'running' AND started_at < $2)
+                 ORDER BY created_at
+                 LIMIT 1
+                 FOR UPDATE SKIP LOCKED
+             )
+             RETURNING id, user_id"
+        )
+        .bind(now)
+        .bind(now - Duration::minutes(STALE_JOB_MINUTES))
+        .fetch_optional(pool)
+        .await?;
+
+        let Some((job_id, user_id)) = job else {
+            return Ok(());
+        };
+
+        tracing::info!(job_id = %job_id, user_id = %user_id, "Building data export");
+
+        match build_archive(pool, user_id).await {
+            Ok(archive) => {
+                let now = Utc::now();
+                sqlx::query(
+                    "UPDATE data_exports SET status = 'ready', archive = $2, completed_at = $3, expires_at = $4
+                     WHERE id = $1"
+                )
+                .bind(job_id)
+                .bind(&archive)
+                .bind(now)
+                .bind(now + Duration::hours(ARCHIVE_TTL_HOURS))
+                .execute(pool)
+                .await?;
+
+                tracing::info!(job_id = %job_id, user_id = %user_id, bytes = archive.len(), "Data export ready");
+            }
+            Err(e) => {
+                tracing::error!(error = %e, job_id = %job_id, user_id = %user_id, "Data export failed");
+                sqlx::query(
+                    "UPDATE data_exports SET status = 'failed', error = $2, completed_at = $3 WHERE id = $1"
+                )
+                .bind(job_id)
+                .bind(&e)
+                .bind(Utc::now())
+                .execute(pool)
+                .await?;
+            }
+        }
+    }
+}
+
+async fn expire_archives(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE data_exports SET status = 'expired', archive = NULL
+         WHERE status = 'ready' AND expires_at <= $1"
+    )
+    .bind(Utc::now())
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() > 0 {
+        tracing::info!(expired = result.rows_affected(), "Data export archives expired");
+    }
+    Ok(())
+}
+
+/// Собирает zip: каждый набор данных в JSON, табличные — еще и в CSV
+async fn build_archive(pool: &PgPool, user_id: Uuid) -> Result<Vec<u8>, String> {
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let sessions = sqlx::query_as::<_, Session>(
+        "SELECT * FROM sessions WHERE user_id = $1 ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let tokens = sqlx::query_as::<_, PersonalAccessToken>(
+        "SELECT * FROM personal_access_tokens WHERE user_id = $1 ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let identities = sqlx::query_as::<_, UserIdentity>(
+        "SELECT * FROM user_identities WHERE user_id = $1 ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let events = sqlx::query_as::<_, AuthEvent>(
+        "SELECT * FROM auth_events WHERE user_id = $1 ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let organizations = sqlx::query_as::<_, OrganizationResponse>(
+        "SELECT o.id, o.name, m.role, m.joined_at AS created_at FROM organizations o
+         JOIN organization_members m ON m.organization_id = o.id
+         WHERE m.user_id = $1
+         ORDER BY m.joined_at"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let tasks_data = internal::fetch_tasks_data(user_id).await?;
+
+    // Хэши паролей и токенов, секрет TOTP в выгрузку не попадают
+    let profile = json!({
+        "id": user.id,
+        "email": user.email,
+        "username": user.username,
+        "created_at": user.created_at,
+        "email_verified_at": user.email_verified_at,
+        "two_factor_enabled_at": user.totp_enabled_at,
+        "deletion_scheduled_at": user.deletion_scheduled_at,
+    });
+
+    let sessions: Vec<Value> = sessions.into_iter().map(|session| json!({
+        "id": session.id,
+        "user_agent": session.user_agent,
+        "ip_address": session.ip_address,
+        "created_at": session.created_at,
+        "last_seen_at": session.last_seen_at,
+        "expires_at": session.expires_at,
+        "revoked_at": session.revoked_at,
+    })).collect();
+
+    let tokens: Vec<Value> = tokens.into_iter().map(|token| json!({
+        "id": token.id,
+        "name": token.name,
+        "scopes": token.scopes.join(" "),
+        "created_at": token.created_at,
+        "expires_at": token.expires_at,
+        "last_used_at": token.last_used_at,
+        "revoked_at": token.revoked_at,
+    })).collect();
+
+    let identities: Vec<Value> = identities.into_iter().map(|identity| json!({
+        "id": identity.id,
+        "provider": identity.provider,
+        "subject": identity.subject,
+        "email": identity.email,
+        "created_at": identity.created_at,
+        "last_login_at": identity.last_login_at,
+    })).collect();
+
+    let events: Vec<Value> = events.into_iter().map(|event| json!({
+        "id": event.id,
+        "event_type": event.event_type,
+        "ip_address": event.ip_address,
+        "user_agent": event.user_agent,
+        "details": event.details,
+        "created_at": event.created_at,
+    })).collect();
+
+    let organizations: Vec<Value> = organizations.into_iter().map(|organization| json!({
+        "id": organization.id,
+        "name": organization.name,
+        "role": organization.role,
+        "joined_at": organization.created_at,
+    })).collect();
+
+    let tasks = tasks_data["tasks"].as_array().cloned().unwrap_or_default();
+    let projects = tasks_data["projects"].as_array().cloned().unwrap_or_default();
+    let labels = tasks_data["labels"].as_array().cloned().unwrap_or_default();
+    let settings = tasks_data["settings"].clone();
+    let notifications = tasks_data["notifications"].as_array().cloned().unwrap_or_default();
+
+    let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let mut add = |name: &str, content: String| -> Result<(), String> {
+        zip.start_file(name, SimpleFileOptions::default()).map_err(|e| e.to_string())?;
+        zip.write_all(content.as_bytes()).map_err(|e| e.to_string())
+    };
+
+    let pretty = |value: &Value| serde_json::to_string_pretty(value).unwrap_or_default();
+
+    add("profile.json", pretty(&profile))?;
+    add("settings.json", pretty(&settings))?;
+    add("sessions.json", pretty(&json!(sessions)))?;
+    add("sessions.csv", to_csv(&sessions))?;
+    add("personal_access_tokens.json", pretty(&json!(tokens)))?;
+    add("personal_access_tokens.csv", to_csv(&tokens))?;
+    add("external_identities.json", pretty(&json!(identities)))?;
+    add("external_identities.csv", to_csv(&identities))?;
+    add("security_events.json", pretty(&json!(events)))?;
+    add("security_events.csv", to_csv(&events))?;
+    add("organizations.json", pretty(&json!(organizations)))?;
+    add("organizations.csv", to_csv(&organizations))?;
+    add("tasks.json", pretty(&json!(tasks)))?;
+    add("tasks.csv", to_csv(&tasks))?;
+    add("projects.json", pretty(&json!(projects)))?;
+    add("projects.csv", to_csv(&projects))?;
+    add("labels.json", pretty(&json!(labels)))?;
+    add("labels.csv", to_csv(&labels))?;
+    add("notifications.json", pretty(&json!(notifications)))?;
+    add("notifications.csv", to_csv(&notifications))?;
+
+    let cursor = zip.finish().map_err(|e| e.to_string())?;
+    Ok(cursor.into_inner())
+}
+
+/// CSV из массива плоских объектов; колонки берутся из первой строки
+fn to_csv(rows: &[Value]) -> String {
+    let Some(Value::Object(first)) = rows.first() else {
+        return String::new();
+    };
+    let columns: Vec<&String> = first.keys().collect();
+
+    let mut csv = columns.iter().map(|c| escape_csv(c)).collect::<Vec<_>>().join(",");
+    csv.push_str("\r\n");
+
+    for row in rows {
+        let line = columns.iter().map(|column| match &row[column.as_str()] {
+            Value::Null => String::new(),
+            Value::String(s) => escape_csv(s),
+            other => escape_csv(&other.to_string()),
+        }).collect::<Vec<_>>().join(",");
+        csv.push_str(&line);
+        csv.push_str("\r\n");
+    }
+
+    csv
+}
+
+fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
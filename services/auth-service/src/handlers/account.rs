@@ -0,0 +1,112 @@
+use axum::{extract::State, http::{StatusCode, HeaderMap}, Json};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::audit::{self, EventType};
+use crate::deletion;
+use crate::handlers::auth::authenticate;
+use crate::handlers::mfa::verify_second_factor;
+use crate::mail::{self, Email, MailSender};
+use crate::models::session::ClientInfo;
+use crate::models::user::{DeleteAccountRequest, DeleteAccountResponse, User};
+use crate::passwords;
+use crate::revocation;
+
+#[utoipa::path(
+    delete,
+    path = "/auth/account",
+    request_body = DeleteAccountRequest,
+    responses(
+        (status = 202, description = "Удаление запланировано, до указанного срока его отменяет вход в аккаунт", body = DeleteAccountResponse),
+        (status = 401, description = "Не авторизован, неверный пароль или код 2FA"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn delete_account(
+    State(pool): State<PgPool>,
+    State(mailer): State<Arc<dyn MailSender>>,
+    headers: HeaderMap,
+    Json(req): Json<DeleteAccountRequest>,
+) -> Result<(StatusCode, Json<DeleteAccountResponse>), (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    tracing::info!(user_id = %user_id, "Account deletion requested");
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching user");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    let valid = passwords::verify(&req.password, &user.password_hash)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .valid;
+
+    if !valid {
+        tracing::warn!(user_id = %user_id, "Account deletion failed: invalid password");
+        return Err((StatusCode::UNAUTHORIZED, "Password is incorrect".to_string()));
+    }
+
+    if user.totp_enabled_at.is_some() {
+        let code = req.totp_code.as_deref().ok_or_else(|| {
+            tracing::warn!(user_id = %user_id, "Account deletion failed: two-factor code required");
+            (StatusCode::UNAUTHORIZED, "Two-factor code required".to_string())
+        })?;
+
+        if !verify_second_factor(&pool, &user, code).await? {
+            tracing::warn!(user_id = %user_id, "Account deletion failed: invalid two-factor code");
+            return Err((StatusCode::UNAUTHORIZED, "Invalid two-factor code".to_string()));
+        }
+    }
+
+    let scheduled_at = Utc::now() + Duration::days(deletion::grace_period_days());
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    sqlx::query("UPDATE users SET deletion_scheduled_at = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(scheduled_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error scheduling account deletion");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    // Выходим на всех устройствах; повторный вход до срока отменит удаление
+    let revoked = revocation::revoke_user_sessions(&mut tx, user_id, None)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error revoking sessions");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    mail::send_in_background(mailer, Email {
+        to: user.email,
+        subject: "Удаление аккаунта Taspla".to_string(),
+        body: format!(
+            "Здравствуйте, {}!\n\nАккаунт и все ваши задачи будут удалены {}.\n\n\
+             Если вы передумали, просто войдите в аккаунт до этой даты — удаление отменится.",
+            user.username,
+            scheduled_at.format("%d.%m.%Y"),
+        ),
+    });
+
+    audit::record(&pool, EventType::SessionRevoked, Some(user_id), &ClientInfo::from_headers(&headers), serde_json::json!({
+        "count": revoked,
+        "reason": "account_deletion",
+    })).await;
+
+    tracing::info!(user_id = %user_id, revoked, scheduled_at = %scheduled_at, "Account deletion scheduled");
+    Ok((StatusCode::ACCEPTED, Json(DeleteAccountResponse { deletion_scheduled_at: scheduled_at })))
+}
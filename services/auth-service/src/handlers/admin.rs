@@ -0,0 +1,334 @@
+use axum::{extract::{Path, Query, State}, http::{StatusCode, HeaderMap}, Json};
+use chrono::Utc;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::audit::{self, EventType};
+use crate::handlers::auth::{authenticate, Claims};
+use crate::handlers::password::send_reset_link;
+use crate::mail::MailSender;
+use crate::models::admin::{AdminUserResponse, UpdateRoleRequest, UserListResponse, UserSearchQuery};
+use crate::models::session::ClientInfo;
+use crate::models::user::User;
+use crate::revocation;
+use crate::roles::{Permission, Role};
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Проверяет токен и право на действие в админке
+pub async fn authorize(
+    pool: &PgPool,
+    headers: &HeaderMap,
+    permission: Permission,
+) -> Result<Claims, (StatusCode, String)> {
+    let claims = authenticate(pool, headers).await?;
+
+    claims.role().require(permission).inspect_err(|_| {
+        tracing::warn!(
+            user_id = %claims.sub,
+            role = %claims.role,
+            permission = ?permission,
+            event = "admin_access_denied",
+            "Admin action rejected: insufficient permissions"
+        );
+    })?;
+
+    Ok(claims)
+}
+
+async fn fetch_user(pool: &PgPool, id: Uuid) -> Result<User, (StatusCode, String)> {
+    sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error fetching user");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/users",
+    params(UserSearchQuery),
+    responses(
+        (status = 200, description = "Пользователи, по email или имени", body = UserListResponse),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Недостаточно прав"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "admin"
+)]
+pub async fn list_users(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Query(query): Query<UserSearchQuery>,
+) -> Result<Json<UserListResponse>, (StatusCode, String)> {
+    authorize(&pool, &headers, Permission::ViewUsers).await?;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    // % и _ в запросе ищутся буквально
+    let pattern = query.q
+        .map(|q| q.trim().replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"))
+        .filter(|q| !q.is_empty())
+        .map(|q| format!("%{}%", q));
+
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM users
+         WHERE $1::text IS NULL OR email ILIKE $1 OR username ILIKE $1"
+    )
+    .bind(&pattern)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error counting users");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    let users = sqlx::query_as::<_, User>(
+        "SELECT * FROM users
+         WHERE $1::text IS NULL OR email ILIKE $1 OR username ILIKE $1
+         ORDER BY created_at DESC
+         LIMIT $2 OFFSET $3"
+    )
+    .bind(&pattern)
+    .bind(per_page)
+    .bind((page - 1) * per_page)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error listing users");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(Json(UserListResponse {
+        items: users.into_iter().map(AdminUserResponse::from).collect(),
+        total,
+        page,
+        per_page,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/users/{id}",
+    params(("id" = Uuid, Path, description = "ID пользователя")),
+    responses(
+        (status = 200, description = "Пользователь", body = AdminUserResponse),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Недостаточно прав"),
+        (status = 404, description = "Пользователь не найден"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "admin"
+)]
+pub async fn get_user(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AdminUserResponse>, (StatusCode, String)> {
+    authorize(&pool, &headers, Permission::ViewUsers).await?;
+
+    Ok(Json(fetch_user(&pool, id).await?.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/disable",
+    params(("id" = Uuid, Path, description = "ID пользователя")),
+    responses(
+        (status = 200, description = "Аккаунт заблокирован, все сессии завершены", body = AdminUserResponse),
+        (status = 400, description = "Нельзя заблокировать самого себя"),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Недостаточно прав"),
+        (status = 404, description = "Пользователь не найден"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "admin"
+)]
+pub async fn disable_user(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AdminUserResponse>, (StatusCode, String)> {
+    let admin_id = authorize(&pool, &headers, Permission::DisableUsers).await?.user_id()?;
+
+    if admin_id == id {
+        return Err((StatusCode::BAD_REQUEST, "You cannot disable your own account".to_string()));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET disabled_at = COALESCE(disabled_at, $2) WHERE id = $1 RETURNING *"
+    )
+    .bind(id)
+    .bind(Utc::now())
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error disabling user");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    let revoked = revocation::revoke_user_sessions(&mut tx, id, None)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error revoking sessions");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    audit::record(&pool, EventType::SessionRevoked, Some(id), &ClientInfo::from_headers(&headers), serde_json::json!({
+        "count": revoked,
+        "reason": "user_disabled",
+        "admin_id": admin_id,
+    })).await;
+
+    tracing::warn!(admin_id = %admin_id, user_id = %id, revoked, event = "user_disabled", "User disabled by admin");
+    Ok(Json(user.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/enable",
+    params(("id" = Uuid, Path, description = "ID пользователя")),
+    responses(
+        (status = 200, description = "Аккаунт разблокирован", body = AdminUserResponse),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Недостаточно прав"),
+        (status = 404, description = "Пользователь не найден"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "admin"
+)]
+pub async fn enable_user(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AdminUserResponse>, (StatusCode, String)> {
+    let admin_id = authorize(&pool, &headers, Permission::DisableUsers).await?.user_id()?;
+
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET disabled_at = NULL WHERE id = $1 RETURNING *"
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error enabling user");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    tracing::warn!(admin_id = %admin_id, user_id = %id, event = "user_enabled", "User enabled by admin");
+    Ok(Json(user.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/password-reset",
+    params(("id" = Uuid, Path, description = "ID пользователя")),
+    responses(
+        (status = 202, description = "Пользователю отправлена ссылка для сброса пароля"),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Недостаточно прав"),
+        (status = 404, description = "Пользователь не найден"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "admin"
+)]
+pub async fn reset_user_password(
+    State(pool): State<PgPool>,
+    State(mailer): State<Arc<dyn MailSender>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    // Пароль поддержке не виден: пользователь сам задает новый по ссылке из письма
+    let admin_id = authorize(&pool, &headers, Permission::ResetPasswords).await?.user_id()?;
+
+    let user = fetch_user(&pool, id).await?;
+    send_reset_link(&pool, mailer, user).await?;
+
+    tracing::warn!(admin_id = %admin_id, user_id = %id, event = "admin_password_reset", "Password reset link sent by admin");
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/users/{id}/role",
+    params(("id" = Uuid, Path, description = "ID пользователя")),
+    request_body = UpdateRoleRequest,
+    responses(
+        (status = 200, description = "Роль изменена, пользователю нужно войти заново", body = AdminUserResponse),
+        (status = 400, description = "Неизвестная роль или попытка изменить свою роль"),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Недостаточно прав"),
+        (status = 404, description = "Пользователь не найден"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "admin"
+)]
+pub async fn update_role(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateRoleRequest>,
+) -> Result<Json<AdminUserResponse>, (StatusCode, String)> {
+    let admin_id = authorize(&pool, &headers, Permission::ManageRoles).await?.user_id()?;
+
+    let role = Role::parse(&req.role)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("Unknown role: {}", req.role)))?;
+
+    // Иначе последний администратор может случайно остаться без прав
+    if admin_id == id {
+        return Err((StatusCode::BAD_REQUEST, "You cannot change your own role".to_string()));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET role = $2 WHERE id = $1 RETURNING *"
+    )
+    .bind(id)
+    .bind(role.as_str())
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error updating role");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    // Роль зашита в access-токены, поэтому старые сессии завершаем
+    let revoked = revocation::revoke_user_sessions(&mut tx, id, None)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error revoking sessions");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    audit::record(&pool, EventType::SessionRevoked, Some(id), &ClientInfo::from_headers(&headers), serde_json::json!({
+        "count": revoked,
+        "reason": "role_changed",
+        "admin_id": admin_id,
+    })).await;
+
+    tracing::warn!(admin_id = %admin_id, user_id = %id, role = role.as_str(), revoked, event = "role_changed", "User role changed");
+    Ok(Json(user.into()))
+}
@@ -1,24 +1,79 @@
-use axum::{extract::State, http::{StatusCode, HeaderMap}, Json};
-use bcrypt::{hash, verify, DEFAULT_COST};
-use chrono::{Duration, Utc};
-use jsonwebtoken::{encode, decode, EncodingKey, DecodingKey, Header, Validation};
+use axum::{extract::State, http::{header, StatusCode, HeaderMap}, response::IntoResponse, Json};
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqlx::PgPool;
+use std::sync::{Arc, LazyLock};
 use uuid::Uuid;
-use utoipa::ToSchema;
-use crate::models::user::{AuthResponse, LoginRequest, RegisterRequest, User, UpdateProfileRequest, ChangePasswordRequest, UpdateProfileResponse};
+use crate::audit::{self, EventType};
+use crate::handlers::mfa::{start_mfa_challenge, verify_second_factor};
+use crate::handlers::users::{map_username_conflict, validate_username};
+use crate::handlers::verification::{issue_verification_token, queue_verification_email};
+use crate::mail::MailSender;
+use crate::keys;
+use crate::passwords;
+use crate::models::session::{ClientInfo, RefreshRequest, Session};
+use crate::revocation;
+use crate::roles::Role;
+use crate::throttle;
+use crate::models::user::{AuthResponse, LoginRequest, LoginResponse, RegisterRequest, User, UpdateProfileRequest, ChangePasswordRequest, UpdateProfileResponse};
+
+/// Access-токен живет недолго, сессию продлевает refresh-токен
+pub const ACCESS_TOKEN_TTL_MINUTES: i64 = 15;
+const REFRESH_TOKEN_TTL_DAYS: i64 = 30;
+/// Сколько ротированных refresh-токенов сессии помнить для обнаружения повторного использования
+const REFRESH_TOKEN_HISTORY: i32 = 50;
+
+/// Неудачный вход отвечает не быстрее этого. Иначе время выдает, есть ли аккаунт:
+/// bcrypt-хэши еще не перехэшированных аккаунтов проверяются заметно дольше argon2id
+const FAILED_LOGIN_MIN_DURATION: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Хэш, с которым сравнивается пароль, если пользователь не найден
+static DUMMY_PASSWORD_HASH: LazyLock<String> =
+    LazyLock::new(|| passwords::hash(&generate_secret()).expect("Failed to hash dummy password"));
 
 #[derive(Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,      // user_id
     pub username: String,
     pub email: String,    // email
+    pub email_verified: bool,
+    pub sid: String,      // id сессии
+    pub jti: String,      // id токена, по нему токен отзывается
+    pub role: String,     // user, support или admin
+    /// Активная организация сессии; нет — личное пространство
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<String>,
     pub exp: usize,       // когда истекает
 }
 
-#[derive(Serialize, ToSchema)]
-pub struct ErrorResponse {
-    pub message: String,
+impl Claims {
+    pub fn user_id(&self) -> Result<Uuid, (StatusCode, String)> {
+        self.sub.parse::<Uuid>()
+            .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID in token".to_string()))
+    }
+
+    pub fn session_id(&self) -> Result<Uuid, (StatusCode, String)> {
+        self.sid.parse::<Uuid>()
+            .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid session ID in token".to_string()))
+    }
+
+    pub fn token_id(&self) -> Result<Uuid, (StatusCode, String)> {
+        self.jti.parse::<Uuid>()
+            .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid token ID".to_string()))
+    }
+
+    pub fn role(&self) -> Role {
+        Role::parse(&self.role).unwrap_or(Role::User)
+    }
+
+    pub fn organization_id(&self) -> Result<Option<Uuid>, (StatusCode, String)> {
+        self.org_id.as_deref()
+            .map(|id| id.parse::<Uuid>())
+            .transpose()
+            .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid organization ID in token".to_string()))
+    }
 }
 
 #[utoipa::path(
@@ -27,16 +82,23 @@ pub struct ErrorResponse {
     request_body = RegisterRequest,
     responses(
         (status = 200, description = "Успешная регистрация", body = AuthResponse),
-        (status = 409, description = "Email уже занят"),
+        (status = 400, description = "Недопустимое имя пользователя или пароль не проходит политику"),
+        (status = 409, description = "Email или имя пользователя уже заняты"),
         (status = 500, description = "Внутренняя ошибка сервера"),
     ),
     tag = "auth"
 )]
 pub async fn register(
     State(pool): State<PgPool>,
+    State(mailer): State<Arc<dyn MailSender>>,
+    headers: HeaderMap,
     Json(req): Json<RegisterRequest>,
 ) -> Result<Json<AuthResponse>, (StatusCode, String)> {
     tracing::info!(email = %req.email, username = %req.username, "Attempting registration");
+
+    let username = req.username.trim();
+    validate_username(username)?;
+    passwords::check_policy(&req.password).await?;
     
     let existing = sqlx::query_scalar::<_, i64>(
         "SELECT COUNT(*) FROM users WHERE email = $1"
@@ -54,32 +116,38 @@ pub async fn register(
         return Err((StatusCode::CONFLICT, "Email already taken".to_string()));
     }
 
-    let password_hash = hash(&req.password, DEFAULT_COST)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let password_hash = hash_password(&req.password)?;
 
-    let user_id = Uuid::new_v4();
+    // Пользователь и токен подтверждения создаются вместе: иначе при сбое на токене
+    // аккаунт остался бы, а повторная регистрация упиралась бы в "email занят"
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    sqlx::query(
+    let user = sqlx::query_as::<_, User>(
         "INSERT INTO users (id, email, username, password_hash, created_at)
-         VALUES ($1, $2, $3, $4, $5)"
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING *"
     )
-    .bind(user_id)
+    .bind(Uuid::new_v4())
     .bind(&req.email)
-    .bind(&req.username)
+    .bind(username)
     .bind(&password_hash)
     .bind(Utc::now())
-    .execute(&pool)
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    .map_err(map_username_conflict)?;
 
-    let token = create_token(user_id, &req.username, &req.email)?;
+    let token = issue_verification_token(&mut tx, user.id).await?;
 
-    tracing::info!(user_id = %user_id, username = %req.username, "User registered successfully");
-    Ok(Json(AuthResponse {
-        token,
-        user_id,
-        username: req.username,
-    }))
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    queue_verification_email(mailer, &user, &token);
+
+    let client = ClientInfo::from_headers(&headers);
+    let response = start_session(&pool, &user, &client).await?;
+    audit::record(&pool, EventType::Register, Some(user.id), &client, serde_json::json!({ "method": "password" })).await;
+
+    tracing::info!(user_id = %user.id, username = %user.username, "User registered successfully");
+    Ok(Json(response))
 }
 
 #[utoipa::path(
@@ -87,18 +155,52 @@ pub async fn register(
     path = "/auth/login",
     request_body = LoginRequest,
     responses(
-        (status = 200, description = "Успешный вход", body = AuthResponse),
+        (status = 200, description = "Успешный вход или запрос второго фактора", body = LoginResponse),
         (status = 401, description = "Неверный email или пароль"),
+        (status = 429, description = "Слишком много неудачных попыток, вход временно заблокирован"),
         (status = 500, description = "Внутренняя ошибка сервера"),
     ),
     tag = "auth"
 )]
 pub async fn login(
     State(pool): State<PgPool>,
+    headers: HeaderMap,
     Json(req): Json<LoginRequest>,
-) -> Result<Json<AuthResponse>, (StatusCode, String)> {
+) -> Result<Json<LoginResponse>, (StatusCode, String)> {
     tracing::info!(email = %req.email, "Attempting login");
-    
+
+    let client = ClientInfo::from_headers(&headers);
+    let account_key = throttle::account_key(&req.email);
+    let ip_key = client.ip_address.as_deref().map(throttle::ip_key);
+    let keys: Vec<String> = std::iter::once(account_key.clone()).chain(ip_key.clone()).collect();
+
+    // Блокировка проверяется до поиска пользователя, поэтому выглядит одинаково для любого email
+    let locked_for = throttle::locked_for(&pool, &keys).await.map_err(|e| {
+        tracing::error!(error = %e, "Database error checking login throttle");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+    if let Some(remaining) = locked_for {
+        let retry_after = remaining.num_seconds().max(0) + 1;
+        tracing::warn!(
+            event = "login_throttled",
+            email = %req.email,
+            ip = ?client.ip_address,
+            retry_after,
+            "Login rejected: too many failed attempts"
+        );
+        audit::record(&pool, EventType::LoginFailed, None, &client, serde_json::json!({
+            "method": "password",
+            "email": req.email,
+            "reason": "throttled",
+        })).await;
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("Too many login attempts, try again in {} seconds", retry_after),
+        ));
+    }
+
+    let started = tokio::time::Instant::now();
+
     let user = sqlx::query_as::<_, User>(
         "SELECT * FROM users WHERE email = $1"
     )
@@ -108,25 +210,197 @@ pub async fn login(
     .map_err(|e| {
         tracing::error!(error = %e, "Database error fetching user");
         (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    // Для неизвестного email хэш тоже проверяется, чтобы время ответа не выдавало, есть ли аккаунт
+    let password_hash = user.as_ref().map_or(DUMMY_PASSWORD_HASH.as_str(), |u| u.password_hash.as_str());
+    let verification = passwords::verify(&req.password, password_hash)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let user = match user {
+        Some(user) if verification.valid => user,
+        user => {
+            let reason = if user.is_none() {
+                tracing::warn!(email = %req.email, "Login failed: user not found");
+                "unknown_email"
+            } else {
+                tracing::warn!(email = %req.email, "Login failed: invalid password");
+                "invalid_password"
+            };
+            audit::record(&pool, EventType::LoginFailed, user.map(|u| u.id), &client, serde_json::json!({
+                "method": "password",
+                "email": req.email,
+                "reason": reason,
+            })).await;
+            record_login_failure(&pool, &account_key, ip_key.as_deref()).await?;
+            tokio::time::sleep_until(started + FAILED_LOGIN_MIN_DURATION).await;
+            return Err((StatusCode::UNAUTHORIZED, "Invalid email or password".to_string()));
+        }
+    };
+
+    let mut conn = pool.acquire().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    throttle::clear(&mut conn, &account_key).await.map_err(|e| {
+        tracing::error!(error = %e, "Database error clearing login throttle");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+    drop(conn);
+
+    if verification.needs_rehash {
+        rehash_password(&pool, &user, &req.password).await;
+    }
+
+    if user.totp_enabled_at.is_some() {
+        let challenge = start_mfa_challenge(&pool, &user).await?;
+        tracing::info!(user_id = %user.id, "Password accepted, waiting for second factor");
+        return Ok(Json(LoginResponse::MfaRequired(challenge)));
+    }
+
+    let response = start_session(&pool, &user, &client).await?;
+    audit::record(&pool, EventType::LoginSucceeded, Some(user.id), &client, serde_json::json!({ "method": "password" })).await;
+
+    tracing::info!(user_id = %user.id, username = %user.username, "User logged in successfully");
+    Ok(Json(LoginResponse::Authenticated(response)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "Токены обновлены", body = AuthResponse),
+        (status = 401, description = "Refresh-токен невалиден, истек или уже был использован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    tag = "auth"
+)]
+pub async fn refresh(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<RefreshRequest>,
+) -> Result<Json<AuthResponse>, (StatusCode, String)> {
+    let (session_id, secret) = parse_refresh_token(&req.refresh_token)
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid refresh token".to_string()))?;
+
+    tracing::info!(session_id = %session_id, "Attempting token refresh");
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!(error = %e, "Failed to start transaction");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    // Блокируем строку, чтобы два параллельных refresh не ротировали один токен дважды
+    let session = sqlx::query_as::<_, Session>(
+        "SELECT * FROM sessions WHERE id = $1 FOR UPDATE"
+    )
+    .bind(session_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching session");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
     })?
     .ok_or_else(|| {
-        tracing::warn!(email = %req.email, "Login failed: user not found");
-        (StatusCode::UNAUTHORIZED, "Invalid email or password".to_string())
+        tracing::warn!(session_id = %session_id, "Refresh failed: session not found");
+        (StatusCode::UNAUTHORIZED, "Invalid refresh token".to_string())
     })?;
 
-    let valid = verify(&req.password, &user.password_hash)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if session.revoked_at.is_some() {
+        tracing::warn!(session_id = %session_id, "Refresh failed: session revoked");
+        return Err((StatusCode::UNAUTHORIZED, "Session has been revoked".to_string()));
+    }
 
-    if !valid {
-        tracing::warn!(email = %req.email, "Login failed: invalid password");
-        return Err((StatusCode::UNAUTHORIZED, "Invalid email or password".to_string()));
+    let now = Utc::now();
+    if session.expires_at <= now {
+        tracing::warn!(session_id = %session_id, "Refresh failed: session expired");
+        return Err((StatusCode::UNAUTHORIZED, "Refresh token expired".to_string()));
     }
 
-    let token = create_token(user.id, &user.username, &user.email)?;
+    let secret_hash = hash_token(secret);
 
-    tracing::info!(user_id = %user.id, username = %user.username, "User logged in successfully");
+    // id сессии не секрет (он есть в `sid` любого access-токена), поэтому
+    // неизвестный секрет — просто невалидный токен, а не повод гасить сессию
+    if secret_hash != session.refresh_token_hash
+        && !session.previous_refresh_token_hashes.contains(&secret_hash)
+    {
+        tracing::warn!(session_id = %session_id, "Refresh failed: unknown refresh token");
+        return Err((StatusCode::UNAUTHORIZED, "Invalid refresh token".to_string()));
+    }
+
+    // Предъявлен уже ротированный токен. Кто-то из двоих (владелец или злоумышленник)
+    // держит копию, поэтому гасим всю сессию.
+    if secret_hash != session.refresh_token_hash {
+        revocation::revoke_session(&mut tx, session_id, session.user_id)
+            .await
+            .map_err(|e| {
+                tracing::error!(error = %e, "Database error revoking session");
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            })?;
+
+        tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        tracing::warn!(session_id = %session_id, user_id = %session.user_id, "Refresh token reuse detected, session revoked");
+        audit::record(&pool, EventType::SessionRevoked, Some(session.user_id), &ClientInfo::from_headers(&headers), serde_json::json!({
+            "session_id": session_id,
+            "reason": "refresh_token_reuse",
+        })).await;
+        return Err((StatusCode::UNAUTHORIZED, "Refresh token reuse detected".to_string()));
+    }
+
+    let new_secret = generate_secret();
+    let client = ClientInfo::from_headers(&headers);
+
+    // Refresh — единственный регулярный запрос от клиента в auth-service,
+    // поэтому здесь же обновляем "последнюю активность" и адрес устройства.
+    // Если пользователя исключили из активной организации, сессия возвращается в личное пространство.
+    let organization_id = sqlx::query_scalar::<_, Option<Uuid>>(
+        "UPDATE sessions SET
+            previous_refresh_token_hashes = (refresh_token_hash || previous_refresh_token_hashes)[1:$7],
+            refresh_token_hash = $2,
+            expires_at = $3,
+            last_seen_at = $4,
+            user_agent = COALESCE($5, user_agent),
+            ip_address = COALESCE($6, ip_address),
+            organization_id = (
+                SELECT m.organization_id FROM organization_members m
+                WHERE m.organization_id = sessions.organization_id AND m.user_id = sessions.user_id
+            )
+         WHERE id = $1
+         RETURNING organization_id"
+    )
+    .bind(session_id)
+    .bind(hash_token(&new_secret))
+    .bind(now + Duration::days(REFRESH_TOKEN_TTL_DAYS))
+    .bind(now)
+    .bind(&client.user_agent)
+    .bind(&client.ip_address)
+    .bind(REFRESH_TOKEN_HISTORY)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error rotating refresh token");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users WHERE id = $1"
+    )
+    .bind(session.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching user");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let token = create_token(&user, session_id, organization_id)?;
+
+    tracing::info!(session_id = %session_id, user_id = %user.id, "Tokens refreshed successfully");
     Ok(Json(AuthResponse {
         token,
+        refresh_token: format!("{}.{}", session_id, new_secret),
+        expires_in: ACCESS_TOKEN_TTL_MINUTES * 60,
         user_id: user.id,
         username: user.username,
     }))
@@ -143,81 +417,344 @@ pub async fn login(
     tag = "auth"
 )]
 pub async fn verify_token(
+    State(pool): State<PgPool>,
     headers: HeaderMap,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
     tracing::debug!("Verifying token");
-    
-    let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
 
-    let auth_header = headers
-        .get("authorization")
-        .and_then(|h| h.to_str().ok())
-        .ok_or_else(|| {
-            tracing::warn!("Token verification failed: missing authorization header");
-            (StatusCode::UNAUTHORIZED, "Missing authorization header".to_string())
+    let claims = authenticate(&pool, &headers).await?;
+
+    tracing::info!(user_id = %claims.sub, "Token verified successfully");
+    Ok(Json(serde_json::json!({
+        "user_id": claims.sub,
+        "username": claims.username,
+        "email": claims.email,
+        "email_verified": claims.email_verified,
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    responses(
+        (status = 200, description = "Публичные ключи для проверки access-токенов", body = serde_json::Value),
+    ),
+    tag = "auth"
+)]
+pub async fn jwks() -> impl IntoResponse {
+    // Кэш короче окна ротации ключей, чтобы сервисы успевали увидеть новый ключ
+    (
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(keys::jwks()),
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    responses(
+        (status = 204, description = "Сессия завершена, токен отозван"),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn logout(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let claims = authenticate(&pool, &headers).await?;
+    let user_id = claims.user_id()?;
+    let session_id = claims.session_id()?;
+    let token_id = claims.token_id()?;
+
+    tracing::info!(user_id = %user_id, session_id = %session_id, "Logging out");
+
+    let token_expires_at = DateTime::<Utc>::from_timestamp(claims.exp as i64, 0)
+        .unwrap_or_else(Utc::now);
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    revocation::deny(&mut tx, token_id, user_id, token_expires_at)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error revoking token");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
         })?;
 
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or((StatusCode::UNAUTHORIZED, "Invalid authorization header format".to_string()))?;
+    revocation::revoke_session(&mut tx, session_id, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error revoking session");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
 
-    let token_data = jsonwebtoken::decode::<Claims>(
-        token,
-        &jsonwebtoken::DecodingKey::from_secret(secret.as_bytes()),
-        &jsonwebtoken::Validation::default(),
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    audit::record(&pool, EventType::SessionRevoked, Some(user_id), &ClientInfo::from_headers(&headers), serde_json::json!({
+        "session_id": session_id,
+        "reason": "logout",
+    })).await;
+
+    tracing::info!(user_id = %user_id, session_id = %session_id, "Logged out successfully");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout-all",
+    responses(
+        (status = 204, description = "Все сессии пользователя завершены"),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn logout_all(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let claims = authenticate(&pool, &headers).await?;
+    let user_id = claims.user_id()?;
+
+    tracing::info!(user_id = %user_id, "Logging out from all sessions");
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let revoked = revocation::revoke_user_sessions(&mut tx, user_id, None)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error revoking sessions");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    audit::record(&pool, EventType::SessionRevoked, Some(user_id), &ClientInfo::from_headers(&headers), serde_json::json!({
+        "count": revoked,
+        "reason": "logout_all",
+    })).await;
+
+    tracing::info!(user_id = %user_id, revoked, "Logged out from all sessions");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn create_token(user: &User, session_id: Uuid, organization_id: Option<Uuid>) -> Result<String, (StatusCode, String)> {
+    let claims = Claims {
+        sub: user.id.to_string(),
+        username: user.username.clone(),
+        email: user.email.clone(),
+        email_verified: user.email_verified_at.is_some(),
+        sid: session_id.to_string(),
+        jti: Uuid::new_v4().to_string(),
+        role: user.role.clone(),
+        org_id: organization_id.map(|id| id.to_string()),
+        exp: (Utc::now() + Duration::minutes(ACCESS_TOKEN_TTL_MINUTES)).timestamp() as usize,
+    };
+
+    keys::sign(&claims)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Создает новую сессию и выдает пару access/refresh токенов.
+/// Refresh-токен имеет вид `<session_id>.<secret>`, в базе хранится только SHA-256 от secret.
+pub async fn start_session(
+    pool: &PgPool,
+    user: &User,
+    client: &ClientInfo,
+) -> Result<AuthResponse, (StatusCode, String)> {
+    if user.disabled_at.is_some() {
+        tracing::warn!(user_id = %user.id, event = "disabled_login", "Session rejected: account is disabled");
+        audit::record(pool, EventType::LoginFailed, Some(user.id), client, serde_json::json!({ "reason": "account_disabled" })).await;
+        return Err((StatusCode::FORBIDDEN, "Account is disabled".to_string()));
+    }
+
+    // Вход в течение срока ожидания отменяет запрошенное удаление аккаунта
+    if user.deletion_scheduled_at.is_some() {
+        sqlx::query("UPDATE users SET deletion_scheduled_at = NULL WHERE id = $1")
+            .bind(user.id)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!(error = %e, "Database error cancelling account deletion");
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            })?;
+        tracing::info!(user_id = %user.id, event = "account_deletion_cancelled", "Account deletion cancelled by login");
+    }
+
+    let session_id = Uuid::new_v4();
+    let secret = generate_secret();
+    let now = Utc::now();
+
+    sqlx::query(
+        "INSERT INTO sessions (id, user_id, refresh_token_hash, created_at, expires_at,
+                               user_agent, ip_address, last_seen_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $4)"
     )
+    .bind(session_id)
+    .bind(user.id)
+    .bind(hash_token(&secret))
+    .bind(now)
+    .bind(now + Duration::days(REFRESH_TOKEN_TTL_DAYS))
+    .bind(&client.user_agent)
+    .bind(&client.ip_address)
+    .execute(pool)
+    .await
     .map_err(|e| {
-        tracing::warn!(error = %e, "Token verification failed: invalid token");
-        (StatusCode::UNAUTHORIZED, "Invalid token".to_string())
+        tracing::error!(error = %e, "Database error creating session");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
     })?;
 
-    tracing::info!(user_id = %token_data.claims.sub, "Token verified successfully");
-    Ok(Json(serde_json::json!({
-        "user_id": token_data.claims.sub,
-        "username": token_data.claims.username,
-        "email": token_data.claims.email,
-    })))
+    let token = create_token(user, session_id, None)?;
+
+    tracing::debug!(user_id = %user.id, session_id = %session_id, "Session created");
+    Ok(AuthResponse {
+        token,
+        refresh_token: format!("{}.{}", session_id, secret),
+        expires_in: ACCESS_TOKEN_TTL_MINUTES * 60,
+        user_id: user.id,
+        username: user.username.clone(),
+    })
 }
 
-fn create_token(user_id: Uuid, username: &str, email: &str) -> Result<String, (StatusCode, String)> {
-    let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
+/// Учитывает неудачный вход по email и по IP и пишет события безопасности
+async fn record_login_failure(
+    pool: &PgPool,
+    account_key: &str,
+    ip_key: Option<&str>,
+) -> Result<(), (StatusCode, String)> {
+    let keys = std::iter::once((account_key, &throttle::ACCOUNT))
+        .chain(ip_key.map(|key| (key, &throttle::IP)));
+
+    for (key, policy) in keys {
+        let failure = throttle::record_failure(pool, key, policy).await.map_err(|e| {
+            tracing::error!(error = %e, "Database error recording login failure");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
 
-    let claims = Claims {
-        sub: user_id.to_string(),
-        username: username.to_string(),
-        email: email.to_string(),
-        exp: (Utc::now() + Duration::days(7)).timestamp() as usize,
+        match failure.delay {
+            Some(delay) if failure.locked_out => tracing::warn!(
+                event = "login_lockout",
+                key,
+                failures = failure.failures,
+                locked_for = delay.num_seconds(),
+                "Login locked after repeated failures"
+            ),
+            Some(delay) => tracing::warn!(
+                event = "login_backoff",
+                key,
+                failures = failure.failures,
+                delay = delay.num_seconds(),
+                "Login delayed after failed attempt"
+            ),
+            None => tracing::info!(event = "login_failed", key, failures = failure.failures, "Failed login recorded"),
+        }
+    }
+
+    Ok(())
+}
+
+pub fn hash_password(password: &str) -> Result<String, (StatusCode, String)> {
+    passwords::hash(password).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// Переводит хэш на текущий алгоритм и параметры, пока известен открытый пароль.
+/// Вход не должен падать из-за этого, поэтому ошибки только логируются.
+async fn rehash_password(pool: &PgPool, user: &User, password: &str) {
+    let new_hash = match passwords::hash(password) {
+        Ok(new_hash) => new_hash,
+        Err(e) => {
+            tracing::error!(error = %e, user_id = %user.id, "Failed to rehash password");
+            return;
+        }
     };
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+    // Условие на старый хэш не даст затереть пароль, смененный параллельно
+    let result = sqlx::query("UPDATE users SET password_hash = $2 WHERE id = $1 AND password_hash = $3")
+        .bind(user.id)
+        .bind(&new_hash)
+        .bind(&user.password_hash)
+        .execute(pool)
+        .await;
+
+    match result {
+        Ok(_) => tracing::info!(user_id = %user.id, "Password hash upgraded"),
+        Err(e) => tracing::error!(error = %e, user_id = %user.id, "Database error upgrading password hash"),
+    }
+}
+
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
 }
 
-fn extract_user_id_from_token(headers: &HeaderMap) -> Result<Uuid, (StatusCode, String)> {
-    let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
+fn parse_refresh_token(token: &str) -> Option<(Uuid, &str)> {
+    let (session_id, secret) = token.split_once('.')?;
+    let session_id = session_id.parse::<Uuid>().ok()?;
+    if secret.is_empty() {
+        return None;
+    }
+    Some((session_id, secret))
+}
 
+fn decode_claims(headers: &HeaderMap) -> Result<Claims, (StatusCode, String)> {
     let auth_header = headers
         .get("authorization")
         .and_then(|h| h.to_str().ok())
-        .ok_or((StatusCode::UNAUTHORIZED, "Missing authorization header".to_string()))?;
+        .ok_or_else(|| {
+            tracing::warn!("Token verification failed: missing authorization header");
+            (StatusCode::UNAUTHORIZED, "Missing authorization header".to_string())
+        })?;
 
     let token = auth_header
         .strip_prefix("Bearer ")
         .ok_or((StatusCode::UNAUTHORIZED, "Invalid authorization header format".to_string()))?;
 
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
-    )
-    .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid token".to_string()))?;
+    keys::verify::<Claims>(token).map_err(|e| {
+        tracing::warn!(error = %e, "Token verification failed: invalid token");
+        (StatusCode::UNAUTHORIZED, "Invalid token".to_string())
+    })
+}
+
+/// Проверяет подпись токена и то, что ни он, ни его сессия не отозваны
+pub async fn authenticate(pool: &PgPool, headers: &HeaderMap) -> Result<Claims, (StatusCode, String)> {
+    let claims = match decode_claims(headers) {
+        Ok(claims) => claims,
+        Err(e) => {
+            // Запрос вовсе без токена — не попытка подделки, в журнал не пишем
+            if headers.contains_key(header::AUTHORIZATION) {
+                audit::record(pool, EventType::TokenRejected, None, &ClientInfo::from_headers(headers), serde_json::json!({
+                    "reason": e.1,
+                })).await;
+            }
+            return Err(e);
+        }
+    };
 
-    token_data.claims.sub.parse::<Uuid>()
-        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID in token".to_string()))
+    let denied = revocation::is_denied(pool, &[claims.token_id()?, claims.session_id()?])
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error checking token denylist");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if denied {
+        tracing::warn!(user_id = %claims.sub, jti = %claims.jti, "Token verification failed: token revoked");
+        audit::record(pool, EventType::TokenRejected, claims.user_id().ok(), &ClientInfo::from_headers(headers), serde_json::json!({
+            "reason": "revoked",
+            "session_id": claims.sid,
+        })).await;
+        return Err((StatusCode::UNAUTHORIZED, "Token has been revoked".to_string()));
+    }
+
+    Ok(claims)
 }
 
 #[utoipa::path(
@@ -226,7 +763,9 @@ fn extract_user_id_from_token(headers: &HeaderMap) -> Result<Uuid, (StatusCode,
     request_body = UpdateProfileRequest,
     responses(
         (status = 200, description = "Профиль обновлен", body = UpdateProfileResponse),
+        (status = 400, description = "Недопустимое имя пользователя"),
         (status = 401, description = "Не авторизован"),
+        (status = 409, description = "Имя пользователя уже занято"),
         (status = 500, description = "Внутренняя ошибка сервера"),
     ),
     security(("bearer_auth" = [])),
@@ -237,24 +776,29 @@ pub async fn update_profile(
     headers: HeaderMap,
     Json(req): Json<UpdateProfileRequest>,
 ) -> Result<Json<UpdateProfileResponse>, (StatusCode, String)> {
-    let user_id = extract_user_id_from_token(&headers)?;
+    let claims = authenticate(&pool, &headers).await?;
+    let user_id = claims.user_id()?;
     
     tracing::info!(user_id = %user_id, new_username = %req.username, "Updating user profile");
 
+    let username = req.username.trim();
+    validate_username(username)?;
+
     let user = sqlx::query_as::<_, User>(
         "UPDATE users SET username = $1 WHERE id = $2 RETURNING *"
     )
-    .bind(&req.username)
+    .bind(username)
     .bind(user_id)
     .fetch_one(&pool)
     .await
-    .map_err(|e| {
-        tracing::error!(error = %e, "Database error updating profile");
-        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
-    })?;
+    .map_err(map_username_conflict)?;
+
+    audit::record(&pool, EventType::ProfileUpdated, Some(user_id), &ClientInfo::from_headers(&headers), serde_json::json!({
+        "fields": ["username"],
+    })).await;
 
     // Создаем новый JWT токен с обновленным username
-    let token = create_token(user.id, &user.username, &user.email)?;
+    let token = create_token(&user, claims.session_id()?, claims.organization_id()?)?;
 
     tracing::info!(user_id = %user_id, "Profile updated successfully with new token");
     Ok(Json(UpdateProfileResponse {
@@ -271,6 +815,7 @@ pub async fn update_profile(
     request_body = ChangePasswordRequest,
     responses(
         (status = 200, description = "Пароль изменен"),
+        (status = 400, description = "Новый пароль не проходит политику"),
         (status = 401, description = "Не авторизован или неверный текущий пароль"),
         (status = 500, description = "Внутренняя ошибка сервера"),
     ),
@@ -282,7 +827,8 @@ pub async fn change_password(
     headers: HeaderMap,
     Json(req): Json<ChangePasswordRequest>,
 ) -> Result<StatusCode, (StatusCode, String)> {
-    let user_id = extract_user_id_from_token(&headers)?;
+    let claims = authenticate(&pool, &headers).await?;
+    let user_id = claims.user_id()?;
     
     tracing::info!(user_id = %user_id, "Attempting password change");
 
@@ -297,29 +843,70 @@ pub async fn change_password(
         (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
     })?;
 
-    let valid = verify(&req.current_password, &user.password_hash)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let valid = passwords::verify(&req.current_password, &user.password_hash)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .valid;
 
     if !valid {
         tracing::warn!(user_id = %user_id, "Password change failed: invalid current password");
         return Err((StatusCode::UNAUTHORIZED, "Current password is incorrect".to_string()));
     }
 
-    let new_password_hash = hash(&req.new_password, DEFAULT_COST)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if user.totp_enabled_at.is_some() {
+        let code = req.totp_code.as_deref().ok_or_else(|| {
+            tracing::warn!(user_id = %user_id, "Password change failed: two-factor code required");
+            (StatusCode::UNAUTHORIZED, "Two-factor code required".to_string())
+        })?;
+
+        if !verify_second_factor(&pool, &user, code).await? {
+            tracing::warn!(user_id = %user_id, "Password change failed: invalid two-factor code");
+            return Err((StatusCode::UNAUTHORIZED, "Invalid two-factor code".to_string()));
+        }
+    }
+
+    passwords::check_policy(&req.new_password).await?;
+
+    // Новый хэш всегда делается текущим алгоритмом, так что смена пароля заодно обновляет и его
+    let new_password_hash = hash_password(&req.new_password)?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     sqlx::query(
         "UPDATE users SET password_hash = $1 WHERE id = $2"
     )
     .bind(&new_password_hash)
     .bind(user_id)
-    .execute(&pool)
+    .execute(&mut *tx)
     .await
     .map_err(|e| {
         tracing::error!(error = %e, "Database error updating password");
         (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
     })?;
 
+    let revoked = if req.revoke_other_sessions {
+        let revoked = revocation::revoke_user_sessions(&mut tx, user_id, Some(claims.session_id()?))
+            .await
+            .map_err(|e| {
+                tracing::error!(error = %e, "Database error revoking sessions");
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            })?;
+        tracing::info!(user_id = %user_id, revoked, "Other sessions revoked after password change");
+        revoked
+    } else {
+        0
+    };
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let client = ClientInfo::from_headers(&headers);
+    audit::record(&pool, EventType::PasswordChanged, Some(user_id), &client, serde_json::json!({})).await;
+    if revoked > 0 {
+        audit::record(&pool, EventType::SessionRevoked, Some(user_id), &client, serde_json::json!({
+            "count": revoked,
+            "reason": "password_changed",
+        })).await;
+    }
+
     tracing::info!(user_id = %user_id, "Password changed successfully");
     Ok(StatusCode::OK)
 }
@@ -0,0 +1,287 @@
+use axum::{extract::State, http::{StatusCode, HeaderMap}, Json};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::audit::{self, EventType};
+use crate::handlers::auth::{authenticate, create_token, generate_secret, hash_token};
+use crate::handlers::mfa::verify_second_factor;
+use crate::mail::{self, Email, MailSender};
+use crate::models::session::ClientInfo;
+use crate::models::user::{ChangeEmailRequest, ConfirmEmailChangeRequest, UpdateProfileResponse, User};
+use crate::passwords;
+
+const EMAIL_CHANGE_TOKEN_TTL_HOURS: i64 = 24;
+
+pub fn is_valid_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !email.contains(char::is_whitespace)
+                && email.len() <= 254
+        }
+        None => false,
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/email/change",
+    request_body = ChangeEmailRequest,
+    responses(
+        (status = 202, description = "На новый адрес отправлена ссылка для подтверждения, на старый — уведомление"),
+        (status = 400, description = "Некорректный или совпадающий с текущим адрес"),
+        (status = 401, description = "Не авторизован, неверный пароль или код 2FA"),
+        (status = 409, description = "Адрес уже занят"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn request_email_change(
+    State(pool): State<PgPool>,
+    State(mailer): State<Arc<dyn MailSender>>,
+    headers: HeaderMap,
+    Json(req): Json<ChangeEmailRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+    let new_email = req.new_email.trim();
+
+    tracing::info!(user_id = %user_id, "Email change requested");
+
+    if !is_valid_email(new_email) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid email address".to_string()));
+    }
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching user");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    if user.email.eq_ignore_ascii_case(new_email) {
+        return Err((StatusCode::BAD_REQUEST, "New email matches the current one".to_string()));
+    }
+
+    let valid = passwords::verify(&req.password, &user.password_hash)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .valid;
+
+    if !valid {
+        tracing::warn!(user_id = %user_id, "Email change failed: invalid password");
+        return Err((StatusCode::UNAUTHORIZED, "Password is incorrect".to_string()));
+    }
+
+    if user.totp_enabled_at.is_some() {
+        let code = req.totp_code.as_deref().ok_or_else(|| {
+            tracing::warn!(user_id = %user_id, "Email change failed: two-factor code required");
+            (StatusCode::UNAUTHORIZED, "Two-factor code required".to_string())
+        })?;
+
+        if !verify_second_factor(&pool, &user, code).await? {
+            tracing::warn!(user_id = %user_id, "Email change failed: invalid two-factor code");
+            return Err((StatusCode::UNAUTHORIZED, "Invalid two-factor code".to_string()));
+        }
+    }
+
+    // Ранняя проверка ради понятной ошибки; окончательно занятость адреса решает
+    // UNIQUE-ограничение при подтверждении
+    let taken = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM users WHERE email = $1)"
+    )
+    .bind(new_email)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error checking email");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    if taken {
+        tracing::warn!(user_id = %user_id, "Email change failed: address already taken");
+        return Err((StatusCode::CONFLICT, "Email already taken".to_string()));
+    }
+
+    let token = generate_secret();
+    let now = Utc::now();
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Действует только последний запрос на смену
+    sqlx::query(
+        "UPDATE email_change_requests SET cancelled_at = $2
+         WHERE user_id = $1 AND confirmed_at IS NULL AND cancelled_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error cancelling previous email change");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    sqlx::query(
+        "INSERT INTO email_change_requests (id, user_id, new_email, token_hash, created_at, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(new_email)
+    .bind(hash_token(&token))
+    .bind(now)
+    .bind(now + Duration::hours(EMAIL_CHANGE_TOKEN_TTL_HOURS))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error creating email change request");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    mail::send_in_background(mailer.clone(), Email {
+        to: new_email.to_string(),
+        subject: "Подтверждение нового адреса Taspla".to_string(),
+        body: format!(
+            "Здравствуйте, {}!\n\nЧтобы сделать этот адрес основным для аккаунта Taspla, перейдите по ссылке:\n\
+             {}/confirm-email-change?token={}\n\nСсылка действует {} часов.",
+            user.username,
+            mail::app_url(),
+            token,
+            EMAIL_CHANGE_TOKEN_TTL_HOURS,
+        ),
+    });
+
+    mail::send_in_background(mailer, Email {
+        to: user.email.clone(),
+        subject: "Запрошена смена почты Taspla".to_string(),
+        body: format!(
+            "Здравствуйте, {}!\n\nДля вашего аккаунта запрошена смена адреса почты на {}. \
+             Адрес сменится только после подтверждения по ссылке, отправленной на новый адрес.\n\n\
+             Если это были не вы, как можно скорее смените пароль в профиле.",
+            user.username,
+            new_email,
+        ),
+    });
+
+    tracing::info!(user_id = %user_id, "Email change confirmation sent");
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/email/confirm",
+    request_body = ConfirmEmailChangeRequest,
+    responses(
+        (status = 200, description = "Адрес изменен, выдан новый access-токен", body = UpdateProfileResponse),
+        (status = 400, description = "Токен невалиден, истек или уже использован"),
+        (status = 401, description = "Не авторизован"),
+        (status = 409, description = "Адрес уже занят другим аккаунтом"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn confirm_email_change(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<ConfirmEmailChangeRequest>,
+) -> Result<Json<UpdateProfileResponse>, (StatusCode, String)> {
+    let claims = authenticate(&pool, &headers).await?;
+    let user_id = claims.user_id()?;
+    let now = Utc::now();
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Токен принимается только из сессии того же пользователя
+    let new_email = sqlx::query_scalar::<_, String>(
+        "UPDATE email_change_requests SET confirmed_at = $3
+         WHERE token_hash = $1 AND user_id = $2
+           AND confirmed_at IS NULL AND cancelled_at IS NULL AND expires_at > $3
+         RETURNING new_email"
+    )
+    .bind(hash_token(&req.token))
+    .bind(user_id)
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error consuming email change token");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or_else(|| {
+        tracing::warn!(user_id = %user_id, "Email change failed: invalid or expired token");
+        (StatusCode::BAD_REQUEST, "Invalid or expired confirmation token".to_string())
+    })?;
+
+    // Если адрес успели занять, UNIQUE-ограничение откатит всю транзакцию
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET email = $2, email_verified_at = $3 WHERE id = $1 RETURNING *"
+    )
+    .bind(user_id)
+    .bind(&new_email)
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        if e.as_database_error().is_some_and(|db| db.is_unique_violation()) {
+            tracing::warn!(user_id = %user_id, "Email change failed: address taken before confirmation");
+            (StatusCode::CONFLICT, "Email already taken".to_string())
+        } else {
+            tracing::error!(error = %e, "Database error updating email");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        }
+    })?;
+
+    // Ссылки, отправленные на старый адрес, больше не должны работать
+    sqlx::query(
+        "UPDATE password_reset_tokens SET used_at = $2 WHERE user_id = $1 AND used_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error consuming reset tokens");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    sqlx::query(
+        "UPDATE email_verification_tokens SET used_at = $2 WHERE user_id = $1 AND used_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error consuming verification tokens");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Как и в update_profile: новый access-токен для текущей сессии с новым email
+    let token = create_token(&user, claims.session_id()?, claims.organization_id()?)?;
+
+    audit::record(&pool, EventType::ProfileUpdated, Some(user_id), &ClientInfo::from_headers(&headers), serde_json::json!({
+        "fields": ["email"],
+    })).await;
+
+    tracing::info!(user_id = %user_id, "Email changed successfully");
+    Ok(Json(UpdateProfileResponse {
+        user_id: user.id,
+        username: user.username,
+        email: user.email,
+        token,
+    }))
+}
@@ -0,0 +1,149 @@
+use axum::{extract::{Query, State}, http::{StatusCode, HeaderMap}, Json};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::audit::EventType;
+use crate::handlers::admin::authorize;
+use crate::handlers::auth::authenticate;
+use crate::models::audit::{AdminAuthEventQuery, AuthEvent, AuthEventListResponse, AuthEventQuery};
+use crate::roles::Permission;
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Фильтры журнала; пустой фильтр не ограничивает выборку
+struct EventFilter {
+    user_id: Option<Uuid>,
+    event_type: Option<String>,
+    ip_address: Option<String>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/events",
+    params(AuthEventQuery),
+    responses(
+        (status = 200, description = "События безопасности текущего пользователя, новые сначала", body = AuthEventListResponse),
+        (status = 400, description = "Неизвестный тип события"),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn list_my_events(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Query(query): Query<AuthEventQuery>,
+) -> Result<Json<AuthEventListResponse>, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    list_events(&pool, EventFilter {
+        user_id: Some(user_id),
+        event_type: query.event_type,
+        ip_address: None,
+        from: None,
+        to: None,
+        page: query.page,
+        per_page: query.per_page,
+    })
+    .await
+    .map(Json)
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/auth-events",
+    params(AdminAuthEventQuery),
+    responses(
+        (status = 200, description = "События безопасности по фильтрам, новые сначала", body = AuthEventListResponse),
+        (status = 400, description = "Неизвестный тип события"),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Недостаточно прав"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "admin"
+)]
+pub async fn admin_list_events(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Query(query): Query<AdminAuthEventQuery>,
+) -> Result<Json<AuthEventListResponse>, (StatusCode, String)> {
+    authorize(&pool, &headers, Permission::ViewAuditLog).await?;
+
+    list_events(&pool, EventFilter {
+        user_id: query.user_id,
+        event_type: query.event_type,
+        ip_address: query.ip_address.map(|ip| ip.trim().to_string()).filter(|ip| !ip.is_empty()),
+        from: query.from,
+        to: query.to,
+        page: query.page,
+        per_page: query.per_page,
+    })
+    .await
+    .map(Json)
+}
+
+async fn list_events(pool: &PgPool, filter: EventFilter) -> Result<AuthEventListResponse, (StatusCode, String)> {
+    let event_type = match filter.event_type.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        Some(event_type) => Some(
+            EventType::parse(event_type)
+                .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("Unknown event type: {}", event_type)))?
+                .as_str(),
+        ),
+        None => None,
+    };
+
+    let page = filter.page.unwrap_or(1).max(1);
+    let per_page = filter.per_page.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    const CONDITIONS: &str = "($1::uuid IS NULL OR user_id = $1)
+           AND ($2::text IS NULL OR event_type = $2)
+           AND ($3::text IS NULL OR ip_address = $3)
+           AND ($4::timestamptz IS NULL OR created_at >= $4)
+           AND ($5::timestamptz IS NULL OR created_at < $5)";
+
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM auth_events WHERE {}", CONDITIONS))
+        .bind(filter.user_id)
+        .bind(event_type)
+        .bind(&filter.ip_address)
+        .bind(filter.from)
+        .bind(filter.to)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error counting auth events");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    let events = sqlx::query_as::<_, AuthEvent>(&format!(
+        "SELECT * FROM auth_events WHERE {} ORDER BY created_at DESC LIMIT $6 OFFSET $7",
+        CONDITIONS
+    ))
+    .bind(filter.user_id)
+    .bind(event_type)
+    .bind(&filter.ip_address)
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(per_page)
+    .bind((page - 1) * per_page)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error listing auth events");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(AuthEventListResponse {
+        items: events.into_iter().map(Into::into).collect(),
+        total,
+        page,
+        per_page,
+    })
+}
@@ -0,0 +1,194 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode, HeaderMap},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::handlers::auth::authenticate;
+use crate::keys;
+use crate::mail;
+use crate::models::export::{DataExport, DataExportResponse, DownloadQuery};
+
+/// Ссылка на скачивание выдается при каждом запросе статуса и живет недолго
+const DOWNLOAD_LINK_TTL_MINUTES: i64 = 15;
+const DOWNLOAD_PURPOSE: &str = "data_export";
+const EXPORT_COLUMNS: &str = "id, user_id, status, created_at, completed_at, expires_at";
+
+#[derive(Serialize, Deserialize)]
+struct DownloadClaims {
+    sub: String,
+    export_id: String,
+    purpose: String,
+    exp: usize,
+}
+
+fn to_response(export: DataExport) -> Result<DataExportResponse, (StatusCode, String)> {
+    let download_url = match (export.status.as_str(), export.expires_at) {
+        ("ready", Some(expires_at)) => {
+            let link_expires_at = (Utc::now() + Duration::minutes(DOWNLOAD_LINK_TTL_MINUTES)).min(expires_at);
+            let token = keys::sign(&DownloadClaims {
+                sub: export.user_id.to_string(),
+                export_id: export.id.to_string(),
+                purpose: DOWNLOAD_PURPOSE.to_string(),
+                exp: link_expires_at.timestamp() as usize,
+            })
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+            Some(format!("{}/api/auth/export/{}/download?token={}", mail::app_url(), export.id, token))
+        }
+        _ => None,
+    };
+
+    Ok(DataExportResponse {
+        id: export.id,
+        status: export.status,
+        created_at: export.created_at,
+        completed_at: export.completed_at,
+        expires_at: export.expires_at,
+        download_url,
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/export",
+    responses(
+        (status = 202, description = "Выгрузка поставлена в очередь", body = DataExportResponse),
+        (status = 401, description = "Не авторизован"),
+        (status = 409, description = "Предыдущая выгрузка еще собирается"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn create_export(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<DataExportResponse>), (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    // Частичный уникальный индекс не даст поставить вторую выгрузку параллельно
+    let export = sqlx::query_as::<_, DataExport>(&format!(
+        "INSERT INTO data_exports (id, user_id, status, created_at)
+         VALUES ($1, $2, 'pending', $3)
+         ON CONFLICT (user_id) WHERE status IN ('pending', 'running') DO NOTHING
+         RETURNING {}",
+        EXPORT_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(Utc::now())
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error creating data export");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or_else(|| {
+        tracing::warn!(user_id = %user_id, "Data export rejected: already in progress");
+        (StatusCode::CONFLICT, "Data export is already in progress".to_string())
+    })?;
+
+    tracing::info!(user_id = %user_id, export_id = %export.id, "Data export requested");
+    Ok((StatusCode::ACCEPTED, Json(to_response(export)?)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/export/{id}",
+    params(("id" = Uuid, Path, description = "ID выгрузки")),
+    responses(
+        (status = 200, description = "Статус выгрузки и ссылка на архив, если он готов", body = DataExportResponse),
+        (status = 401, description = "Не авторизован"),
+        (status = 404, description = "Выгрузка не найдена"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn get_export(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DataExportResponse>, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let export = sqlx::query_as::<_, DataExport>(&format!(
+        "SELECT {} FROM data_exports WHERE id = $1 AND user_id = $2",
+        EXPORT_COLUMNS
+    ))
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching data export");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or((StatusCode::NOT_FOUND, "Export not found".to_string()))?;
+
+    Ok(Json(to_response(export)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/export/{id}/download",
+    params(
+        ("id" = Uuid, Path, description = "ID выгрузки"),
+        ("token" = String, Query, description = "Подписанный токен из download_url"),
+    ),
+    responses(
+        (status = 200, description = "ZIP-архив с данными пользователя", content_type = "application/zip"),
+        (status = 401, description = "Ссылка недействительна или истекла"),
+        (status = 404, description = "Архив не найден или уже удален"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    tag = "auth"
+)]
+pub async fn download_export(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DownloadQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let claims = keys::verify::<DownloadClaims>(&query.token).map_err(|e| {
+        tracing::warn!(error = %e, export_id = %id, "Data export download rejected: invalid link");
+        (StatusCode::UNAUTHORIZED, "Invalid or expired download link".to_string())
+    })?;
+
+    if claims.purpose != DOWNLOAD_PURPOSE || claims.export_id != id.to_string() {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid or expired download link".to_string()));
+    }
+
+    let user_id = claims.sub.parse::<Uuid>()
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid or expired download link".to_string()))?;
+
+    let archive = sqlx::query_scalar::<_, Vec<u8>>(
+        "SELECT archive FROM data_exports
+         WHERE id = $1 AND user_id = $2 AND status = 'ready' AND expires_at > $3"
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(Utc::now())
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching data export archive");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or((StatusCode::NOT_FOUND, "Export not found or expired".to_string()))?;
+
+    tracing::info!(user_id = %user_id, export_id = %id, "Data export downloaded");
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"taspla-export-{}.zip\"", id)),
+        ],
+        archive,
+    ))
+}
@@ -0,0 +1,242 @@
+use axum::{extract::State, http::{StatusCode, HeaderMap}, Json};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::audit::{self, EventType};
+use crate::handlers::auth::{generate_secret, hash_token, start_session};
+use crate::handlers::mfa::start_mfa_challenge;
+use crate::mail::{self, Email, MailSender};
+use crate::models::session::ClientInfo;
+use crate::models::user::{ConsumeMagicLinkRequest, LoginResponse, MagicLinkRequest, MagicLinkResponse, User};
+use crate::throttle;
+
+const MAGIC_LINK_TTL_MINUTES: i64 = 15;
+
+#[utoipa::path(
+    post,
+    path = "/auth/magic-link",
+    request_body = MagicLinkRequest,
+    responses(
+        (status = 202, description = "Если аккаунт существует, на почту отправлена ссылка для входа", body = MagicLinkResponse),
+        (status = 429, description = "Слишком много запросов ссылки"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    tag = "auth"
+)]
+pub async fn request_magic_link(
+    State(pool): State<PgPool>,
+    State(mailer): State<Arc<dyn MailSender>>,
+    headers: HeaderMap,
+    Json(req): Json<MagicLinkRequest>,
+) -> Result<(StatusCode, Json<MagicLinkResponse>), (StatusCode, String)> {
+    tracing::info!(email = %req.email, "Magic link requested");
+
+    let client = ClientInfo::from_headers(&headers);
+    let limits: Vec<(String, &throttle::Policy)> =
+        std::iter::once((throttle::magic_link_key(&throttle::account_key(&req.email)), &throttle::MAGIC_LINK_ACCOUNT))
+            .chain(client.ip_address.as_deref().map(|ip| {
+                (throttle::magic_link_key(&throttle::ip_key(ip)), &throttle::MAGIC_LINK_IP)
+            }))
+            .collect();
+
+    let keys: Vec<String> = limits.iter().map(|(key, _)| key.clone()).collect();
+    let locked_for = throttle::locked_for(&pool, &keys).await.map_err(|e| {
+        tracing::error!(error = %e, "Database error checking magic link throttle");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+    if let Some(remaining) = locked_for {
+        let retry_after = remaining.num_seconds().max(0) + 1;
+        tracing::warn!(event = "magic_link_throttled", email = %req.email, ip = ?client.ip_address, retry_after, "Magic link rejected: too many requests");
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("Too many sign-in link requests, try again in {} seconds", retry_after),
+        ));
+    }
+
+    // Счетчик растет и для несуществующих адресов, чтобы лимит не выдавал, есть ли аккаунт
+    for (key, policy) in &limits {
+        throttle::record_failure(&pool, key, policy).await.map_err(|e| {
+            tracing::error!(error = %e, "Database error recording magic link request");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    }
+
+    let browser_token = generate_secret();
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users WHERE email = $1"
+    )
+    .bind(&req.email)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching user");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    // Ответ одинаковый независимо от того, есть ли такой аккаунт
+    match user {
+        Some(user) if user.disabled_at.is_none() => send_magic_link(&pool, mailer, user, &browser_token).await?,
+        Some(user) => tracing::warn!(user_id = %user.id, "Magic link requested for disabled account"),
+        None => tracing::warn!(email = %req.email, "Magic link requested for unknown email"),
+    }
+
+    Ok((StatusCode::ACCEPTED, Json(MagicLinkResponse { browser_token })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/magic-link/consume",
+    request_body = ConsumeMagicLinkRequest,
+    responses(
+        (status = 200, description = "Вход выполнен или требуется второй фактор", body = LoginResponse),
+        (status = 400, description = "Ссылка невалидна, истекла, уже использована или открыта в другом браузере"),
+        (status = 403, description = "Аккаунт заблокирован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    tag = "auth"
+)]
+pub async fn consume_magic_link(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<ConsumeMagicLinkRequest>,
+) -> Result<Json<LoginResponse>, (StatusCode, String)> {
+    let client = ClientInfo::from_headers(&headers);
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Ссылка действительна, только пока адрес аккаунта не менялся
+    let link = sqlx::query_as::<_, (Uuid, String, Uuid)>(
+        "SELECT l.id, l.browser_hash, l.user_id FROM magic_link_tokens l
+         JOIN users u ON u.id = l.user_id AND u.email = l.email
+         WHERE l.token_hash = $1 AND l.used_at IS NULL AND l.expires_at > $2
+         FOR UPDATE OF l"
+    )
+    .bind(hash_token(&req.token))
+    .bind(Utc::now())
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching magic link");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    let Some((link_id, browser_hash, user_id)) = link else {
+        tracing::warn!("Magic link login failed: invalid or expired token");
+        audit::record(&pool, EventType::LoginFailed, None, &client, serde_json::json!({
+            "method": "magic_link",
+            "reason": "invalid_link",
+        })).await;
+        return Err((StatusCode::BAD_REQUEST, "Invalid or expired sign-in link".to_string()));
+    };
+
+    // Ссылку, открытую в чужом браузере (или сканером почты), не гасим:
+    // владелец еще сможет открыть ее там, где запрашивал
+    if hash_token(&req.browser_token) != browser_hash {
+        tracing::warn!(user_id = %user_id, link_id = %link_id, "Magic link opened in a different browser");
+        audit::record(&pool, EventType::LoginFailed, Some(user_id), &client, serde_json::json!({
+            "method": "magic_link",
+            "reason": "browser_mismatch",
+        })).await;
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Open the sign-in link in the browser where you requested it".to_string(),
+        ));
+    }
+
+    let now = Utc::now();
+
+    // Гасим и эту ссылку, и все остальные неиспользованные ссылки пользователя
+    sqlx::query("UPDATE magic_link_tokens SET used_at = $2 WHERE user_id = $1 AND used_at IS NULL")
+        .bind(user_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error consuming magic links");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    // Переход по ссылке из письма подтверждает владение адресом
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET email_verified_at = COALESCE(email_verified_at, $2) WHERE id = $1 RETURNING *"
+    )
+    .bind(user_id)
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching user");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if user.totp_enabled_at.is_some() {
+        let challenge = start_mfa_challenge(&pool, &user).await?;
+        tracing::info!(user_id = %user.id, "Magic link accepted, waiting for second factor");
+        return Ok(Json(LoginResponse::MfaRequired(challenge)));
+    }
+
+    let response = start_session(&pool, &user, &client).await?;
+    audit::record(&pool, EventType::LoginSucceeded, Some(user.id), &client, serde_json::json!({ "method": "magic_link" })).await;
+
+    tracing::info!(user_id = %user.id, username = %user.username, "User logged in via magic link");
+    Ok(Json(LoginResponse::Authenticated(response)))
+}
+
+/// Выпускает одноразовую ссылку для входа, привязанную к браузеру запроса
+async fn send_magic_link(
+    pool: &PgPool,
+    mailer: Arc<dyn MailSender>,
+    user: User,
+    browser_token: &str,
+) -> Result<(), (StatusCode, String)> {
+    let token = generate_secret();
+    let now = Utc::now();
+
+    sqlx::query("DELETE FROM magic_link_tokens WHERE expires_at <= $1")
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error purging magic links");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    sqlx::query(
+        "INSERT INTO magic_link_tokens (id, user_id, email, token_hash, browser_hash, created_at, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user.id)
+    .bind(&user.email)
+    .bind(hash_token(&token))
+    .bind(hash_token(browser_token))
+    .bind(now)
+    .bind(now + Duration::minutes(MAGIC_LINK_TTL_MINUTES))
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error creating magic link");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    mail::send_in_background(mailer, Email {
+        to: user.email,
+        subject: "Вход в Taspla".to_string(),
+        body: format!(
+            "Здравствуйте, {}!\n\nЧтобы войти, перейдите по ссылке:\n{}/magic-link?token={}\n\n\
+             Ссылка одноразовая, действует {} минут и откроется только в том браузере, где вы ее запросили. \
+             Если вы не пытались войти, просто проигнорируйте это письмо.",
+            user.username,
+            mail::app_url(),
+            token,
+            MAGIC_LINK_TTL_MINUTES,
+        ),
+    });
+
+    tracing::info!(user_id = %user.id, "Magic link issued");
+    Ok(())
+}
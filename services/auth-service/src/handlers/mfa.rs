@@ -0,0 +1,426 @@
+use axum::{extract::State, http::{StatusCode, HeaderMap}, Json};
+use chrono::{Duration, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::audit::{self, EventType};
+use crate::handlers::auth::{authenticate, hash_token, start_session};
+use crate::keys;
+use crate::models::mfa::{
+    DisableTotpRequest, MfaLoginRequest, MfaRequiredResponse, RecoveryCodesResponse,
+    TotpCodeRequest, TotpSetupResponse,
+};
+use crate::models::session::ClientInfo;
+use crate::models::user::{AuthResponse, User};
+use crate::passwords;
+use crate::totp;
+
+const MFA_TOKEN_TTL_MINUTES: i64 = 5;
+/// После стольких неверных кодов придется заново войти по паролю
+const MFA_MAX_ATTEMPTS: i32 = 5;
+const RECOVERY_CODES_COUNT: usize = 10;
+const MFA_PENDING_PURPOSE: &str = "mfa_pending";
+
+/// Токен между первым (пароль) и вторым (код) шагом входа.
+/// Он не подходит как access-токен: в нем нет sid/jti/username.
+#[derive(Serialize, Deserialize)]
+struct MfaPendingClaims {
+    sub: String,
+    challenge_id: String,
+    purpose: String,
+    exp: usize,
+}
+
+/// Первый шаг входа пройден: заводим challenge и выдаем короткоживущий mfa_pending токен
+pub async fn start_mfa_challenge(pool: &PgPool, user: &User) -> Result<MfaRequiredResponse, (StatusCode, String)> {
+    let challenge_id = Uuid::new_v4();
+    let expires_at = Utc::now() + Duration::minutes(MFA_TOKEN_TTL_MINUTES);
+
+    sqlx::query(
+        "INSERT INTO mfa_challenges (id, user_id, attempts, created_at, expires_at)
+         VALUES ($1, $2, 0, $3, $4)"
+    )
+    .bind(challenge_id)
+    .bind(user.id)
+    .bind(Utc::now())
+    .bind(expires_at)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error creating MFA challenge");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    let claims = MfaPendingClaims {
+        sub: user.id.to_string(),
+        challenge_id: challenge_id.to_string(),
+        purpose: MFA_PENDING_PURPOSE.to_string(),
+        exp: expires_at.timestamp() as usize,
+    };
+
+    let mfa_token = keys::sign(&claims)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(MfaRequiredResponse {
+        mfa_required: true,
+        mfa_token,
+        expires_in: MFA_TOKEN_TTL_MINUTES * 60,
+    })
+}
+
+/// Проверяет код TOTP или одноразовый код восстановления.
+/// Принятый TOTP-код запоминается по номеру шага и повторно не принимается.
+pub async fn verify_second_factor(pool: &PgPool, user: &User, code: &str) -> Result<bool, (StatusCode, String)> {
+    let (Some(encrypted), Some(_)) = (&user.totp_secret_encrypted, user.totp_enabled_at) else {
+        return Ok(false);
+    };
+
+    let secret = totp::decrypt_secret(encrypted).map_err(|e| {
+        tracing::error!(error = %e, user_id = %user.id, "Failed to decrypt TOTP secret");
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to verify two-factor code".to_string())
+    })?;
+    let generator = totp::build(secret, &user.email)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    if let Some(step) = totp::verify(&generator, code, Utc::now().timestamp()) {
+        let accepted = sqlx::query(
+            "UPDATE users SET totp_last_step = $2
+             WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)"
+        )
+        .bind(user.id)
+        .bind(step)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error updating TOTP step");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?
+        .rows_affected() == 1;
+
+        if !accepted {
+            tracing::warn!(user_id = %user.id, "TOTP code replay rejected");
+        }
+        return Ok(accepted);
+    }
+
+    let used_recovery_code = sqlx::query(
+        "UPDATE totp_recovery_codes SET used_at = $3
+         WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL"
+    )
+    .bind(user.id)
+    .bind(hash_token(&normalize_recovery_code(code)))
+    .bind(Utc::now())
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error consuming recovery code");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .rows_affected() == 1;
+
+    if used_recovery_code {
+        tracing::info!(user_id = %user.id, "Recovery code used");
+    }
+    Ok(used_recovery_code)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/setup",
+    responses(
+        (status = 200, description = "Секрет выдан, 2FA ждет подтверждения кодом", body = TotpSetupResponse),
+        (status = 401, description = "Не авторизован"),
+        (status = 409, description = "2FA уже включена"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn setup_totp(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<Json<TotpSetupResponse>, (StatusCode, String)> {
+    let user = fetch_user(&pool, authenticate(&pool, &headers).await?.user_id()?).await?;
+
+    if user.totp_enabled_at.is_some() {
+        return Err((StatusCode::CONFLICT, "Two-factor authentication is already enabled".to_string()));
+    }
+
+    tracing::info!(user_id = %user.id, "Starting TOTP enrollment");
+
+    let secret = totp::generate_secret();
+    let encrypted = totp::encrypt_secret(&secret).map_err(|e| {
+        tracing::error!(error = %e, "Failed to encrypt TOTP secret");
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to set up two-factor authentication".to_string())
+    })?;
+    let generator = totp::build(secret, &user.email)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    sqlx::query(
+        "UPDATE users SET totp_secret_encrypted = $2, totp_enabled_at = NULL, totp_last_step = NULL
+         WHERE id = $1"
+    )
+    .bind(user.id)
+    .bind(&encrypted)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error storing TOTP secret");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(Json(TotpSetupResponse {
+        secret: generator.get_secret_base32(),
+        otpauth_uri: generator.get_url(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/confirm",
+    request_body = TotpCodeRequest,
+    responses(
+        (status = 200, description = "2FA включена, выданы коды восстановления", body = RecoveryCodesResponse),
+        (status = 400, description = "Сначала нужно вызвать /auth/2fa/setup"),
+        (status = 401, description = "Не авторизован или неверный код"),
+        (status = 409, description = "2FA уже включена"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn confirm_totp(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<TotpCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, (StatusCode, String)> {
+    let user = fetch_user(&pool, authenticate(&pool, &headers).await?.user_id()?).await?;
+
+    if user.totp_enabled_at.is_some() {
+        return Err((StatusCode::CONFLICT, "Two-factor authentication is already enabled".to_string()));
+    }
+
+    let encrypted = user.totp_secret_encrypted.as_ref()
+        .ok_or((StatusCode::BAD_REQUEST, "Two-factor setup has not been started".to_string()))?;
+
+    let secret = totp::decrypt_secret(encrypted).map_err(|e| {
+        tracing::error!(error = %e, user_id = %user.id, "Failed to decrypt TOTP secret");
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to verify two-factor code".to_string())
+    })?;
+    let generator = totp::build(secret, &user.email)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let step = totp::verify(&generator, &req.code, Utc::now().timestamp()).ok_or_else(|| {
+        tracing::warn!(user_id = %user.id, "TOTP confirmation failed: invalid code");
+        (StatusCode::UNAUTHORIZED, "Invalid two-factor code".to_string())
+    })?;
+
+    let recovery_codes: Vec<String> = (0..RECOVERY_CODES_COUNT).map(|_| generate_recovery_code()).collect();
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    sqlx::query(
+        "UPDATE users SET totp_enabled_at = $2, totp_last_step = $3 WHERE id = $1"
+    )
+    .bind(user.id)
+    .bind(Utc::now())
+    .bind(step)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error enabling TOTP");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    for code in &recovery_codes {
+        sqlx::query(
+            "INSERT INTO totp_recovery_codes (id, user_id, code_hash, created_at)
+             VALUES ($1, $2, $3, $4)"
+        )
+        .bind(Uuid::new_v4())
+        .bind(user.id)
+        .bind(hash_token(code))
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error storing recovery codes");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    }
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(user_id = %user.id, "Two-factor authentication enabled");
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/disable",
+    request_body = DisableTotpRequest,
+    responses(
+        (status = 204, description = "2FA отключена"),
+        (status = 401, description = "Не авторизован, неверный пароль или код"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn disable_totp(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<DisableTotpRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user = fetch_user(&pool, authenticate(&pool, &headers).await?.user_id()?).await?;
+
+    let valid_password = passwords::verify(&req.password, &user.password_hash)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .valid;
+
+    if !valid_password || !verify_second_factor(&pool, &user, &req.code).await? {
+        tracing::warn!(user_id = %user.id, "Disabling 2FA failed: invalid credentials");
+        return Err((StatusCode::UNAUTHORIZED, "Invalid password or two-factor code".to_string()));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    sqlx::query(
+        "UPDATE users SET totp_secret_encrypted = NULL, totp_enabled_at = NULL, totp_last_step = NULL
+         WHERE id = $1"
+    )
+    .bind(user.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error disabling TOTP");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(user_id = %user.id, "Two-factor authentication disabled");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/login/2fa",
+    request_body = MfaLoginRequest,
+    responses(
+        (status = 200, description = "Второй фактор подтвержден", body = AuthResponse),
+        (status = 401, description = "mfa_token невалиден или неверный код"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    tag = "auth"
+)]
+pub async fn login_mfa(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<MfaLoginRequest>,
+) -> Result<Json<AuthResponse>, (StatusCode, String)> {
+    let claims = keys::verify::<MfaPendingClaims>(&req.mfa_token).map_err(|e| {
+        tracing::warn!(error = %e, "MFA login failed: invalid mfa token");
+        (StatusCode::UNAUTHORIZED, "Invalid or expired MFA token".to_string())
+    })?;
+
+    if claims.purpose != MFA_PENDING_PURPOSE {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid or expired MFA token".to_string()));
+    }
+
+    let (Ok(user_id), Ok(challenge_id)) = (claims.sub.parse::<Uuid>(), claims.challenge_id.parse::<Uuid>()) else {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid or expired MFA token".to_string()));
+    };
+
+    // Каждая попытка расходует challenge; после лимита или успеха он больше не действует
+    let attempt = sqlx::query_scalar::<_, i32>(
+        "UPDATE mfa_challenges SET attempts = attempts + 1
+         WHERE id = $1 AND user_id = $2 AND completed_at IS NULL AND attempts < $3 AND expires_at > $4
+         RETURNING attempts"
+    )
+    .bind(challenge_id)
+    .bind(user_id)
+    .bind(MFA_MAX_ATTEMPTS)
+    .bind(Utc::now())
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error updating MFA challenge");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    if attempt.is_none() {
+        tracing::warn!(user_id = %user_id, challenge_id = %challenge_id, "MFA login failed: challenge exhausted or completed");
+        return Err((StatusCode::UNAUTHORIZED, "Invalid or expired MFA token".to_string()));
+    }
+
+    let user = fetch_user(&pool, user_id).await?;
+    let client = ClientInfo::from_headers(&headers);
+
+    if !verify_second_factor(&pool, &user, &req.code).await? {
+        tracing::warn!(user_id = %user_id, attempt = ?attempt, "MFA login failed: invalid code");
+        audit::record(&pool, EventType::LoginFailed, Some(user_id), &client, serde_json::json!({
+            "method": "2fa",
+            "reason": "invalid_second_factor",
+        })).await;
+        return Err((StatusCode::UNAUTHORIZED, "Invalid two-factor code".to_string()));
+    }
+
+    sqlx::query("UPDATE mfa_challenges SET completed_at = $2 WHERE id = $1")
+        .bind(challenge_id)
+        .bind(Utc::now())
+        .execute(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let response = start_session(&pool, &user, &client).await?;
+    audit::record(&pool, EventType::LoginSucceeded, Some(user.id), &client, serde_json::json!({ "method": "2fa" })).await;
+
+    tracing::info!(user_id = %user.id, username = %user.username, "User logged in successfully with 2FA");
+    Ok(Json(response))
+}
+
+async fn fetch_user(pool: &PgPool, user_id: Uuid) -> Result<User, (StatusCode, String)> {
+    sqlx::query_as::<_, User>(
+        "SELECT * FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching user");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })
+}
+
+/// Код вида `xxxxx-xxxxx`
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 5];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let code = hex::encode(bytes);
+    format!("{}-{}", &code[..5], &code[5..])
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    let code: String = code.trim().to_lowercase().chars().filter(|c| *c != '-').collect();
+    // Срез по байтам безопасен только для ASCII; прочий ввод все равно не совпадет ни с одним кодом
+    if code.is_ascii() && code.len() == 10 {
+        format!("{}-{}", &code[..5], &code[5..])
+    } else {
+        code
+    }
+}
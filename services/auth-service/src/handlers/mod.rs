@@ -1 +1,17 @@
+pub mod account;
+pub mod admin;
 pub mod auth;
+pub mod email;
+pub mod events;
+pub mod export;
+pub mod magic_link;
+pub mod mfa;
+pub mod oauth;
+pub mod oauth_clients;
+pub mod oidc;
+pub mod organizations;
+pub mod password;
+pub mod sessions;
+pub mod tokens;
+pub mod users;
+pub mod verification;
@@ -0,0 +1,795 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode, HeaderMap},
+    response::{IntoResponse, Response},
+    Form, Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{Duration, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::handlers::auth::{authenticate, generate_secret, hash_token};
+use crate::mail;
+use crate::models::oauth::{
+    AuthorizeDecisionRequest, AuthorizeDecisionResponse, AuthorizeInfoResponse, AuthorizeQuery,
+    ConsentResponse, DeviceCodeRequest, DeviceCodeResponse, DeviceDecisionRequest, DeviceInfoResponse,
+    DeviceQuery, OAuthClient, OAuthErrorResponse, OAuthToken, OAuthTokenResponse, TokenRequest,
+    ACCESS_TOKEN_PREFIX, DEVICE_CODE_GRANT, REFRESH_TOKEN_PREFIX,
+};
+use crate::oauth;
+
+/// Ошибка token- и device-endpoint'ов: приложения ждут формат RFC 6749, а не текст
+pub struct OAuthError {
+    status: StatusCode,
+    error: &'static str,
+    description: String,
+}
+
+impl OAuthError {
+    fn new(error: &'static str, description: impl Into<String>) -> Self {
+        let status = match error {
+            "invalid_client" => StatusCode::UNAUTHORIZED,
+            "server_error" => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        Self { status, error, description: description.into() }
+    }
+
+    fn server(e: impl std::fmt::Display) -> Self {
+        tracing::error!(error = %e, "Database error in OAuth endpoint");
+        Self::new("server_error", "Internal server error")
+    }
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(OAuthErrorResponse { error: self.error.to_string(), error_description: self.description }),
+        )
+            .into_response()
+    }
+}
+
+async fn fetch_client(pool: &PgPool, id: Uuid) -> Result<Option<OAuthClient>, sqlx::Error> {
+    sqlx::query_as::<_, OAuthClient>("SELECT * FROM oauth_clients WHERE id = $1 AND revoked_at IS NULL")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Клиент из HTTP Basic или из полей формы. У конфиденциального клиента секрет обязателен.
+async fn authenticate_client(
+    pool: &PgPool,
+    headers: &HeaderMap,
+    client_id: Option<&str>,
+    client_secret: Option<&str>,
+) -> Result<OAuthClient, OAuthError> {
+    let basic = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Basic "))
+        .and_then(|encoded| STANDARD.decode(encoded).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok());
+
+    let (client_id, client_secret) = match &basic {
+        Some(credentials) => match credentials.split_once(':') {
+            Some((id, secret)) => (Some(id), Some(secret)),
+            None => return Err(OAuthError::new("invalid_client", "Malformed client credentials")),
+        },
+        None => (client_id, client_secret),
+    };
+
+    let invalid = || OAuthError::new("invalid_client", "Unknown client or invalid credentials");
+
+    let client_id = client_id.and_then(|id| id.parse::<Uuid>().ok()).ok_or_else(invalid)?;
+    let client = fetch_client(pool, client_id).await.map_err(OAuthError::server)?.ok_or_else(invalid)?;
+
+    if let Some(secret_hash) = &client.secret_hash {
+        if client_secret.map(hash_token).as_ref() != Some(secret_hash) {
+            tracing::warn!(client_id = %client.id, "OAuth client authentication failed");
+            return Err(invalid());
+        }
+    }
+
+    Ok(client)
+}
+
+/// Выдает приложению пару токенов от имени пользователя
+async fn issue_tokens(
+    conn: &mut PgConnection,
+    client_id: Uuid,
+    user_id: Uuid,
+    scopes: &[String],
+) -> Result<OAuthTokenResponse, OAuthError> {
+    // Заблокированный или удаляемый аккаунт не должен получать новые токены
+    let active = sqlx::query_scalar::<_, bool>(
+        "SELECT disabled_at IS NULL AND deletion_scheduled_at IS NULL FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(OAuthError::server)?
+    .unwrap_or(false);
+
+    if !active {
+        return Err(OAuthError::new("invalid_grant", "User account is not active"));
+    }
+
+    let id = Uuid::new_v4();
+    let access_secret = generate_secret();
+    let refresh_secret = generate_secret();
+    let now = Utc::now();
+
+    sqlx::query(
+        "INSERT INTO oauth_tokens (id, client_id, user_id, scopes, access_token_hash, access_expires_at,
+                                   refresh_token_hash, refresh_expires_at, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+    )
+    .bind(id)
+    .bind(client_id)
+    .bind(user_id)
+    .bind(scopes)
+    .bind(hash_token(&access_secret))
+    .bind(now + Duration::minutes(oauth::ACCESS_TOKEN_TTL_MINUTES))
+    .bind(hash_token(&refresh_secret))
+    .bind(now + Duration::days(oauth::REFRESH_TOKEN_TTL_DAYS))
+    .bind(now)
+    .execute(&mut *conn)
+    .await
+    .map_err(OAuthError::server)?;
+
+    tracing::info!(client_id = %client_id, user_id = %user_id, grant_id = %id, scopes = ?scopes, "OAuth tokens issued");
+
+    Ok(token_response(id, &access_secret, &refresh_secret, scopes))
+}
+
+fn token_response(id: Uuid, access_secret: &str, refresh_secret: &str, scopes: &[String]) -> OAuthTokenResponse {
+    OAuthTokenResponse {
+        access_token: format!("{}{}.{}", ACCESS_TOKEN_PREFIX, id, access_secret),
+        token_type: "Bearer".to_string(),
+        expires_in: oauth::ACCESS_TOKEN_TTL_MINUTES * 60,
+        refresh_token: format!("{}{}.{}", REFRESH_TOKEN_PREFIX, id, refresh_secret),
+        scope: scopes.join(" "),
+    }
+}
+
+/// Запоминает, что пользователь разрешил приложению эти права; новые права добавляются к прежним
+async fn record_consent(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    client_id: Uuid,
+    scopes: &[String],
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    sqlx::query(
+        "INSERT INTO oauth_consents (user_id, client_id, scopes, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $4)
+         ON CONFLICT (user_id, client_id) DO UPDATE SET
+            scopes = ARRAY(SELECT DISTINCT unnest(oauth_consents.scopes || EXCLUDED.scopes) ORDER BY 1),
+            updated_at = EXCLUDED.updated_at"
+    )
+    .bind(user_id)
+    .bind(client_id)
+    .bind(scopes)
+    .bind(now)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Проверяет запрос авторизации. Ошибки здесь показываются пользователю,
+/// а не передаются приложению: адресу возврата еще нельзя доверять.
+async fn validate_authorize(
+    pool: &PgPool,
+    params: &AuthorizeQuery,
+) -> Result<(OAuthClient, Vec<String>), (StatusCode, String)> {
+    let client = fetch_client(pool, params.client_id)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error fetching OAuth client");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?
+        .ok_or((StatusCode::BAD_REQUEST, "Unknown client".to_string()))?;
+
+    if !oauth::redirect_uri_allowed(&client, &params.redirect_uri) {
+        tracing::warn!(client_id = %client.id, redirect_uri = %params.redirect_uri, "OAuth authorize rejected: redirect_uri not registered");
+        return Err((StatusCode::BAD_REQUEST, "redirect_uri is not registered for this client".to_string()));
+    }
+
+    if params.response_type != "code" {
+        return Err((StatusCode::BAD_REQUEST, "Only response_type=code is supported".to_string()));
+    }
+
+    // PKCE обязателен для всех клиентов, plain не принимается
+    if params.code_challenge_method != "S256" || !(43..=128).contains(&params.code_challenge.len()) {
+        return Err((StatusCode::BAD_REQUEST, "PKCE with code_challenge_method=S256 is required".to_string()));
+    }
+
+    let scopes = oauth::parse_scopes(params.scope.as_deref(), &client)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    Ok((client, scopes))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/oauth/authorize",
+    params(AuthorizeQuery),
+    responses(
+        (status = 200, description = "Данные для экрана согласия", body = AuthorizeInfoResponse),
+        (status = 400, description = "Неизвестный клиент, незарегистрированный адрес возврата, неверные права или нет PKCE"),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "oauth"
+)]
+pub async fn get_authorize(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Query(params): Query<AuthorizeQuery>,
+) -> Result<Json<AuthorizeInfoResponse>, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+    let (client, scopes) = validate_authorize(&pool, &params).await?;
+
+    let granted = sqlx::query_scalar::<_, Vec<String>>(
+        "SELECT scopes FROM oauth_consents WHERE user_id = $1 AND client_id = $2"
+    )
+    .bind(user_id)
+    .bind(client.id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching OAuth consent");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .unwrap_or_default();
+
+    Ok(Json(AuthorizeInfoResponse {
+        client_id: client.id,
+        client_name: client.name,
+        consented: scopes.iter().all(|scope| granted.contains(scope)),
+        scopes,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/oauth/authorize",
+    request_body = AuthorizeDecisionRequest,
+    responses(
+        (status = 200, description = "Адрес возврата в приложение с кодом или ошибкой", body = AuthorizeDecisionResponse),
+        (status = 400, description = "Неизвестный клиент, незарегистрированный адрес возврата, неверные права или нет PKCE"),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "oauth"
+)]
+pub async fn decide_authorize(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<AuthorizeDecisionRequest>,
+) -> Result<Json<AuthorizeDecisionResponse>, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+    let params = &req.params;
+    let (client, scopes) = validate_authorize(&pool, params).await?;
+    let state = params.state.as_deref();
+
+    if !req.approve {
+        tracing::info!(client_id = %client.id, user_id = %user_id, "OAuth authorization denied by user");
+        let mut query = vec![("error", "access_denied")];
+        query.extend(state.map(|state| ("state", state)));
+        return Ok(Json(AuthorizeDecisionResponse {
+            redirect_url: oauth::redirect_with(&params.redirect_uri, &query),
+        }));
+    }
+
+    let code = generate_secret();
+    let now = Utc::now();
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    record_consent(&mut tx, user_id, client.id, &scopes).await.map_err(|e| {
+        tracing::error!(error = %e, "Database error saving OAuth consent");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    sqlx::query(
+        "INSERT INTO oauth_authorization_codes
+            (code_hash, client_id, user_id, redirect_uri, scopes, code_challenge, created_at, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+    )
+    .bind(hash_token(&code))
+    .bind(client.id)
+    .bind(user_id)
+    .bind(&params.redirect_uri)
+    .bind(&scopes)
+    .bind(&params.code_challenge)
+    .bind(now)
+    .bind(now + Duration::minutes(oauth::AUTHORIZATION_CODE_TTL_MINUTES))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error creating authorization code");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(client_id = %client.id, user_id = %user_id, scopes = ?scopes, "OAuth authorization granted");
+
+    let mut query = vec![("code", code.as_str())];
+    query.extend(state.map(|state| ("state", state)));
+    Ok(Json(AuthorizeDecisionResponse {
+        redirect_url: oauth::redirect_with(&params.redirect_uri, &query),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/oauth/token",
+    request_body(content = TokenRequest, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Токены выданы", body = OAuthTokenResponse),
+        (status = 400, description = "Ошибка RFC 6749: invalid_grant, authorization_pending, slow_down и т.д.", body = OAuthErrorResponse),
+        (status = 401, description = "Неизвестный клиент или неверный секрет", body = OAuthErrorResponse),
+    ),
+    tag = "oauth"
+)]
+pub async fn token(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Form(req): Form<TokenRequest>,
+) -> Result<Response, OAuthError> {
+    let client = authenticate_client(&pool, &headers, req.client_id.as_deref(), req.client_secret.as_deref()).await?;
+
+    let tokens = match req.grant_type.as_str() {
+        "authorization_code" => exchange_code(&pool, &client, &req).await?,
+        "refresh_token" => refresh_tokens(&pool, &client, &req).await?,
+        DEVICE_CODE_GRANT => poll_device_code(&pool, &client, &req).await?,
+        other => {
+            return Err(OAuthError::new("unsupported_grant_type", format!("Unsupported grant_type {}", other)));
+        }
+    };
+
+    // Ответ с токенами не должен оседать в кэшах (RFC 6749, 5.1)
+    Ok(([(header::CACHE_CONTROL, "no-store")], Json(tokens)).into_response())
+}
+
+async fn exchange_code(pool: &PgPool, client: &OAuthClient, req: &TokenRequest) -> Result<OAuthTokenResponse, OAuthError> {
+    let (Some(code), Some(redirect_uri), Some(code_verifier)) =
+        (req.code.as_deref(), req.redirect_uri.as_deref(), req.code_verifier.as_deref())
+    else {
+        return Err(OAuthError::new("invalid_request", "code, redirect_uri and code_verifier are required"));
+    };
+
+    let mut tx = pool.begin().await.map_err(OAuthError::server)?;
+
+    // Код одноразовый: UPDATE ... RETURNING не даст обменять его дважды
+    let grant = sqlx::query_as::<_, (Uuid, String, Vec<String>, String)>(
+        "UPDATE oauth_authorization_codes SET used_at = $3
+         WHERE code_hash = $1 AND client_id = $2 AND used_at IS NULL AND expires_at > $3
+         RETURNING user_id, redirect_uri, scopes, code_challenge"
+    )
+    .bind(hash_token(code))
+    .bind(client.id)
+    .bind(Utc::now())
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(OAuthError::server)?;
+
+    let Some((user_id, expected_redirect_uri, scopes, challenge)) = grant else {
+        tracing::warn!(client_id = %client.id, "OAuth code exchange failed: invalid, used or expired code");
+        return Err(OAuthError::new("invalid_grant", "Invalid or expired authorization code"));
+    };
+
+    let mismatch = if redirect_uri != expected_redirect_uri {
+        Some("redirect_uri does not match the authorization request")
+    } else if oauth::code_challenge(code_verifier) != challenge {
+        Some("PKCE verification failed")
+    } else {
+        None
+    };
+
+    // Неудачная попытка тоже сжигает код, иначе verifier можно было бы подбирать
+    if let Some(reason) = mismatch {
+        tx.commit().await.map_err(OAuthError::server)?;
+        tracing::warn!(client_id = %client.id, user_id = %user_id, reason, "OAuth code exchange failed");
+        return Err(OAuthError::new("invalid_grant", reason));
+    }
+
+    let tokens = issue_tokens(&mut tx, client.id, user_id, &scopes).await?;
+    tx.commit().await.map_err(OAuthError::server)?;
+    Ok(tokens)
+}
+
+/// Refresh-токен ротируется при каждом использовании. Предъявление старого значения
+/// означает утечку, и вся выдача отзывается — так же, как с сессиями.
+async fn refresh_tokens(pool: &PgPool, client: &OAuthClient, req: &TokenRequest) -> Result<OAuthTokenResponse, OAuthError> {
+    let invalid = || OAuthError::new("invalid_grant", "Invalid or expired refresh token");
+
+    let (id, secret) = req.refresh_token.as_deref()
+        .and_then(|token| token.strip_prefix(REFRESH_TOKEN_PREFIX))
+        .and_then(|token| token.split_once('.'))
+        .ok_or_else(invalid)?;
+    let id = id.parse::<Uuid>().map_err(|_| invalid())?;
+
+    let mut tx = pool.begin().await.map_err(OAuthError::server)?;
+
+    let grant = sqlx::query_as::<_, OAuthToken>("SELECT * FROM oauth_tokens WHERE id = $1 AND client_id = $2 FOR UPDATE")
+        .bind(id)
+        .bind(client.id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(OAuthError::server)?
+        .ok_or_else(invalid)?;
+
+    let now = Utc::now();
+    if grant.revoked_at.is_some() || grant.refresh_expires_at <= now {
+        return Err(invalid());
+    }
+
+    if hash_token(secret) != grant.refresh_token_hash {
+        sqlx::query("UPDATE oauth_tokens SET revoked_at = $2 WHERE id = $1")
+            .bind(id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(OAuthError::server)?;
+        tx.commit().await.map_err(OAuthError::server)?;
+
+        tracing::warn!(client_id = %client.id, grant_id = %id, user_id = %grant.user_id, "OAuth refresh token reuse detected, grant revoked");
+        return Err(invalid());
+    }
+
+    let active = sqlx::query_scalar::<_, bool>(
+        "SELECT disabled_at IS NULL AND deletion_scheduled_at IS NULL FROM users WHERE id = $1"
+    )
+    .bind(grant.user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(OAuthError::server)?
+    .unwrap_or(false);
+
+    if !active {
+        return Err(OAuthError::new("invalid_grant", "User account is not active"));
+    }
+
+    let access_secret = generate_secret();
+    let refresh_secret = generate_secret();
+
+    sqlx::query(
+        "UPDATE oauth_tokens SET
+            access_token_hash = $2, access_expires_at = $3,
+            refresh_token_hash = $4, refresh_expires_at = $5
+         WHERE id = $1"
+    )
+    .bind(id)
+    .bind(hash_token(&access_secret))
+    .bind(now + Duration::minutes(oauth::ACCESS_TOKEN_TTL_MINUTES))
+    .bind(hash_token(&refresh_secret))
+    .bind(now + Duration::days(oauth::REFRESH_TOKEN_TTL_DAYS))
+    .execute(&mut *tx)
+    .await
+    .map_err(OAuthError::server)?;
+
+    tx.commit().await.map_err(OAuthError::server)?;
+
+    tracing::info!(client_id = %client.id, grant_id = %id, "OAuth tokens refreshed");
+    Ok(token_response(id, &access_secret, &refresh_secret, &grant.scopes))
+}
+
+async fn poll_device_code(pool: &PgPool, client: &OAuthClient, req: &TokenRequest) -> Result<OAuthTokenResponse, OAuthError> {
+    let device_code = req.device_code.as_deref()
+        .ok_or_else(|| OAuthError::new("invalid_request", "device_code is required"))?;
+
+    let mut tx = pool.begin().await.map_err(OAuthError::server)?;
+
+    let row = sqlx::query_as::<_, (Uuid, String, Option<Uuid>, Vec<String>, i32, Option<chrono::DateTime<Utc>>, chrono::DateTime<Utc>)>(
+        "SELECT id, status, user_id, scopes, interval_seconds, last_polled_at, expires_at
+         FROM oauth_device_codes
+         WHERE device_code_hash = $1 AND client_id = $2
+         FOR UPDATE"
+    )
+    .bind(hash_token(device_code))
+    .bind(client.id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(OAuthError::server)?;
+
+    let Some((id, status, user_id, scopes, interval, last_polled_at, expires_at)) = row else {
+        return Err(OAuthError::new("invalid_grant", "Unknown device code"));
+    };
+
+    let now = Utc::now();
+    if expires_at <= now {
+        return Err(OAuthError::new("expired_token", "Device code has expired"));
+    }
+
+    // Слишком частый опрос: интервал растет, как требует RFC 8628
+    if last_polled_at.is_some_and(|at| now - at < Duration::seconds(interval.into())) {
+        sqlx::query("UPDATE oauth_device_codes SET interval_seconds = interval_seconds + $2, last_polled_at = $3 WHERE id = $1")
+            .bind(id)
+            .bind(oauth::DEVICE_POLL_INTERVAL_SECONDS)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(OAuthError::server)?;
+        tx.commit().await.map_err(OAuthError::server)?;
+        return Err(OAuthError::new("slow_down", "Polling too frequently"));
+    }
+
+    match (status.as_str(), user_id) {
+        ("approved", Some(user_id)) => {
+            sqlx::query("DELETE FROM oauth_device_codes WHERE id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(OAuthError::server)?;
+
+            let tokens = issue_tokens(&mut tx, client.id, user_id, &scopes).await?;
+            tx.commit().await.map_err(OAuthError::server)?;
+            Ok(tokens)
+        }
+        ("denied", _) => {
+            sqlx::query("DELETE FROM oauth_device_codes WHERE id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(OAuthError::server)?;
+            tx.commit().await.map_err(OAuthError::server)?;
+            Err(OAuthError::new("access_denied", "The user denied the request"))
+        }
+        _ => {
+            sqlx::query("UPDATE oauth_device_codes SET last_polled_at = $2 WHERE id = $1")
+                .bind(id)
+                .bind(now)
+                .execute(&mut *tx)
+                .await
+                .map_err(OAuthError::server)?;
+            tx.commit().await.map_err(OAuthError::server)?;
+            Err(OAuthError::new("authorization_pending", "The user has not approved the request yet"))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/oauth/device/code",
+    request_body(content = DeviceCodeRequest, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Код устройства и код для пользователя", body = DeviceCodeResponse),
+        (status = 400, description = "Неверные права", body = OAuthErrorResponse),
+        (status = 401, description = "Неизвестный клиент или неверный секрет", body = OAuthErrorResponse),
+    ),
+    tag = "oauth"
+)]
+pub async fn device_code(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Form(req): Form<DeviceCodeRequest>,
+) -> Result<Json<DeviceCodeResponse>, OAuthError> {
+    let client = authenticate_client(&pool, &headers, req.client_id.as_deref(), req.client_secret.as_deref()).await?;
+    let scopes = oauth::parse_scopes(req.scope.as_deref(), &client)
+        .map_err(|e| OAuthError::new("invalid_scope", e))?;
+
+    let device_code = generate_secret();
+    let user_code = oauth::generate_user_code();
+    let now = Utc::now();
+
+    sqlx::query(
+        "INSERT INTO oauth_device_codes
+            (id, device_code_hash, user_code, client_id, scopes, status, interval_seconds, created_at, expires_at)
+         VALUES ($1, $2, $3, $4, $5, 'pending', $6, $7, $8)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(hash_token(&device_code))
+    .bind(&user_code)
+    .bind(client.id)
+    .bind(&scopes)
+    .bind(oauth::DEVICE_POLL_INTERVAL_SECONDS)
+    .bind(now)
+    .bind(now + Duration::minutes(oauth::DEVICE_CODE_TTL_MINUTES))
+    .execute(&pool)
+    .await
+    .map_err(OAuthError::server)?;
+
+    tracing::info!(client_id = %client.id, scopes = ?scopes, "Device authorization started");
+
+    let verification_uri = format!("{}/device", mail::app_url());
+    Ok(Json(DeviceCodeResponse {
+        device_code,
+        verification_uri_complete: format!("{}?user_code={}", verification_uri, user_code),
+        verification_uri,
+        user_code,
+        expires_in: oauth::DEVICE_CODE_TTL_MINUTES * 60,
+        interval: oauth::DEVICE_POLL_INTERVAL_SECONDS.into(),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/oauth/device",
+    params(DeviceQuery),
+    responses(
+        (status = 200, description = "Какое приложение и какие права запрашивает", body = DeviceInfoResponse),
+        (status = 401, description = "Не авторизован"),
+        (status = 404, description = "Код не найден или истек"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "oauth"
+)]
+pub async fn get_device(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Query(query): Query<DeviceQuery>,
+) -> Result<Json<DeviceInfoResponse>, (StatusCode, String)> {
+    authenticate(&pool, &headers).await?;
+
+    let (client_id, client_name, scopes) = sqlx::query_as::<_, (Uuid, String, Vec<String>)>(
+        "SELECT c.id, c.name, d.scopes FROM oauth_device_codes d
+         JOIN oauth_clients c ON c.id = d.client_id
+         WHERE d.user_code = $1 AND d.status = 'pending' AND d.expires_at > $2 AND c.revoked_at IS NULL"
+    )
+    .bind(oauth::normalize_user_code(&query.user_code))
+    .bind(Utc::now())
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching device code");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or((StatusCode::NOT_FOUND, "Code not found or expired".to_string()))?;
+
+    Ok(Json(DeviceInfoResponse { client_id, client_name, scopes }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/oauth/device",
+    request_body = DeviceDecisionRequest,
+    responses(
+        (status = 204, description = "Решение передано устройству"),
+        (status = 401, description = "Не авторизован"),
+        (status = 404, description = "Код не найден или истек"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "oauth"
+)]
+pub async fn decide_device(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<DeviceDecisionRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let (client_id, scopes) = sqlx::query_as::<_, (Uuid, Vec<String>)>(
+        "UPDATE oauth_device_codes SET status = $3, user_id = $4
+         WHERE user_code = $1 AND status = 'pending' AND expires_at > $2
+         RETURNING client_id, scopes"
+    )
+    .bind(oauth::normalize_user_code(&req.user_code))
+    .bind(Utc::now())
+    .bind(if req.approve { "approved" } else { "denied" })
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error updating device code");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or((StatusCode::NOT_FOUND, "Code not found or expired".to_string()))?;
+
+    if req.approve {
+        record_consent(&mut tx, user_id, client_id, &scopes).await.map_err(|e| {
+            tracing::error!(error = %e, "Database error saving OAuth consent");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    }
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(client_id = %client_id, user_id = %user_id, approved = req.approve, "Device authorization decided");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/oauth/consents",
+    responses(
+        (status = 200, description = "Приложения, которым выдан доступ", body = Vec<ConsentResponse>),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "oauth"
+)]
+pub async fn list_consents(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ConsentResponse>>, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let consents = sqlx::query_as::<_, ConsentResponse>(
+        "SELECT c.id AS client_id, c.name AS client_name, o.scopes, o.created_at, o.updated_at
+         FROM oauth_consents o
+         JOIN oauth_clients c ON c.id = o.client_id
+         WHERE o.user_id = $1 AND c.revoked_at IS NULL
+         ORDER BY o.updated_at DESC"
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching OAuth consents");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(Json(consents))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/oauth/consents/{client_id}",
+    params(("client_id" = Uuid, Path, description = "ID приложения")),
+    responses(
+        (status = 204, description = "Доступ отозван, токены приложения больше не действуют"),
+        (status = 401, description = "Не авторизован"),
+        (status = 404, description = "Приложению не выдавался доступ"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "oauth"
+)]
+pub async fn revoke_consent(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(client_id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let deleted = sqlx::query("DELETE FROM oauth_consents WHERE user_id = $1 AND client_id = $2")
+        .bind(user_id)
+        .bind(client_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error deleting OAuth consent");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?
+        .rows_affected();
+
+    if deleted == 0 {
+        return Err((StatusCode::NOT_FOUND, "Consent not found".to_string()));
+    }
+
+    // tasks-service проверяет OAuth-токены по базе, так что отзыв действует сразу
+    let revoked = sqlx::query(
+        "UPDATE oauth_tokens SET revoked_at = $3 WHERE user_id = $1 AND client_id = $2 AND revoked_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(client_id)
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error revoking OAuth tokens");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .rows_affected();
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(user_id = %user_id, client_id = %client_id, revoked, "OAuth consent revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,170 @@
+use axum::{extract::{Path, State}, http::{StatusCode, HeaderMap}, Json};
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::handlers::admin::authorize;
+use crate::handlers::auth::{generate_secret, hash_token};
+use crate::models::oauth::{ClientResponse, CreateClientRequest, CreatedClientResponse, OAuthClient};
+use crate::models::token::SCOPES;
+use crate::oauth;
+use crate::roles::Permission;
+
+#[utoipa::path(
+    get,
+    path = "/admin/oauth/clients",
+    responses(
+        (status = 200, description = "Зарегистрированные приложения", body = Vec<ClientResponse>),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Недостаточно прав"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "admin"
+)]
+pub async fn list_clients(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ClientResponse>>, (StatusCode, String)> {
+    authorize(&pool, &headers, Permission::ManageOAuthClients).await?;
+
+    let clients = sqlx::query_as::<_, OAuthClient>("SELECT * FROM oauth_clients ORDER BY created_at DESC")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error fetching OAuth clients");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    Ok(Json(clients.into_iter().map(ClientResponse::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/oauth/clients",
+    request_body = CreateClientRequest,
+    responses(
+        (status = 201, description = "Приложение зарегистрировано, секрет показывается один раз", body = CreatedClientResponse),
+        (status = 400, description = "Неверное имя, адреса возврата или права"),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Недостаточно прав"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "admin"
+)]
+pub async fn create_client(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<CreateClientRequest>,
+) -> Result<(StatusCode, Json<CreatedClientResponse>), (StatusCode, String)> {
+    let admin_id = authorize(&pool, &headers, Permission::ManageOAuthClients).await?.user_id()?;
+
+    let name = req.name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err((StatusCode::BAD_REQUEST, "Client name must be 1-100 characters".to_string()));
+    }
+
+    if let Some(uri) = req.redirect_uris.iter().find(|uri| !oauth::valid_redirect_uri(uri)) {
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid redirect URI: {}", uri)));
+    }
+
+    if req.scopes.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "At least one scope is required".to_string()));
+    }
+    if let Some(scope) = req.scopes.iter().find(|s| !SCOPES.contains(&s.as_str())) {
+        return Err((StatusCode::BAD_REQUEST, format!("Unknown scope: {}", scope)));
+    }
+    let mut scopes = req.scopes.clone();
+    scopes.sort();
+    scopes.dedup();
+
+    let secret = req.confidential.then(generate_secret);
+
+    let client = sqlx::query_as::<_, OAuthClient>(
+        "INSERT INTO oauth_clients (id, name, secret_hash, redirect_uris, scopes, created_by, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(name)
+    .bind(secret.as_deref().map(hash_token))
+    .bind(&req.redirect_uris)
+    .bind(&scopes)
+    .bind(admin_id)
+    .bind(Utc::now())
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error creating OAuth client");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    tracing::info!(
+        admin_id = %admin_id,
+        client_id = %client.id,
+        confidential = req.confidential,
+        event = "oauth_client_created",
+        "OAuth client registered"
+    );
+
+    Ok((StatusCode::CREATED, Json(CreatedClientResponse {
+        client_secret: secret,
+        details: client.into(),
+    })))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/oauth/clients/{id}",
+    params(("id" = Uuid, Path, description = "client_id приложения")),
+    responses(
+        (status = 204, description = "Приложение отключено, его токены больше не действуют"),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Недостаточно прав"),
+        (status = 404, description = "Приложение не найдено"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "admin"
+)]
+pub async fn revoke_client(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let admin_id = authorize(&pool, &headers, Permission::ManageOAuthClients).await?.user_id()?;
+    let now = Utc::now();
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let revoked = sqlx::query("UPDATE oauth_clients SET revoked_at = $2 WHERE id = $1 AND revoked_at IS NULL")
+        .bind(id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error revoking OAuth client");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?
+        .rows_affected();
+
+    if revoked == 0 {
+        return Err((StatusCode::NOT_FOUND, "Client not found".to_string()));
+    }
+
+    sqlx::query("UPDATE oauth_tokens SET revoked_at = $2 WHERE client_id = $1 AND revoked_at IS NULL")
+        .bind(id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error revoking OAuth tokens");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::warn!(admin_id = %admin_id, client_id = %id, event = "oauth_client_revoked", "OAuth client revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
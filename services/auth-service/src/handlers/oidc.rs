@@ -0,0 +1,438 @@
+use axum::{extract::{Path, State}, http::{StatusCode, HeaderMap}, Json};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::audit::{self, EventType};
+use crate::handlers::auth::{authenticate, generate_secret, hash_password, hash_token, start_session};
+use crate::handlers::mfa::start_mfa_challenge;
+use crate::handlers::verification::send_verification_email;
+use crate::mail::MailSender;
+use crate::models::oidc::{
+    IdentityResponse, OidcAuthorizeResponse, OidcCallbackRequest, OidcProviderResponse, UserIdentity,
+};
+use crate::models::session::ClientInfo;
+use crate::models::user::{LoginResponse, User};
+use crate::oidc::{self, IdTokenClaims, Provider};
+
+/// Сколько у пользователя есть на вход у провайдера
+const LOGIN_STATE_TTL_MINUTES: i64 = 10;
+/// Попыток подобрать свободное имя для нового аккаунта
+const USERNAME_ATTEMPTS: usize = 5;
+
+fn find_provider(id: &str) -> Result<&'static Provider, (StatusCode, String)> {
+    oidc::provider(id).ok_or((StatusCode::NOT_FOUND, "Unknown identity provider".to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/oidc/providers",
+    responses(
+        (status = 200, description = "Провайдеры, через которых можно войти", body = Vec<OidcProviderResponse>),
+    ),
+    tag = "auth"
+)]
+pub async fn list_providers() -> Json<Vec<OidcProviderResponse>> {
+    Json(oidc::providers().iter().map(|provider| OidcProviderResponse {
+        id: provider.config.id.clone(),
+        name: provider.config.name.clone(),
+    }).collect())
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/oidc/{provider}/authorize",
+    params(("provider" = String, Path, description = "ID провайдера")),
+    responses(
+        (status = 200, description = "Адрес страницы входа провайдера", body = OidcAuthorizeResponse),
+        (status = 404, description = "Провайдер не настроен"),
+        (status = 502, description = "Провайдер недоступен"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    tag = "auth"
+)]
+pub async fn authorize(
+    State(pool): State<PgPool>,
+    Path(provider_id): Path<String>,
+) -> Result<Json<OidcAuthorizeResponse>, (StatusCode, String)> {
+    let provider = find_provider(&provider_id)?;
+
+    let state = oidc::random_token();
+    let nonce = oidc::random_token();
+    let code_verifier = oidc::random_token();
+
+    let authorization_url = provider.authorization_url(&state, &nonce, &code_verifier).await.map_err(|e| {
+        tracing::error!(error = %e, provider = %provider_id, "OIDC discovery failed");
+        (StatusCode::BAD_GATEWAY, "Identity provider is unavailable".to_string())
+    })?;
+
+    let now = Utc::now();
+
+    // Заодно чистим брошенные попытки входа
+    sqlx::query("DELETE FROM oidc_login_states WHERE expires_at <= $1")
+        .bind(now)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error purging OIDC login states");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    sqlx::query(
+        "INSERT INTO oidc_login_states (state_hash, provider, code_verifier, nonce, created_at, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6)"
+    )
+    .bind(hash_token(&state))
+    .bind(&provider_id)
+    .bind(&code_verifier)
+    .bind(&nonce)
+    .bind(now)
+    .bind(now + Duration::minutes(LOGIN_STATE_TTL_MINUTES))
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error saving OIDC login state");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    tracing::info!(provider = %provider_id, "OIDC login started");
+    Ok(Json(OidcAuthorizeResponse { authorization_url, state }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/oidc/{provider}/callback",
+    params(("provider" = String, Path, description = "ID провайдера")),
+    request_body = OidcCallbackRequest,
+    responses(
+        (status = 200, description = "Успешный вход или запрос второго фактора", body = LoginResponse),
+        (status = 400, description = "Попытка входа не найдена или истекла, либо провайдер не вернул email"),
+        (status = 401, description = "Провайдер не подтвердил вход"),
+        (status = 403, description = "Аккаунт заблокирован или регистрация через провайдера запрещена"),
+        (status = 404, description = "Провайдер не настроен"),
+        (status = 409, description = "Аккаунт с этим email уже есть, нужно войти по паролю"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    tag = "auth"
+)]
+pub async fn callback(
+    State(pool): State<PgPool>,
+    State(mailer): State<Arc<dyn MailSender>>,
+    Path(provider_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<OidcCallbackRequest>,
+) -> Result<Json<LoginResponse>, (StatusCode, String)> {
+    let provider = find_provider(&provider_id)?;
+
+    // state одноразовый: повторная отправка того же кода не пройдет
+    let (code_verifier, nonce) = sqlx::query_as::<_, (String, String)>(
+        "DELETE FROM oidc_login_states
+         WHERE state_hash = $1 AND provider = $2 AND expires_at > $3
+         RETURNING code_verifier, nonce"
+    )
+    .bind(hash_token(&req.state))
+    .bind(&provider_id)
+    .bind(Utc::now())
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error consuming OIDC login state");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or_else(|| {
+        tracing::warn!(provider = %provider_id, "OIDC login failed: unknown or expired state");
+        (StatusCode::BAD_REQUEST, "Login attempt expired, start again".to_string())
+    })?;
+
+    let client = ClientInfo::from_headers(&headers);
+
+    let claims = match provider.exchange_code(&req.code, &code_verifier, &nonce).await {
+        Ok(claims) => claims,
+        Err(e) => {
+            tracing::warn!(error = %e, provider = %provider_id, "OIDC login failed");
+            audit::record(&pool, EventType::LoginFailed, None, &client, serde_json::json!({
+                "method": "sso",
+                "provider": provider_id,
+                "reason": "provider_error",
+            })).await;
+            return Err((StatusCode::UNAUTHORIZED, "Identity provider login failed".to_string()));
+        }
+    };
+
+    let user = resolve_user(&pool, mailer, provider, &claims, &client).await?;
+
+    if user.totp_enabled_at.is_some() {
+        let challenge = start_mfa_challenge(&pool, &user).await?;
+        tracing::info!(user_id = %user.id, provider = %provider_id, "SSO accepted, waiting for second factor");
+        return Ok(Json(LoginResponse::MfaRequired(challenge)));
+    }
+
+    let response = start_session(&pool, &user, &client).await?;
+    audit::record(&pool, EventType::LoginSucceeded, Some(user.id), &client, serde_json::json!({
+        "method": "sso",
+        "provider": provider_id,
+    })).await;
+
+    tracing::info!(user_id = %user.id, provider = %provider_id, "User logged in via SSO");
+    Ok(Json(LoginResponse::Authenticated(response)))
+}
+
+/// Аккаунт для внешней учетной записи: уже привязанный, найденный по подтвержденному
+/// email или созданный при первом входе
+async fn resolve_user(
+    pool: &PgPool,
+    mailer: Arc<dyn MailSender>,
+    provider: &Provider,
+    claims: &IdTokenClaims,
+    client: &ClientInfo,
+) -> Result<User, (StatusCode, String)> {
+    let provider_id = provider.config.id.as_str();
+    let now = Utc::now();
+
+    let linked = sqlx::query_as::<_, User>(
+        "SELECT u.* FROM user_identities i
+         JOIN users u ON u.id = i.user_id
+         WHERE i.provider = $1 AND i.subject = $2"
+    )
+    .bind(provider_id)
+    .bind(&claims.sub)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching linked identity");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    if let Some(user) = linked {
+        sqlx::query(
+            "UPDATE user_identities SET last_login_at = $3, email = COALESCE($4, email)
+             WHERE provider = $1 AND subject = $2"
+        )
+        .bind(provider_id)
+        .bind(&claims.sub)
+        .bind(now)
+        .bind(&claims.email)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error updating identity");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+        return Ok(user);
+    }
+
+    let email = claims.email.as_deref().map(str::trim).filter(|email| !email.is_empty()).ok_or_else(|| {
+        tracing::warn!(provider = %provider_id, "OIDC login failed: no email claim");
+        (StatusCode::BAD_REQUEST, "Identity provider did not return an email".to_string())
+    })?;
+
+    let existing = sqlx::query_as::<_, User>("SELECT * FROM users WHERE email = $1")
+        .bind(email)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error fetching user");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if let Some(user) = existing {
+        // Без подтверждения почты провайдером кто угодно завел бы там чужой адрес и вошел в аккаунт
+        if !provider.config.link_by_email || !claims.email_verified() {
+            tracing::warn!(provider = %provider_id, user_id = %user.id, "OIDC login failed: email belongs to an unlinked account");
+            return Err((
+                StatusCode::CONFLICT,
+                "An account with this email already exists, sign in with your password".to_string(),
+            ));
+        }
+
+        let mut conn = pool.acquire().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        link_identity(&mut conn, user.id, provider_id, claims).await?;
+
+        tracing::info!(user_id = %user.id, provider = %provider_id, "External identity linked by email");
+        return Ok(user);
+    }
+
+    if !provider.config.allow_signup {
+        tracing::warn!(provider = %provider_id, "OIDC login failed: sign-up disabled");
+        return Err((StatusCode::FORBIDDEN, "Sign-up via this provider is disabled".to_string()));
+    }
+
+    // Пароль случайный и никому не известен; задать свой можно через сброс пароля
+    let password_hash = hash_password(&generate_secret())?;
+    let email_verified_at = claims.email_verified().then_some(now);
+    let base = username_base(claims, email);
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut created = None;
+    for attempt in 0..USERNAME_ATTEMPTS {
+        let username = if attempt == 0 {
+            base.clone()
+        } else {
+            let suffix = &generate_secret()[..4];
+            format!("{}-{}", base.chars().take(27).collect::<String>(), suffix)
+        };
+
+        // ON CONFLICT без цели не прерывает транзакцию ни на email, ни на имени
+        created = sqlx::query_as::<_, User>(
+            "INSERT INTO users (id, email, username, password_hash, created_at, email_verified_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT DO NOTHING
+             RETURNING *"
+        )
+        .bind(Uuid::new_v4())
+        .bind(email)
+        .bind(&username)
+        .bind(&password_hash)
+        .bind(now)
+        .bind(email_verified_at)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error creating user");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+        if created.is_some() {
+            break;
+        }
+    }
+
+    let user = created.ok_or_else(|| {
+        tracing::warn!(provider = %provider_id, "OIDC sign-up failed: no free username or email taken concurrently");
+        (StatusCode::CONFLICT, "Could not create an account, try again".to_string())
+    })?;
+
+    link_identity(&mut tx, user.id, provider_id, claims).await?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if user.email_verified_at.is_none() {
+        send_verification_email(pool, mailer, &user).await?;
+    }
+
+    audit::record(pool, EventType::Register, Some(user.id), client, serde_json::json!({
+        "method": "sso",
+        "provider": provider_id,
+    })).await;
+
+    tracing::info!(user_id = %user.id, provider = %provider_id, "User registered via SSO");
+    Ok(user)
+}
+
+async fn link_identity(
+    conn: &mut sqlx::PgConnection,
+    user_id: Uuid,
+    provider_id: &str,
+    claims: &IdTokenClaims,
+) -> Result<(), (StatusCode, String)> {
+    let now = Utc::now();
+
+    sqlx::query(
+        "INSERT INTO user_identities (id, user_id, provider, subject, email, created_at, last_login_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $6)
+         ON CONFLICT (provider, subject) DO NOTHING"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(provider_id)
+    .bind(&claims.sub)
+    .bind(&claims.email)
+    .bind(now)
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error linking identity");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(())
+}
+
+/// Имя для нового аккаунта из профиля провайдера, приведенное к правилам `validate_username`
+fn username_base(claims: &IdTokenClaims, email: &str) -> String {
+    let source = claims.preferred_username.as_deref()
+        .or(claims.name.as_deref())
+        .unwrap_or_else(|| email.split('@').next().unwrap_or_default());
+
+    let mut username: String = source.trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '_' | '.' | '-') { c } else { '_' })
+        .take(32)
+        .collect();
+
+    if username.chars().count() < 3 {
+        username = format!("user_{}", &generate_secret()[..6]);
+    }
+    username
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/identities",
+    responses(
+        (status = 200, description = "Привязанные внешние учетные записи", body = Vec<IdentityResponse>),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn list_identities(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<IdentityResponse>>, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let identities = sqlx::query_as::<_, UserIdentity>(
+        "SELECT * FROM user_identities WHERE user_id = $1 ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching identities");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(Json(identities.into_iter().map(IdentityResponse::from).collect()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/identities/{id}",
+    params(("id" = Uuid, Path, description = "ID привязки")),
+    responses(
+        (status = 204, description = "Привязка удалена"),
+        (status = 401, description = "Не авторизован"),
+        (status = 404, description = "Привязка не найдена"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn delete_identity(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let result = sqlx::query("DELETE FROM user_identities WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error deleting identity");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Identity not found".to_string()));
+    }
+
+    tracing::info!(user_id = %user_id, identity_id = %id, "External identity unlinked");
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,842 @@
+use axum::{extract::{Path, Query, State}, http::{StatusCode, HeaderMap}, Json};
+use chrono::{Duration, Utc};
+use sqlx::{PgConnection, PgPool};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::handlers::auth::{authenticate, create_token, generate_secret, hash_token};
+use crate::handlers::email::is_valid_email;
+use crate::mail::{self, Email, MailSender};
+use crate::models::organization::{
+    AcceptInviteRequest, CreateInviteRequest, CreateOrganizationRequest, InvitePreviewResponse, InviteResponse,
+    InviteTokenQuery, MemberResponse, Organization, OrganizationInvite, OrganizationResponse,
+    SwitchOrganizationRequest, SwitchOrganizationResponse, TransferOwnershipRequest, UpdateMemberRoleRequest,
+};
+use crate::models::user::User;
+use crate::roles::OrgRole;
+
+const INVITE_TTL_DAYS: i64 = 7;
+const NAME_MAX_LEN: usize = 100;
+
+/// Роль пользователя в организации; 404, если он в ней не состоит,
+/// чтобы не выдавать существование чужих организаций
+async fn membership(
+    conn: &mut PgConnection,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<OrgRole, (StatusCode, String)> {
+    let role = sqlx::query_scalar::<_, String>(
+        "SELECT role FROM organization_members WHERE organization_id = $1 AND user_id = $2"
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching membership");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or((StatusCode::NOT_FOUND, "Organization not found".to_string()))?;
+
+    Ok(OrgRole::parse(&role).unwrap_or(OrgRole::Member))
+}
+
+async fn require_manager(
+    conn: &mut PgConnection,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<OrgRole, (StatusCode, String)> {
+    let role = membership(conn, organization_id, user_id).await?;
+    if !role.can_manage_members() {
+        tracing::warn!(user_id = %user_id, organization_id = %organization_id, "Organization action rejected: not an admin");
+        return Err((StatusCode::FORBIDDEN, "Insufficient permissions".to_string()));
+    }
+    Ok(role)
+}
+
+/// Бывший участник не должен оставаться в организации ни в одной сессии.
+/// Уже выданные access-токены с этим `org_id` доживают свои минуты.
+async fn detach_sessions(
+    conn: &mut PgConnection,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), (StatusCode, String)> {
+    sqlx::query("UPDATE sessions SET organization_id = NULL WHERE user_id = $1 AND organization_id = $2")
+        .bind(user_id)
+        .bind(organization_id)
+        .execute(conn)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error detaching sessions from organization");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<(), (StatusCode, String)> {
+    if name.is_empty() || name.chars().count() > NAME_MAX_LEN {
+        return Err((StatusCode::BAD_REQUEST, format!("Organization name must be 1-{} characters", NAME_MAX_LEN)));
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/orgs",
+    request_body = CreateOrganizationRequest,
+    responses(
+        (status = 201, description = "Организация создана, текущий пользователь — владелец", body = OrganizationResponse),
+        (status = 400, description = "Недопустимое название"),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "organizations"
+)]
+pub async fn create_organization(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<CreateOrganizationRequest>,
+) -> Result<(StatusCode, Json<OrganizationResponse>), (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+    let name = req.name.trim();
+    validate_name(name)?;
+
+    let now = Utc::now();
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let organization = sqlx::query_as::<_, Organization>(
+        "INSERT INTO organizations (id, name, created_at) VALUES ($1, $2, $3) RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(name)
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error creating organization");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    sqlx::query(
+        "INSERT INTO organization_members (organization_id, user_id, role, joined_at) VALUES ($1, $2, $3, $4)"
+    )
+    .bind(organization.id)
+    .bind(user_id)
+    .bind(OrgRole::Owner.as_str())
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error adding organization owner");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(user_id = %user_id, organization_id = %organization.id, "Organization created");
+    Ok((StatusCode::CREATED, Json(OrganizationResponse {
+        id: organization.id,
+        name: organization.name,
+        role: OrgRole::Owner.as_str().to_string(),
+        created_at: organization.created_at,
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/orgs",
+    responses(
+        (status = 200, description = "Организации, в которых состоит пользователь", body = Vec<OrganizationResponse>),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "organizations"
+)]
+pub async fn list_organizations(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<OrganizationResponse>>, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let organizations = sqlx::query_as::<_, OrganizationResponse>(
+        "SELECT o.id, o.name, m.role, o.created_at FROM organizations o
+         JOIN organization_members m ON m.organization_id = o.id
+         WHERE m.user_id = $1
+         ORDER BY o.name"
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error listing organizations");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(Json(organizations))
+}
+
+#[utoipa::path(
+    get,
+    path = "/orgs/{id}/members",
+    params(("id" = Uuid, Path, description = "ID организации")),
+    responses(
+        (status = 200, description = "Участники организации", body = Vec<MemberResponse>),
+        (status = 401, description = "Не авторизован"),
+        (status = 404, description = "Организация не найдена или пользователь в ней не состоит"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "organizations"
+)]
+pub async fn list_members(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<MemberResponse>>, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+    let mut conn = pool.acquire().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    membership(&mut conn, id, user_id).await?;
+
+    let members = sqlx::query_as::<_, MemberResponse>(
+        "SELECT u.id AS user_id, u.username, u.email, m.role, m.joined_at
+         FROM organization_members m
+         JOIN users u ON u.id = m.user_id
+         WHERE m.organization_id = $1
+         ORDER BY m.joined_at"
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error listing organization members");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(Json(members))
+}
+
+#[utoipa::path(
+    put,
+    path = "/orgs/{id}/members/{user_id}/role",
+    params(
+        ("id" = Uuid, Path, description = "ID организации"),
+        ("user_id" = Uuid, Path, description = "ID участника"),
+    ),
+    request_body = UpdateMemberRoleRequest,
+    responses(
+        (status = 204, description = "Роль изменена"),
+        (status = 400, description = "Неизвестная роль или попытка изменить роль владельца"),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Менять роли может только владелец"),
+        (status = 404, description = "Организация или участник не найдены"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "organizations"
+)]
+pub async fn update_member_role(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path((id, member_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<UpdateMemberRoleRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let role = OrgRole::parse(&req.role)
+        .filter(|role| *role != OrgRole::Owner)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("Unknown role: {}", req.role)))?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if membership(&mut tx, id, user_id).await? != OrgRole::Owner {
+        return Err((StatusCode::FORBIDDEN, "Only the owner can change roles".to_string()));
+    }
+    if member_id == user_id {
+        return Err((StatusCode::BAD_REQUEST, "Transfer ownership to change your own role".to_string()));
+    }
+
+    let updated = sqlx::query(
+        "UPDATE organization_members SET role = $3 WHERE organization_id = $1 AND user_id = $2"
+    )
+    .bind(id)
+    .bind(member_id)
+    .bind(role.as_str())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error updating member role");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .rows_affected();
+
+    if updated == 0 {
+        return Err((StatusCode::NOT_FOUND, "Member not found".to_string()));
+    }
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(organization_id = %id, user_id = %member_id, role = role.as_str(), changed_by = %user_id, "Organization role changed");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/orgs/{id}/members/{user_id}",
+    params(
+        ("id" = Uuid, Path, description = "ID организации"),
+        ("user_id" = Uuid, Path, description = "ID участника; свой ID — выйти из организации"),
+    ),
+    responses(
+        (status = 204, description = "Участник исключен или вышел сам"),
+        (status = 400, description = "Владелец не может выйти, не передав владение"),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Недостаточно прав, чтобы исключить этого участника"),
+        (status = 404, description = "Организация или участник не найдены"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "organizations"
+)]
+pub async fn remove_member(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path((id, member_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let actor_role = membership(&mut tx, id, user_id).await?;
+    let member_role = membership(&mut tx, id, member_id).await
+        .map_err(|_| (StatusCode::NOT_FOUND, "Member not found".to_string()))?;
+
+    if member_role == OrgRole::Owner {
+        return Err((StatusCode::BAD_REQUEST, "The owner cannot leave or be removed, transfer ownership first".to_string()));
+    }
+    // Выйти может любой участник, исключить — только тот, чья роль выше
+    if member_id != user_id && !(actor_role.can_manage_members() && actor_role.outranks(member_role)) {
+        tracing::warn!(user_id = %user_id, organization_id = %id, member_id = %member_id, "Member removal rejected: insufficient role");
+        return Err((StatusCode::FORBIDDEN, "Insufficient permissions".to_string()));
+    }
+
+    sqlx::query("DELETE FROM organization_members WHERE organization_id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(member_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error removing member");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    detach_sessions(&mut tx, id, member_id).await?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(organization_id = %id, user_id = %member_id, removed_by = %user_id, "Organization member removed");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/orgs/{id}/transfer-ownership",
+    params(("id" = Uuid, Path, description = "ID организации")),
+    request_body = TransferOwnershipRequest,
+    responses(
+        (status = 204, description = "Владение передано"),
+        (status = 400, description = "Нельзя передать владение самому себе"),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Передать владение может только владелец"),
+        (status = 404, description = "Организация или участник не найдены"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "organizations"
+)]
+pub async fn transfer_ownership(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(req): Json<TransferOwnershipRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    if req.user_id == user_id {
+        return Err((StatusCode::BAD_REQUEST, "You already own this organization".to_string()));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Блокируем строку владельца, чтобы две передачи не прошли одновременно
+    let owner = sqlx::query_scalar::<_, Uuid>(
+        "SELECT user_id FROM organization_members WHERE organization_id = $1 AND role = $2 FOR UPDATE"
+    )
+    .bind(id)
+    .bind(OrgRole::Owner.as_str())
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching organization owner");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    if owner != Some(user_id) {
+        membership(&mut tx, id, user_id).await?;
+        return Err((StatusCode::FORBIDDEN, "Only the owner can transfer ownership".to_string()));
+    }
+
+    membership(&mut tx, id, req.user_id).await
+        .map_err(|_| (StatusCode::NOT_FOUND, "Member not found".to_string()))?;
+
+    // Сначала понижаем прежнего владельца: владелец у организации один (уникальный индекс)
+    for (member, role) in [(user_id, OrgRole::Admin), (req.user_id, OrgRole::Owner)] {
+        sqlx::query("UPDATE organization_members SET role = $3 WHERE organization_id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(member)
+            .bind(role.as_str())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!(error = %e, "Database error transferring ownership");
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            })?;
+    }
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::warn!(organization_id = %id, from = %user_id, to = %req.user_id, event = "ownership_transferred", "Organization ownership transferred");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/orgs/{id}/invites",
+    params(("id" = Uuid, Path, description = "ID организации")),
+    request_body = CreateInviteRequest,
+    responses(
+        (status = 201, description = "Приглашение отправлено на почту", body = InviteResponse),
+        (status = 400, description = "Неверный email или роль"),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Приглашать могут владелец и администраторы"),
+        (status = 404, description = "Организация не найдена"),
+        (status = 409, description = "Пользователь уже состоит в организации"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "organizations"
+)]
+pub async fn create_invite(
+    State(pool): State<PgPool>,
+    State(mailer): State<Arc<dyn MailSender>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(req): Json<CreateInviteRequest>,
+) -> Result<(StatusCode, Json<InviteResponse>), (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+    let email = req.email.trim();
+
+    if !is_valid_email(email) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid email address".to_string()));
+    }
+
+    let role = match req.role.as_deref() {
+        None => OrgRole::Member,
+        Some(role) => OrgRole::parse(role)
+            .filter(|role| *role != OrgRole::Owner)
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("Unknown role: {}", role)))?,
+    };
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let actor_role = require_manager(&mut tx, id, user_id).await?;
+    if !actor_role.outranks(role) {
+        return Err((StatusCode::FORBIDDEN, "Only the owner can invite admins".to_string()));
+    }
+
+    let already_member = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(
+            SELECT 1 FROM organization_members m JOIN users u ON u.id = m.user_id
+            WHERE m.organization_id = $1 AND lower(u.email) = lower($2)
+        )"
+    )
+    .bind(id)
+    .bind(email)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error checking membership");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    if already_member {
+        return Err((StatusCode::CONFLICT, "User is already a member".to_string()));
+    }
+
+    let organization_name = sqlx::query_scalar::<_, String>("SELECT name FROM organizations WHERE id = $1")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error fetching organization");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    let now = Utc::now();
+
+    // Повторное приглашение на тот же адрес заменяет прежнее
+    sqlx::query(
+        "UPDATE organization_invites SET revoked_at = $3
+         WHERE organization_id = $1 AND lower(email) = lower($2) AND accepted_at IS NULL AND revoked_at IS NULL"
+    )
+    .bind(id)
+    .bind(email)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error revoking previous invites");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    let token = generate_secret();
+    let invite = sqlx::query_as::<_, OrganizationInvite>(
+        "INSERT INTO organization_invites (id, organization_id, email, role, token_hash, invited_by, created_at, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(id)
+    .bind(email)
+    .bind(role.as_str())
+    .bind(hash_token(&token))
+    .bind(user_id)
+    .bind(now)
+    .bind(now + Duration::days(INVITE_TTL_DAYS))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error creating invite");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    mail::send_in_background(mailer, Email {
+        to: invite.email.clone(),
+        subject: format!("Приглашение в {} на Taspla", organization_name),
+        body: format!(
+            "Здравствуйте!\n\nВас пригласили в организацию «{}» на Taspla. Чтобы присоединиться, перейдите по ссылке:\n\
+             {}/invites/accept?token={}\n\n\
+             Приглашение действует {} дней. Принять его можно из аккаунта с этим адресом почты.",
+            organization_name,
+            mail::app_url(),
+            token,
+            INVITE_TTL_DAYS,
+        ),
+    });
+
+    tracing::info!(organization_id = %id, invite_id = %invite.id, invited_by = %user_id, role = role.as_str(), "Organization invite sent");
+    Ok((StatusCode::CREATED, Json(invite.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/orgs/{id}/invites",
+    params(("id" = Uuid, Path, description = "ID организации")),
+    responses(
+        (status = 200, description = "Неиспользованные приглашения", body = Vec<InviteResponse>),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Приглашения видят владелец и администраторы"),
+        (status = 404, description = "Организация не найдена"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "organizations"
+)]
+pub async fn list_invites(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<InviteResponse>>, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+    let mut conn = pool.acquire().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    require_manager(&mut conn, id, user_id).await?;
+
+    let invites = sqlx::query_as::<_, OrganizationInvite>(
+        "SELECT * FROM organization_invites
+         WHERE organization_id = $1 AND accepted_at IS NULL AND revoked_at IS NULL AND expires_at > $2
+         ORDER BY created_at DESC"
+    )
+    .bind(id)
+    .bind(Utc::now())
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error listing invites");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(Json(invites.into_iter().map(InviteResponse::from).collect()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/orgs/{id}/invites/{invite_id}",
+    params(
+        ("id" = Uuid, Path, description = "ID организации"),
+        ("invite_id" = Uuid, Path, description = "ID приглашения"),
+    ),
+    responses(
+        (status = 204, description = "Приглашение отозвано"),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Отзывать приглашения могут владелец и администраторы"),
+        (status = 404, description = "Организация или приглашение не найдены"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "organizations"
+)]
+pub async fn revoke_invite(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path((id, invite_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+    let mut conn = pool.acquire().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    require_manager(&mut conn, id, user_id).await?;
+
+    let revoked = sqlx::query(
+        "UPDATE organization_invites SET revoked_at = $3
+         WHERE id = $1 AND organization_id = $2 AND accepted_at IS NULL AND revoked_at IS NULL"
+    )
+    .bind(invite_id)
+    .bind(id)
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error revoking invite");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .rows_affected();
+
+    if revoked == 0 {
+        return Err((StatusCode::NOT_FOUND, "Invite not found".to_string()));
+    }
+
+    tracing::info!(organization_id = %id, invite_id = %invite_id, revoked_by = %user_id, "Organization invite revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/invites",
+    params(InviteTokenQuery),
+    responses(
+        (status = 200, description = "Куда приглашают", body = InvitePreviewResponse),
+        (status = 401, description = "Не авторизован"),
+        (status = 404, description = "Приглашение не найдено, истекло или уже использовано"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "organizations"
+)]
+pub async fn get_invite(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Query(query): Query<InviteTokenQuery>,
+) -> Result<Json<InvitePreviewResponse>, (StatusCode, String)> {
+    authenticate(&pool, &headers).await?;
+
+    let preview = sqlx::query_as::<_, InvitePreviewResponse>(
+        "SELECT o.id AS organization_id, o.name AS organization_name, i.email, i.role, i.expires_at
+         FROM organization_invites i
+         JOIN organizations o ON o.id = i.organization_id
+         WHERE i.token_hash = $1 AND i.accepted_at IS NULL AND i.revoked_at IS NULL AND i.expires_at > $2"
+    )
+    .bind(hash_token(&query.token))
+    .bind(Utc::now())
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching invite");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or((StatusCode::NOT_FOUND, "Invite not found or expired".to_string()))?;
+
+    Ok(Json(preview))
+}
+
+#[utoipa::path(
+    post,
+    path = "/invites/accept",
+    request_body = AcceptInviteRequest,
+    responses(
+        (status = 200, description = "Пользователь добавлен в организацию", body = OrganizationResponse),
+        (status = 401, description = "Не авторизован"),
+        (status = 403, description = "Приглашение отправлено на другой адрес или почта не подтверждена"),
+        (status = 404, description = "Приглашение не найдено, истекло или уже использовано"),
+        (status = 409, description = "Пользователь уже состоит в организации"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "organizations"
+)]
+pub async fn accept_invite(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<AcceptInviteRequest>,
+) -> Result<Json<OrganizationResponse>, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+    let now = Utc::now();
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let invite = sqlx::query_as::<_, OrganizationInvite>(
+        "SELECT * FROM organization_invites
+         WHERE token_hash = $1 AND accepted_at IS NULL AND revoked_at IS NULL AND expires_at > $2
+         FOR UPDATE"
+    )
+    .bind(hash_token(&req.token))
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching invite");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or_else(|| {
+        tracing::warn!(user_id = %user_id, "Invite acceptance failed: invalid or expired token");
+        (StatusCode::NOT_FOUND, "Invite not found or expired".to_string())
+    })?;
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error fetching user");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    // Ссылку могли переслать: принять ее может только владелец приглашенного адреса
+    if !user.email.eq_ignore_ascii_case(&invite.email) {
+        tracing::warn!(user_id = %user_id, invite_id = %invite.id, "Invite acceptance failed: email mismatch");
+        return Err((StatusCode::FORBIDDEN, "This invite was sent to a different email address".to_string()));
+    }
+    if user.email_verified_at.is_none() {
+        return Err((StatusCode::FORBIDDEN, "Verify your email to accept the invite".to_string()));
+    }
+
+    let joined = sqlx::query(
+        "INSERT INTO organization_members (organization_id, user_id, role, joined_at)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (organization_id, user_id) DO NOTHING"
+    )
+    .bind(invite.organization_id)
+    .bind(user_id)
+    .bind(&invite.role)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error adding member");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .rows_affected();
+
+    if joined == 0 {
+        return Err((StatusCode::CONFLICT, "You are already a member".to_string()));
+    }
+
+    sqlx::query("UPDATE organization_invites SET accepted_at = $2 WHERE id = $1")
+        .bind(invite.id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error accepting invite");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    let organization = sqlx::query_as::<_, Organization>("SELECT * FROM organizations WHERE id = $1")
+        .bind(invite.organization_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error fetching organization");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(organization_id = %organization.id, user_id = %user_id, role = %invite.role, "Organization invite accepted");
+    Ok(Json(OrganizationResponse {
+        id: organization.id,
+        name: organization.name,
+        role: invite.role,
+        created_at: organization.created_at,
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/auth/session/organization",
+    request_body = SwitchOrganizationRequest,
+    responses(
+        (status = 200, description = "Активная организация сессии изменена", body = SwitchOrganizationResponse),
+        (status = 401, description = "Не авторизован"),
+        (status = 404, description = "Организация не найдена или пользователь в ней не состоит"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "organizations"
+)]
+pub async fn switch_organization(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<SwitchOrganizationRequest>,
+) -> Result<Json<SwitchOrganizationResponse>, (StatusCode, String)> {
+    let claims = authenticate(&pool, &headers).await?;
+    let user_id = claims.user_id()?;
+    let session_id = claims.session_id()?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if let Some(organization_id) = req.organization_id {
+        membership(&mut tx, organization_id, user_id).await?;
+    }
+
+    // Выбор хранится в сессии, чтобы refresh выдавал токены той же организации
+    sqlx::query("UPDATE sessions SET organization_id = $3 WHERE id = $1 AND user_id = $2")
+        .bind(session_id)
+        .bind(user_id)
+        .bind(req.organization_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error switching organization");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error fetching user");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let token = create_token(&user, session_id, req.organization_id)?;
+
+    tracing::info!(user_id = %user_id, session_id = %session_id, organization_id = ?req.organization_id, "Active organization switched");
+    Ok(Json(SwitchOrganizationResponse {
+        token,
+        organization_id: req.organization_id,
+    }))
+}
@@ -0,0 +1,213 @@
+use axum::{extract::State, http::{StatusCode, HeaderMap}, Json};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::audit::{self, EventType};
+use crate::handlers::auth::{generate_secret, hash_password, hash_token};
+use crate::mail::{self, Email, MailSender};
+use crate::models::session::ClientInfo;
+use crate::models::user::{ForgotPasswordRequest, ResetPasswordRequest, User};
+use crate::passwords;
+use crate::revocation;
+use crate::throttle;
+
+const RESET_TOKEN_TTL_MINUTES: i64 = 60;
+/// Не чаще одного письма в минуту на пользователя
+const RESET_COOLDOWN_SECONDS: i64 = 60;
+
+#[utoipa::path(
+    post,
+    path = "/auth/password/forgot",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 202, description = "Если аккаунт существует, на почту отправлена ссылка для сброса"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    tag = "auth"
+)]
+pub async fn forgot_password(
+    State(pool): State<PgPool>,
+    State(mailer): State<Arc<dyn MailSender>>,
+    Json(req): Json<ForgotPasswordRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    tracing::info!(email = %req.email, "Password reset requested");
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users WHERE email = $1"
+    )
+    .bind(&req.email)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching user");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    // Ответ одинаковый независимо от того, есть ли такой аккаунт
+    let Some(user) = user else {
+        tracing::warn!(email = %req.email, "Password reset requested for unknown email");
+        return Ok(StatusCode::ACCEPTED);
+    };
+
+    let recently_sent = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM password_reset_tokens WHERE user_id = $1 AND created_at > $2)"
+    )
+    .bind(user.id)
+    .bind(Utc::now() - Duration::seconds(RESET_COOLDOWN_SECONDS))
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error checking reset tokens");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    // Без ограничения эндпоинт позволял бы засыпать чужой ящик письмами.
+    // Ответ тот же, чтобы по нему нельзя было понять, есть ли аккаунт
+    if recently_sent {
+        tracing::warn!(user_id = %user.id, "Password reset throttled");
+        return Ok(StatusCode::ACCEPTED);
+    }
+
+    send_reset_link(&pool, mailer, user).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/password/reset",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 204, description = "Пароль изменен, все сессии завершены"),
+        (status = 400, description = "Токен сброса невалиден, истек или уже использован, либо пароль не проходит политику"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    tag = "auth"
+)]
+pub async fn reset_password(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<ResetPasswordRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    passwords::check_policy(&req.new_password).await?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let user_id = sqlx::query_scalar::<_, Uuid>(
+        "SELECT user_id FROM password_reset_tokens
+         WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2
+         FOR UPDATE"
+    )
+    .bind(hash_token(&req.token))
+    .bind(Utc::now())
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching reset token");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or_else(|| {
+        tracing::warn!("Password reset failed: invalid or expired token");
+        (StatusCode::BAD_REQUEST, "Invalid or expired reset token".to_string())
+    })?;
+
+    tracing::info!(user_id = %user_id, "Resetting password");
+
+    let new_password_hash = hash_password(&req.new_password)?;
+
+    let email = sqlx::query_scalar::<_, String>(
+        "UPDATE users SET password_hash = $1 WHERE id = $2 RETURNING email"
+    )
+    .bind(&new_password_hash)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error updating password");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    // Гасим и этот токен, и все остальные неиспользованные ссылки пользователя
+    sqlx::query(
+        "UPDATE password_reset_tokens SET used_at = $2 WHERE user_id = $1 AND used_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error consuming reset tokens");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    let revoked = revocation::revoke_user_sessions(&mut tx, user_id, None)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error revoking sessions");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    // Владелец подтвердил доступ к почте, блокировку входа по подбору можно снять
+    throttle::clear(&mut tx, &throttle::account_key(&email))
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error clearing login throttle");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let client = ClientInfo::from_headers(&headers);
+    audit::record(&pool, EventType::PasswordReset, Some(user_id), &client, serde_json::json!({})).await;
+    audit::record(&pool, EventType::SessionRevoked, Some(user_id), &client, serde_json::json!({
+        "count": revoked,
+        "reason": "password_reset",
+    })).await;
+
+    tracing::info!(user_id = %user_id, revoked, event = "login_unlocked", "Password reset successfully");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Выпускает токен сброса и отправляет ссылку пользователю.
+/// Используется и самим пользователем, и поддержкой из админки.
+pub async fn send_reset_link(
+    pool: &PgPool,
+    mailer: Arc<dyn MailSender>,
+    user: User,
+) -> Result<(), (StatusCode, String)> {
+    let token = generate_secret();
+    let now = Utc::now();
+
+    sqlx::query(
+        "INSERT INTO password_reset_tokens (id, user_id, token_hash, created_at, expires_at)
+         VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user.id)
+    .bind(hash_token(&token))
+    .bind(now)
+    .bind(now + Duration::minutes(RESET_TOKEN_TTL_MINUTES))
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error creating reset token");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    mail::send_in_background(mailer, Email {
+        to: user.email,
+        subject: "Сброс пароля Taspla".to_string(),
+        body: format!(
+            "Здравствуйте, {}!\n\nЧтобы задать новый пароль, перейдите по ссылке:\n{}/reset-password?token={}\n\n\
+             Ссылка действует {} минут. Если вы не запрашивали сброс, просто проигнорируйте это письмо.",
+            user.username,
+            mail::app_url(),
+            token,
+            RESET_TOKEN_TTL_MINUTES,
+        ),
+    });
+
+    tracing::info!(user_id = %user.id, "Password reset token issued");
+    Ok(())
+}
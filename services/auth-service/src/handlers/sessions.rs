@@ -0,0 +1,110 @@
+use axum::{extract::{Path, State}, http::{StatusCode, HeaderMap}, Json};
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::handlers::auth::authenticate;
+use crate::audit::{self, EventType};
+use crate::models::session::{ClientInfo, Session, SessionResponse};
+use crate::revocation;
+
+#[utoipa::path(
+    get,
+    path = "/auth/sessions",
+    responses(
+        (status = 200, description = "Активные сессии пользователя", body = Vec<SessionResponse>),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn list_sessions(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<SessionResponse>>, (StatusCode, String)> {
+    let claims = authenticate(&pool, &headers).await?;
+    let user_id = claims.user_id()?;
+    let current_session_id = claims.session_id()?;
+
+    let sessions = sqlx::query_as::<_, Session>(
+        "SELECT * FROM sessions
+         WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > $2
+         ORDER BY last_seen_at DESC"
+    )
+    .bind(user_id)
+    .bind(Utc::now())
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching sessions");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(Json(sessions.into_iter().map(|session| SessionResponse {
+        current: session.id == current_session_id,
+        id: session.id,
+        user_agent: session.user_agent,
+        ip_address: session.ip_address,
+        created_at: session.created_at,
+        last_seen_at: session.last_seen_at,
+    }).collect()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/sessions/{id}",
+    params(("id" = Uuid, Path, description = "ID сессии")),
+    responses(
+        (status = 204, description = "Сессия завершена"),
+        (status = 401, description = "Не авторизован"),
+        (status = 404, description = "Сессия не найдена"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn delete_session(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    tracing::info!(user_id = %user_id, session_id = %id, "Revoking session");
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let exists = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM sessions WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL)"
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching session");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    if !exists {
+        return Err((StatusCode::NOT_FOUND, "Session not found".to_string()));
+    }
+
+    revocation::revoke_session(&mut tx, id, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error revoking session");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    audit::record(&pool, EventType::SessionRevoked, Some(user_id), &ClientInfo::from_headers(&headers), serde_json::json!({
+        "session_id": id,
+        "reason": "user_revoked",
+    })).await;
+
+    tracing::info!(user_id = %user_id, session_id = %id, "Session revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
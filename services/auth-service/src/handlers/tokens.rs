@@ -0,0 +1,162 @@
+use axum::{extract::{Path, State}, http::{StatusCode, HeaderMap}, Json};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::handlers::auth::{authenticate, generate_secret, hash_token};
+use crate::models::token::{
+    CreateTokenRequest, CreatedTokenResponse, PersonalAccessToken, TokenResponse, SCOPES, TOKEN_PREFIX,
+};
+
+const DEFAULT_TOKEN_TTL_DAYS: i64 = 90;
+const MAX_TOKEN_TTL_DAYS: i64 = 365;
+
+#[utoipa::path(
+    post,
+    path = "/auth/tokens",
+    request_body = CreateTokenRequest,
+    responses(
+        (status = 201, description = "Токен создан, значение показывается один раз", body = CreatedTokenResponse),
+        (status = 400, description = "Неверное имя, права или срок действия"),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn create_token(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<CreateTokenRequest>,
+) -> Result<(StatusCode, Json<CreatedTokenResponse>), (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let name = req.name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err((StatusCode::BAD_REQUEST, "Token name must be 1-100 characters".to_string()));
+    }
+
+    if req.scopes.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "At least one scope is required".to_string()));
+    }
+    if let Some(scope) = req.scopes.iter().find(|s| !SCOPES.contains(&s.as_str())) {
+        return Err((StatusCode::BAD_REQUEST, format!("Unknown scope: {}", scope)));
+    }
+    let mut scopes = req.scopes.clone();
+    scopes.sort();
+    scopes.dedup();
+
+    let ttl_days = req.expires_in_days.unwrap_or(DEFAULT_TOKEN_TTL_DAYS);
+    if !(1..=MAX_TOKEN_TTL_DAYS).contains(&ttl_days) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Token lifetime must be between 1 and {} days", MAX_TOKEN_TTL_DAYS),
+        ));
+    }
+
+    let id = Uuid::new_v4();
+    let secret = generate_secret();
+    let now = Utc::now();
+
+    let token = sqlx::query_as::<_, PersonalAccessToken>(
+        "INSERT INTO personal_access_tokens (id, user_id, name, token_hash, scopes, created_at, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING *"
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(name)
+    .bind(hash_token(&secret))
+    .bind(&scopes)
+    .bind(now)
+    .bind(now + Duration::days(ttl_days))
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error creating personal access token");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    tracing::info!(user_id = %user_id, token_id = %id, scopes = ?scopes, "Personal access token created");
+
+    Ok((StatusCode::CREATED, Json(CreatedTokenResponse {
+        token: format!("{}{}.{}", TOKEN_PREFIX, id, secret),
+        details: token.into(),
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/tokens",
+    responses(
+        (status = 200, description = "Действующие персональные токены", body = Vec<TokenResponse>),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn list_tokens(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<TokenResponse>>, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let tokens = sqlx::query_as::<_, PersonalAccessToken>(
+        "SELECT * FROM personal_access_tokens
+         WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > $2
+         ORDER BY created_at DESC"
+    )
+    .bind(user_id)
+    .bind(Utc::now())
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching personal access tokens");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(Json(tokens.into_iter().map(TokenResponse::from).collect()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/tokens/{id}",
+    params(("id" = Uuid, Path, description = "ID токена")),
+    responses(
+        (status = 204, description = "Токен отозван"),
+        (status = 401, description = "Не авторизован"),
+        (status = 404, description = "Токен не найден"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn revoke_token(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let result = sqlx::query(
+        "UPDATE personal_access_tokens SET revoked_at = $3
+         WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(Utc::now())
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error revoking personal access token");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Token not found".to_string()));
+    }
+
+    tracing::info!(user_id = %user_id, token_id = %id, "Personal access token revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
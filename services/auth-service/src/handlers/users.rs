@@ -0,0 +1,273 @@
+use axum::{extract::{Path, Query, State}, http::{StatusCode, HeaderMap}, Json};
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::audit::{self, EventType};
+use crate::handlers::auth::authenticate;
+use crate::models::session::ClientInfo;
+use crate::models::user::{
+    PublicProfileListResponse, PublicProfileResponse, UpdateAvatarRequest, User, UserProfileResponse,
+    UsernameSearchQuery,
+};
+
+const USERNAME_MIN_CHARS: usize = 3;
+const USERNAME_MAX_CHARS: usize = 32;
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 50;
+const AVATAR_URL_MAX_LEN: usize = 2048;
+
+/// Имя пользователя: 3–32 символа, буквы любого алфавита, цифры, `_`, `.` и `-`.
+/// Уникальность без учета регистра проверяет индекс в базе.
+pub fn validate_username(username: &str) -> Result<(), (StatusCode, String)> {
+    let length = username.chars().count();
+    if !(USERNAME_MIN_CHARS..=USERNAME_MAX_CHARS).contains(&length) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Username must be {}-{} characters", USERNAME_MIN_CHARS, USERNAME_MAX_CHARS),
+        ));
+    }
+
+    if !username.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-')) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Username may contain only letters, digits, '_', '.' and '-'".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Нарушение уникального индекса на имени пользователя превращается в 409
+pub fn map_username_conflict(e: sqlx::Error) -> (StatusCode, String) {
+    let conflict = e.as_database_error()
+        .and_then(|db| db.constraint())
+        .is_some_and(|constraint| constraint == "idx_users_username_lower");
+
+    if conflict {
+        (StatusCode::CONFLICT, "Username already taken".to_string())
+    } else {
+        tracing::error!(error = %e, "Database error saving username");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    }
+}
+
+async fn fetch_current_user(pool: &PgPool, headers: &HeaderMap) -> Result<User, (StatusCode, String)> {
+    let user_id = authenticate(pool, headers).await?.user_id()?;
+
+    sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error fetching user");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/me",
+    responses(
+        (status = 200, description = "Профиль текущего пользователя", body = UserProfileResponse),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "users"
+)]
+pub async fn get_me(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<Json<UserProfileResponse>, (StatusCode, String)> {
+    Ok(Json(fetch_current_user(&pool, &headers).await?.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{id}",
+    params(("id" = Uuid, Path, description = "ID пользователя")),
+    responses(
+        (status = 200, description = "Публичный профиль", body = PublicProfileResponse),
+        (status = 401, description = "Не авторизован"),
+        (status = 404, description = "Пользователь не найден"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "users"
+)]
+pub async fn get_user(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PublicProfileResponse>, (StatusCode, String)> {
+    authenticate(&pool, &headers).await?;
+
+    // Заблокированные и ожидающие удаления аккаунты не показываем
+    let user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users
+         WHERE id = $1 AND disabled_at IS NULL AND deletion_scheduled_at IS NULL"
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching user");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    Ok(Json(user.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users",
+    params(UsernameSearchQuery),
+    responses(
+        (status = 200, description = "Пользователи по имени; сначала совпадения с начала имени", body = PublicProfileListResponse),
+        (status = 400, description = "Пустой запрос"),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "users"
+)]
+pub async fn search_users(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Query(query): Query<UsernameSearchQuery>,
+) -> Result<Json<PublicProfileListResponse>, (StatusCode, String)> {
+    authenticate(&pool, &headers).await?;
+
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Search query is required".to_string()));
+    }
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    // % и _ в запросе ищутся буквально
+    let escaped = q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    let contains = format!("%{}%", escaped);
+    let prefix = format!("{}%", escaped);
+
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM users
+         WHERE username ILIKE $1 AND disabled_at IS NULL AND deletion_scheduled_at IS NULL"
+    )
+    .bind(&contains)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error counting users");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    let users = sqlx::query_as::<_, User>(
+        "SELECT * FROM users
+         WHERE username ILIKE $1 AND disabled_at IS NULL AND deletion_scheduled_at IS NULL
+         ORDER BY username ILIKE $2 DESC, lower(username), id
+         LIMIT $3 OFFSET $4"
+    )
+    .bind(&contains)
+    .bind(&prefix)
+    .bind(per_page)
+    .bind((page - 1) * per_page)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error searching users");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(Json(PublicProfileListResponse {
+        items: users.into_iter().map(PublicProfileResponse::from).collect(),
+        total,
+        page,
+        per_page,
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/users/me/avatar",
+    request_body = UpdateAvatarRequest,
+    responses(
+        (status = 200, description = "Аватар обновлен", body = UserProfileResponse),
+        (status = 400, description = "Ссылка должна быть https и не длиннее 2048 символов"),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "users"
+)]
+pub async fn update_avatar(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(req): Json<UpdateAvatarRequest>,
+) -> Result<Json<UserProfileResponse>, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let url = req.url.trim();
+    let valid = url.len() <= AVATAR_URL_MAX_LEN
+        && url.strip_prefix("https://").is_some_and(|rest| !rest.is_empty() && !rest.contains(char::is_whitespace));
+    if !valid {
+        return Err((StatusCode::BAD_REQUEST, "Avatar URL must be an https link".to_string()));
+    }
+
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET avatar_url = $2, avatar_updated_at = $3 WHERE id = $1 RETURNING *"
+    )
+    .bind(user_id)
+    .bind(url)
+    .bind(Utc::now())
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error updating avatar");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    audit::record(&pool, EventType::ProfileUpdated, Some(user_id), &ClientInfo::from_headers(&headers), serde_json::json!({
+        "fields": ["avatar"],
+    })).await;
+
+    tracing::info!(user_id = %user_id, "Avatar updated");
+    Ok(Json(user.into()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/me/avatar",
+    responses(
+        (status = 204, description = "Аватар удален"),
+        (status = 401, description = "Не авторизован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "users"
+)]
+pub async fn delete_avatar(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    sqlx::query("UPDATE users SET avatar_url = NULL, avatar_updated_at = NULL WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Database error deleting avatar");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    audit::record(&pool, EventType::ProfileUpdated, Some(user_id), &ClientInfo::from_headers(&headers), serde_json::json!({
+        "fields": ["avatar"],
+    })).await;
+
+    tracing::info!(user_id = %user_id, "Avatar removed");
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,183 @@
+use axum::{extract::State, http::{StatusCode, HeaderMap}, Json};
+use chrono::{Duration, Utc};
+use sqlx::{PgConnection, PgPool};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::handlers::auth::{authenticate, generate_secret, hash_token};
+use crate::mail::{self, Email, MailSender};
+use crate::models::user::{User, VerifyEmailRequest};
+
+const VERIFICATION_TOKEN_TTL_HOURS: i64 = 48;
+/// Не чаще одного письма в минуту на пользователя
+const RESEND_COOLDOWN_SECONDS: i64 = 60;
+
+/// Выпускает токен подтверждения и отправляет письмо со ссылкой
+pub async fn send_verification_email(
+    pool: &PgPool,
+    mailer: Arc<dyn MailSender>,
+    user: &User,
+) -> Result<(), (StatusCode, String)> {
+    let mut conn = pool.acquire().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let token = issue_verification_token(&mut conn, user.id).await?;
+    queue_verification_email(mailer, user, &token);
+    Ok(())
+}
+
+/// Выпускает токен подтверждения. Принимает соединение, чтобы токен мог создаваться
+/// в одной транзакции с пользователем; письмо отправляется уже после коммита
+pub async fn issue_verification_token(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<String, (StatusCode, String)> {
+    let token = generate_secret();
+    let now = Utc::now();
+
+    sqlx::query(
+        "INSERT INTO email_verification_tokens (id, user_id, token_hash, created_at, expires_at)
+         VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(hash_token(&token))
+    .bind(now)
+    .bind(now + Duration::hours(VERIFICATION_TOKEN_TTL_HOURS))
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error creating verification token");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(token)
+}
+
+/// Ставит письмо со ссылкой подтверждения в очередь отправки
+pub fn queue_verification_email(mailer: Arc<dyn MailSender>, user: &User, token: &str) {
+    mail::send_in_background(mailer, Email {
+        to: user.email.clone(),
+        subject: "Подтверждение почты Taspla".to_string(),
+        body: format!(
+            "Здравствуйте, {}!\n\nПодтвердите адрес почты, перейдя по ссылке:\n{}/verify-email?token={}\n\n\
+             Ссылка действует {} часов.",
+            user.username,
+            mail::app_url(),
+            token,
+            VERIFICATION_TOKEN_TTL_HOURS,
+        ),
+    });
+
+    tracing::info!(user_id = %user.id, "Verification email queued");
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify-email",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 204, description = "Почта подтверждена"),
+        (status = 400, description = "Токен невалиден, истек или уже использован"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    tag = "auth"
+)]
+pub async fn verify_email(
+    State(pool): State<PgPool>,
+    Json(req): Json<VerifyEmailRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let user_id = sqlx::query_scalar::<_, Uuid>(
+        "UPDATE email_verification_tokens SET used_at = $2
+         WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2
+         RETURNING user_id"
+    )
+    .bind(hash_token(&req.token))
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error consuming verification token");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or_else(|| {
+        tracing::warn!("Email verification failed: invalid or expired token");
+        (StatusCode::BAD_REQUEST, "Invalid or expired verification token".to_string())
+    })?;
+
+    sqlx::query(
+        "UPDATE users SET email_verified_at = COALESCE(email_verified_at, $2) WHERE id = $1"
+    )
+    .bind(user_id)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error marking email verified");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Флаг email_verified попадет в access-токен при следующем /auth/refresh
+    tracing::info!(user_id = %user_id, "Email verified successfully");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify-email/resend",
+    responses(
+        (status = 202, description = "Письмо отправлено повторно"),
+        (status = 401, description = "Не авторизован"),
+        (status = 409, description = "Почта уже подтверждена"),
+        (status = 429, description = "Письмо уже отправлялось недавно"),
+        (status = 500, description = "Внутренняя ошибка сервера"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "auth"
+)]
+pub async fn resend_verification(
+    State(pool): State<PgPool>,
+    State(mailer): State<Arc<dyn MailSender>>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = authenticate(&pool, &headers).await?.user_id()?;
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error fetching user");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    if user.email_verified_at.is_some() {
+        return Err((StatusCode::CONFLICT, "Email already verified".to_string()));
+    }
+
+    let recently_sent = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM email_verification_tokens WHERE user_id = $1 AND created_at > $2)"
+    )
+    .bind(user_id)
+    .bind(Utc::now() - Duration::seconds(RESEND_COOLDOWN_SECONDS))
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error checking verification tokens");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    if recently_sent {
+        tracing::warn!(user_id = %user_id, "Verification resend throttled");
+        return Err((StatusCode::TOO_MANY_REQUESTS, "Verification email was sent recently".to_string()));
+    }
+
+    send_verification_email(&pool, mailer, &user).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
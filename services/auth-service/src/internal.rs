@@ -0,0 +1,52 @@
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::keys;
+
+/// Сервисный токен живет ровно столько, сколько нужно на один вызов
+const SERVICE_TOKEN_TTL_SECONDS: i64 = 60;
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Claims токена для вызова внутреннего API другого сервиса. Подписывается тем же ключом,
+/// что и пользовательские токены; от них отличается обязательными `iss` и `aud`.
+#[derive(Serialize)]
+struct ServiceClaims<'a> {
+    iss: &'a str,
+    sub: &'a str,
+    aud: &'a str,
+    exp: usize,
+}
+
+fn service_token(audience: &str) -> Result<String, String> {
+    let claims = ServiceClaims {
+        iss: "auth-service",
+        sub: "auth-service",
+        aud: audience,
+        exp: (Utc::now() + Duration::seconds(SERVICE_TOKEN_TTL_SECONDS)).timestamp() as usize,
+    };
+    keys::sign(&claims).map_err(|e| e.to_string())
+}
+
+fn tasks_service_url() -> String {
+    std::env::var("TASKS_SERVICE_URL").unwrap_or_else(|_| "http://localhost:3002".to_string())
+}
+
+/// Задачи и настройки пользователя из tasks-service
+pub async fn fetch_tasks_data(user_id: Uuid) -> Result<serde_json::Value, String> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    client
+        .get(format!("{}/internal/users/{}/export", tasks_service_url(), user_id))
+        .bearer_auth(service_token("tasks-service")?)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("tasks-service request failed: {}", e))?
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("tasks-service returned invalid data: {}", e))
+}
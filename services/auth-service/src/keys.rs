@@ -0,0 +1,133 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ed25519_dalek::{
+    pkcs8::{DecodePrivateKey, EncodePrivateKey},
+    SigningKey,
+};
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, path::Path, sync::OnceLock};
+use uuid::Uuid;
+
+/// Ключи подписи JWT (EdDSA / Ed25519).
+///
+/// Ключи читаются из `JWT_KEYS_DIR`: каждый `<kid>.pem` — приватный ключ в PKCS#8.
+/// Все ключи из каталога публикуются в JWKS и принимаются при проверке, а подписывает
+/// только `JWT_ACTIVE_KID` (по умолчанию — последний по имени). Ротация без простоя:
+/// положить новый ключ, дождаться, пока сервисы перечитают JWKS, переключить
+/// `JWT_ACTIVE_KID`, а старый ключ удалить не раньше, чем истекут выданные им токены.
+///
+/// Без `JWT_KEYS_DIR` генерируется временный ключ: после рестарта access-токены
+/// перестают проходить проверку, и клиенты обновляют их через refresh.
+static KEY_RING: OnceLock<KeyRing> = OnceLock::new();
+
+struct KeyEntry {
+    kid: String,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    /// Публичный ключ в base64url, параметр `x` в JWK
+    public_x: String,
+}
+
+struct KeyRing {
+    keys: Vec<KeyEntry>,
+    active: usize,
+}
+
+pub fn init() {
+    let ring = match std::env::var("JWT_KEYS_DIR") {
+        Ok(dir) => load_dir(Path::new(&dir)),
+        Err(_) => {
+            tracing::warn!("JWT_KEYS_DIR is not set, using an ephemeral signing key");
+            let key = SigningKey::generate(&mut rand::rngs::OsRng);
+            // Уникальный kid, чтобы сервисы не держали в кэше ключ прошлого запуска под тем же именем
+            KeyRing { keys: vec![entry(format!("ephemeral-{}", Uuid::new_v4()), &key)], active: 0 }
+        }
+    };
+
+    tracing::info!(
+        active_kid = %ring.keys[ring.active].kid,
+        keys = ring.keys.len(),
+        "JWT signing keys loaded"
+    );
+    KEY_RING.set(ring).ok().expect("Signing keys are already initialized");
+}
+
+fn ring() -> &'static KeyRing {
+    KEY_RING.get().expect("Signing keys are not initialized")
+}
+
+fn load_dir(dir: &Path) -> KeyRing {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .expect("Failed to read JWT_KEYS_DIR")
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pem"))
+        .collect();
+    paths.sort();
+
+    let keys: Vec<KeyEntry> = paths.iter().map(|path| {
+        let kid = path.file_stem().unwrap().to_string_lossy().to_string();
+        let pem = fs::read_to_string(path).expect("Failed to read JWT signing key");
+        let key = SigningKey::from_pkcs8_pem(&pem)
+            .unwrap_or_else(|e| panic!("Invalid Ed25519 key {}: {}", path.display(), e));
+        entry(kid, &key)
+    }).collect();
+
+    assert!(!keys.is_empty(), "JWT_KEYS_DIR contains no .pem keys");
+
+    let active = match std::env::var("JWT_ACTIVE_KID") {
+        Ok(kid) => keys.iter().position(|k| k.kid == kid)
+            .unwrap_or_else(|| panic!("JWT_ACTIVE_KID {} not found in JWT_KEYS_DIR", kid)),
+        Err(_) => keys.len() - 1,
+    };
+
+    KeyRing { keys, active }
+}
+
+fn entry(kid: String, key: &SigningKey) -> KeyEntry {
+    let der = key.to_pkcs8_der().expect("Failed to encode signing key");
+    let public = key.verifying_key().to_bytes();
+
+    KeyEntry {
+        kid,
+        encoding: EncodingKey::from_ed_der(der.as_bytes()),
+        decoding: DecodingKey::from_ed_der(&public),
+        public_x: URL_SAFE_NO_PAD.encode(public),
+    }
+}
+
+/// Подписывает claims активным ключом, `kid` попадает в заголовок
+pub fn sign<T: Serialize>(claims: &T) -> Result<String, jsonwebtoken::errors::Error> {
+    let ring = ring();
+    let key = &ring.keys[ring.active];
+
+    let mut header = Header::new(Algorithm::EdDSA);
+    header.kid = Some(key.kid.clone());
+
+    encode(&header, claims, &key.encoding)
+}
+
+/// Проверяет подпись ключом, указанным в `kid`, и срок действия
+pub fn verify<T: DeserializeOwned>(token: &str) -> Result<T, jsonwebtoken::errors::Error> {
+    let kid = decode_header(token)?.kid
+        .ok_or(jsonwebtoken::errors::ErrorKind::InvalidToken)?;
+
+    let key = ring().keys.iter()
+        .find(|k| k.kid == kid)
+        .ok_or(jsonwebtoken::errors::ErrorKind::InvalidToken)?;
+
+    decode::<T>(token, &key.decoding, &Validation::new(Algorithm::EdDSA)).map(|data| data.claims)
+}
+
+/// Публичные ключи в формате JWK Set
+pub fn jwks() -> serde_json::Value {
+    let keys: Vec<_> = ring().keys.iter().map(|key| serde_json::json!({
+        "kty": "OKP",
+        "crv": "Ed25519",
+        "alg": "EdDSA",
+        "use": "sig",
+        "kid": key.kid,
+        "x": key.public_x,
+    })).collect();
+
+    serde_json::json!({ "keys": keys })
+}
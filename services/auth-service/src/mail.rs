@@ -0,0 +1,147 @@
+use async_trait::async_trait;
+use lettre::{
+    message::Mailbox,
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use std::{env, path::PathBuf, sync::Arc};
+use tokio::io::AsyncWriteExt;
+
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Отправка писем. Реализация выбирается через `MAIL_TRANSPORT`:
+/// `smtp` — настоящий SMTP-сервер (или локальный mailpit), `log` — лог/файл для разработки.
+/// Другие значения, в том числе пустое, — ошибка конфигурации: иначе ссылки со
+/// секретами молча уходили бы в лог вместо почты.
+#[async_trait]
+pub trait MailSender: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), String>;
+}
+
+pub fn from_env() -> Arc<dyn MailSender> {
+    match env::var("MAIL_TRANSPORT").as_deref() {
+        Ok("smtp") => Arc::new(SmtpMailSender::from_env()),
+        Ok("log") => Arc::new(LogMailSender::from_env()),
+        _ => panic!("MAIL_TRANSPORT must be set to `smtp` or `log`"),
+    }
+}
+
+/// Отправляет письмо в фоне, чтобы время ответа не зависело от почтового сервера
+pub fn send_in_background(mailer: Arc<dyn MailSender>, email: Email) {
+    tokio::spawn(async move {
+        let to = email.to.clone();
+        match mailer.send(email).await {
+            Ok(()) => tracing::info!(to = %to, "Email sent"),
+            Err(e) => tracing::error!(error = %e, to = %to, "Failed to send email"),
+        }
+    });
+}
+
+/// Базовый адрес фронтенда для ссылок в письмах
+pub fn app_url() -> String {
+    env::var("APP_URL").unwrap_or_else(|_| "http://localhost".to_string())
+}
+
+pub struct SmtpMailSender {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailSender {
+    /// SMTP_TLS: `none` (локальный mailpit), `starttls` или `tls`
+    pub fn from_env() -> Self {
+        let host = env::var("SMTP_HOST").expect("SMTP_HOST must be set when MAIL_TRANSPORT=smtp");
+        let tls = env::var("SMTP_TLS").unwrap_or_else(|_| "starttls".to_string());
+
+        let mut builder = match tls.as_str() {
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host),
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&host)
+                .expect("Failed to configure SMTP relay"),
+            _ => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)
+                .expect("Failed to configure SMTP relay"),
+        };
+
+        if let Ok(port) = env::var("SMTP_PORT") {
+            builder = builder.port(port.parse().expect("SMTP_PORT must be a number"));
+        }
+
+        if let (Ok(username), Ok(password)) = (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD")) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        tracing::info!(host = %host, tls = %tls, "Using SMTP mail sender");
+        Self {
+            transport: builder.build(),
+            from: mail_from(),
+        }
+    }
+}
+
+#[async_trait]
+impl MailSender for SmtpMailSender {
+    async fn send(&self, email: Email) -> Result<(), String> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(email.to.parse::<Mailbox>().map_err(|e| e.to_string())?)
+            .subject(email.subject)
+            .body(email.body)
+            .map_err(|e| e.to_string())?;
+
+        self.transport
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Для локальной разработки: пишет письма в лог и, если задан `MAIL_SINK_PATH`, дописывает их в файл.
+/// Тело письма содержит токены, поэтому в лог оно попадает только на уровне debug
+pub struct LogMailSender {
+    path: Option<PathBuf>,
+}
+
+impl LogMailSender {
+    pub fn from_env() -> Self {
+        let path = env::var("MAIL_SINK_PATH").ok().map(PathBuf::from);
+        tracing::info!(path = ?path, "Using log mail sender");
+        Self { path }
+    }
+}
+
+#[async_trait]
+impl MailSender for LogMailSender {
+    async fn send(&self, email: Email) -> Result<(), String> {
+        tracing::info!(to = %email.to, subject = %email.subject, "Outgoing email");
+        tracing::debug!(to = %email.to, body = %email.body, "Outgoing email body");
+
+        if let Some(path) = &self.path {
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let entry = format!(
+                "To: {}\nSubject: {}\n\n{}\n---\n",
+                email.to, email.subject, email.body
+            );
+            file.write_all(entry.as_bytes()).await.map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn mail_from() -> Mailbox {
+    env::var("MAIL_FROM")
+        .unwrap_or_else(|_| "Taspla <no-reply@taspla.local>".to_string())
+        .parse()
+        .expect("MAIL_FROM must be a valid mailbox")
+}
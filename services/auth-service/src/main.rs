@@ -1,34 +1,200 @@
-use axum::{routing::{get, post, put}, Router};
-use sqlx::postgres::PgPoolOptions;
+use axum::{extract::FromRef, routing::{delete, get, post, put}, Router};
+use sqlx::{postgres::PgPoolOptions, PgPool};
 use dotenvy::dotenv;
-use std::env;
+use std::{env, sync::Arc};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 mod models;
+mod audit;
+mod deletion;
+mod export;
 mod handlers;
+mod internal;
+mod keys;
+mod mail;
+mod oauth;
+mod oidc;
+mod passwords;
+mod revocation;
+mod roles;
+mod throttle;
+mod totp;
+
+use mail::MailSender;
+
+#[derive(Clone)]
+struct AppState {
+    pool: PgPool,
+    mailer: Arc<dyn MailSender>,
+}
+
+impl FromRef<AppState> for PgPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn MailSender> {
+    fn from_ref(state: &AppState) -> Self {
+        state.mailer.clone()
+    }
+}
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         handlers::auth::register,
         handlers::auth::login,
+        handlers::auth::refresh,
         handlers::auth::verify_token,
+        handlers::auth::jwks,
+        handlers::auth::logout,
+        handlers::auth::logout_all,
         handlers::auth::update_profile,
         handlers::auth::change_password,
+        handlers::password::forgot_password,
+        handlers::magic_link::request_magic_link,
+        handlers::magic_link::consume_magic_link,
+        handlers::password::reset_password,
+        handlers::verification::verify_email,
+        handlers::verification::resend_verification,
+        handlers::email::request_email_change,
+        handlers::email::confirm_email_change,
+        handlers::oidc::list_providers,
+        handlers::oidc::authorize,
+        handlers::oidc::callback,
+        handlers::oidc::list_identities,
+        handlers::oidc::delete_identity,
+        handlers::oauth::get_authorize,
+        handlers::oauth::decide_authorize,
+        handlers::oauth::token,
+        handlers::oauth::device_code,
+        handlers::oauth::get_device,
+        handlers::oauth::decide_device,
+        handlers::oauth::list_consents,
+        handlers::oauth::revoke_consent,
+        handlers::mfa::setup_totp,
+        handlers::mfa::confirm_totp,
+        handlers::mfa::disable_totp,
+        handlers::mfa::login_mfa,
+        handlers::sessions::list_sessions,
+        handlers::sessions::delete_session,
+        handlers::tokens::create_token,
+        handlers::tokens::list_tokens,
+        handlers::tokens::revoke_token,
+        handlers::account::delete_account,
+        handlers::export::create_export,
+        handlers::export::get_export,
+        handlers::export::download_export,
+        handlers::admin::list_users,
+        handlers::admin::get_user,
+        handlers::admin::disable_user,
+        handlers::admin::enable_user,
+        handlers::admin::reset_user_password,
+        handlers::admin::update_role,
+        handlers::oauth_clients::list_clients,
+        handlers::oauth_clients::create_client,
+        handlers::oauth_clients::revoke_client,
+        handlers::events::list_my_events,
+        handlers::events::admin_list_events,
+        handlers::organizations::create_organization,
+        handlers::organizations::list_organizations,
+        handlers::organizations::list_members,
+        handlers::organizations::update_member_role,
+        handlers::organizations::remove_member,
+        handlers::organizations::transfer_ownership,
+        handlers::organizations::create_invite,
+        handlers::organizations::list_invites,
+        handlers::organizations::revoke_invite,
+        handlers::organizations::get_invite,
+        handlers::organizations::accept_invite,
+        handlers::organizations::switch_organization,
+        handlers::users::get_me,
+        handlers::users::get_user,
+        handlers::users::search_users,
+        handlers::users::update_avatar,
+        handlers::users::delete_avatar,
     ),
     components(
         schemas(
             models::user::RegisterRequest,
             models::user::LoginRequest,
             models::user::AuthResponse,
+            models::user::LoginResponse,
+            models::mfa::TotpSetupResponse,
+            models::mfa::TotpCodeRequest,
+            models::mfa::RecoveryCodesResponse,
+            models::mfa::DisableTotpRequest,
+            models::mfa::MfaLoginRequest,
+            models::mfa::MfaRequiredResponse,
+            models::session::RefreshRequest,
+            models::session::SessionResponse,
+            models::token::CreateTokenRequest,
+            models::token::TokenResponse,
+            models::token::CreatedTokenResponse,
             models::user::UpdateProfileRequest,
             models::user::ChangePasswordRequest,
+            models::user::ForgotPasswordRequest,
+            models::user::MagicLinkRequest,
+            models::user::MagicLinkResponse,
+            models::user::ConsumeMagicLinkRequest,
+            models::user::ResetPasswordRequest,
+            models::user::VerifyEmailRequest,
+            models::user::ChangeEmailRequest,
+            models::user::ConfirmEmailChangeRequest,
+            models::oidc::OidcProviderResponse,
+            models::oidc::OidcAuthorizeResponse,
+            models::oidc::OidcCallbackRequest,
+            models::oidc::IdentityResponse,
+            models::audit::AuthEventResponse,
+            models::audit::AuthEventListResponse,
+            models::oauth::CreateClientRequest,
+            models::oauth::ClientResponse,
+            models::oauth::CreatedClientResponse,
+            models::oauth::AuthorizeQuery,
+            models::oauth::AuthorizeInfoResponse,
+            models::oauth::AuthorizeDecisionRequest,
+            models::oauth::AuthorizeDecisionResponse,
+            models::oauth::TokenRequest,
+            models::oauth::OAuthTokenResponse,
+            models::oauth::DeviceCodeRequest,
+            models::oauth::DeviceCodeResponse,
+            models::oauth::DeviceInfoResponse,
+            models::oauth::DeviceDecisionRequest,
+            models::oauth::ConsentResponse,
+            models::oauth::OAuthErrorResponse,
+            models::organization::CreateOrganizationRequest,
+            models::organization::OrganizationResponse,
+            models::organization::MemberResponse,
+            models::organization::UpdateMemberRoleRequest,
+            models::organization::TransferOwnershipRequest,
+            models::organization::CreateInviteRequest,
+            models::organization::InviteResponse,
+            models::organization::InvitePreviewResponse,
+            models::organization::AcceptInviteRequest,
+            models::organization::SwitchOrganizationRequest,
+            models::organization::SwitchOrganizationResponse,
             models::user::UpdateProfileResponse,
+            models::user::DeleteAccountRequest,
+            models::user::DeleteAccountResponse,
+            models::export::DataExportResponse,
+            models::admin::AdminUserResponse,
+            models::admin::UserListResponse,
+            models::admin::UpdateRoleRequest,
+            models::user::Avatar,
+            models::user::UserProfileResponse,
+            models::user::PublicProfileResponse,
+            models::user::PublicProfileListResponse,
+            models::user::UpdateAvatarRequest,
         )
     ),
     tags(
-        (name = "auth", description = "Аутентификация и авторизация")
+        (name = "auth", description = "Аутентификация и авторизация"),
+        (name = "users", description = "Профили пользователей"),
+        (name = "oauth", description = "Доступ сторонних приложений (OAuth 2.0)"),
+        (name = "organizations", description = "Организации, участники и приглашения"),
+        (name = "admin", description = "Администрирование пользователей")
     ),
     info(
         title = "Auth Service API",
@@ -74,6 +240,10 @@ async fn main() {
     
     tracing::info!("Auth service starting...");
 
+    keys::init();
+    passwords::init();
+    oidc::init();
+
     let pool = PgPoolOptions::new()
         .max_connections(5)
         .connect(&database_url)
@@ -93,14 +263,627 @@ async fn main() {
     .await
     .expect("Failed to create users table");
 
+    // Пользователи, зарегистрированные до появления подтверждения, считаются подтвержденными,
+    // иначе политика limited сделает их read-only. Заполняем только при добавлении колонки,
+    // чтобы рестарт не подтверждал новые аккаунты.
+    sqlx::query(
+        "DO $$
+         BEGIN
+             IF NOT EXISTS (
+                 SELECT 1 FROM information_schema.columns
+                 WHERE table_schema = current_schema() AND table_name = 'users' AND column_name = 'email_verified_at'
+             ) THEN
+                 ALTER TABLE users ADD COLUMN email_verified_at TIMESTAMPTZ;
+                 UPDATE users SET email_verified_at = created_at WHERE email_verified_at IS NULL;
+             END IF;
+         END
+         $$"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to add email_verified_at to users table");
+
+    sqlx::query(
+        "ALTER TABLE users
+            ADD COLUMN IF NOT EXISTS totp_secret_encrypted TEXT,
+            ADD COLUMN IF NOT EXISTS totp_enabled_at TIMESTAMPTZ,
+            ADD COLUMN IF NOT EXISTS totp_last_step BIGINT"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to add TOTP columns to users table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS sessions (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            refresh_token_hash TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL,
+            revoked_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create sessions table");
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_user_id ON sessions(user_id)")
+        .execute(&pool)
+        .await
+        .expect("Failed to create index on sessions.user_id");
+
+    sqlx::query(
+        "ALTER TABLE sessions
+            ADD COLUMN IF NOT EXISTS user_agent TEXT,
+            ADD COLUMN IF NOT EXISTS ip_address TEXT,
+            ADD COLUMN IF NOT EXISTS last_seen_at TIMESTAMPTZ NOT NULL DEFAULT now()"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to add device columns to sessions table");
+
+    // Хэши уже ротированных refresh-токенов: их повторное предъявление означает утечку
+    sqlx::query(
+        "ALTER TABLE sessions
+            ADD COLUMN IF NOT EXISTS previous_refresh_token_hashes TEXT[] NOT NULL DEFAULT '{}'"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to add refresh token history to sessions table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS organizations (
+            id UUID PRIMARY KEY,
+            name TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create organizations table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS organization_members (
+            organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            role VARCHAR(20) NOT NULL,
+            joined_at TIMESTAMPTZ NOT NULL,
+            PRIMARY KEY (organization_id, user_id)
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create organization_members table");
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_organization_members_user_id ON organization_members(user_id)")
+        .execute(&pool)
+        .await
+        .expect("Failed to create index on organization_members.user_id");
+
+    // Владелец у организации ровно один
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_organization_members_owner ON organization_members(organization_id)
+         WHERE role = 'owner'"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create index on organization owners");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS organization_invites (
+            id UUID PRIMARY KEY,
+            organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+            email TEXT NOT NULL,
+            role VARCHAR(20) NOT NULL,
+            token_hash TEXT NOT NULL UNIQUE,
+            invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL,
+            accepted_at TIMESTAMPTZ,
+            revoked_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create organization_invites table");
+
+    sqlx::query(
+        "ALTER TABLE sessions
+            ADD COLUMN IF NOT EXISTS organization_id UUID REFERENCES organizations(id) ON DELETE SET NULL"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to add organization column to sessions table");
+
+    // Денайлист общий: tasks-service читает его и подписывается на NOTIFY token_revoked
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS token_denylist (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create token_denylist table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS password_reset_tokens (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            token_hash TEXT UNIQUE NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL,
+            used_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create password_reset_tokens table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS email_verification_tokens (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            token_hash TEXT UNIQUE NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL,
+            used_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create email_verification_tokens table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS magic_link_tokens (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            -- адрес, на который ушла ссылка; после смены email она перестает работать
+            email TEXT NOT NULL,
+            token_hash TEXT UNIQUE NOT NULL,
+            browser_hash TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL,
+            used_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create magic_link_tokens table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS email_change_requests (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            new_email TEXT NOT NULL,
+            token_hash TEXT UNIQUE NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL,
+            confirmed_at TIMESTAMPTZ,
+            cancelled_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create email_change_requests table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS user_identities (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            provider TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            email TEXT,
+            created_at TIMESTAMPTZ NOT NULL,
+            last_login_at TIMESTAMPTZ NOT NULL,
+            UNIQUE (provider, subject)
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create user_identities table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS oidc_login_states (
+            state_hash TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            code_verifier TEXT NOT NULL,
+            nonce TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create oidc_login_states table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS oauth_clients (
+            id UUID PRIMARY KEY,
+            name VARCHAR(100) NOT NULL,
+            secret_hash TEXT,
+            redirect_uris TEXT[] NOT NULL,
+            scopes TEXT[] NOT NULL,
+            created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            revoked_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create oauth_clients table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS oauth_consents (
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            client_id UUID NOT NULL REFERENCES oauth_clients(id) ON DELETE CASCADE,
+            scopes TEXT[] NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            updated_at TIMESTAMPTZ NOT NULL,
+            PRIMARY KEY (user_id, client_id)
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create oauth_consents table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS oauth_authorization_codes (
+            code_hash TEXT PRIMARY KEY,
+            client_id UUID NOT NULL REFERENCES oauth_clients(id) ON DELETE CASCADE,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            redirect_uri TEXT NOT NULL,
+            scopes TEXT[] NOT NULL,
+            code_challenge TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL,
+            used_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create oauth_authorization_codes table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS oauth_device_codes (
+            id UUID PRIMARY KEY,
+            device_code_hash TEXT UNIQUE NOT NULL,
+            user_code TEXT UNIQUE NOT NULL,
+            client_id UUID NOT NULL REFERENCES oauth_clients(id) ON DELETE CASCADE,
+            scopes TEXT[] NOT NULL,
+            -- pending, approved или denied
+            status VARCHAR(16) NOT NULL,
+            user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+            interval_seconds INTEGER NOT NULL,
+            last_polled_at TIMESTAMPTZ,
+            created_at TIMESTAMPTZ NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create oauth_device_codes table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS oauth_tokens (
+            id UUID PRIMARY KEY,
+            client_id UUID NOT NULL REFERENCES oauth_clients(id) ON DELETE CASCADE,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            scopes TEXT[] NOT NULL,
+            access_token_hash TEXT NOT NULL,
+            access_expires_at TIMESTAMPTZ NOT NULL,
+            refresh_token_hash TEXT NOT NULL,
+            refresh_expires_at TIMESTAMPTZ NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            last_used_at TIMESTAMPTZ,
+            revoked_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create oauth_tokens table");
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_oauth_tokens_user_client ON oauth_tokens (user_id, client_id)")
+        .execute(&pool)
+        .await
+        .expect("Failed to create idx_oauth_tokens_user_client index");
+
+    // Журнал безопасности; user_id пустой, если событие не связано с аккаунтом
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS auth_events (
+            id UUID PRIMARY KEY,
+            user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+            event_type VARCHAR(32) NOT NULL,
+            ip_address TEXT,
+            user_agent TEXT,
+            details JSONB NOT NULL DEFAULT '{}',
+            created_at TIMESTAMPTZ NOT NULL
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create auth_events table");
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_auth_events_user_created ON auth_events (user_id, created_at DESC)")
+        .execute(&pool)
+        .await
+        .expect("Failed to create idx_auth_events_user_created index");
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_auth_events_created ON auth_events (created_at)")
+        .execute(&pool)
+        .await
+        .expect("Failed to create idx_auth_events_created index");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS totp_recovery_codes (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            code_hash TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            used_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create totp_recovery_codes table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS mfa_challenges (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            created_at TIMESTAMPTZ NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL,
+            completed_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create mfa_challenges table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS personal_access_tokens (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            name VARCHAR(100) NOT NULL,
+            token_hash VARCHAR(64) NOT NULL,
+            scopes TEXT[] NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL,
+            last_used_at TIMESTAMPTZ,
+            revoked_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create personal_access_tokens table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS login_throttle (
+            key TEXT PRIMARY KEY,
+            failures INTEGER NOT NULL,
+            last_failure_at TIMESTAMPTZ NOT NULL,
+            locked_until TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create login_throttle table");
+
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS deletion_scheduled_at TIMESTAMPTZ")
+        .execute(&pool)
+        .await
+        .expect("Failed to add deletion_scheduled_at column");
+
+    sqlx::query(
+        "ALTER TABLE users
+            ADD COLUMN IF NOT EXISTS role VARCHAR(20) NOT NULL DEFAULT 'user',
+            ADD COLUMN IF NOT EXISTS disabled_at TIMESTAMPTZ"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to add role columns to users table");
+
+    sqlx::query(
+        "ALTER TABLE users
+            ADD COLUMN IF NOT EXISTS avatar_url TEXT,
+            ADD COLUMN IF NOT EXISTS avatar_updated_at TIMESTAMPTZ"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to add avatar columns to users table");
+
+    // Раньше имена не были уникальными: более поздним дубликатам добавляем суффикс из id
+    sqlx::query(
+        "UPDATE users SET username = username || '_' || left(id::text, 6)
+         WHERE id IN (
+             SELECT id FROM (
+                 SELECT id, row_number() OVER (PARTITION BY lower(username) ORDER BY created_at) AS n
+                 FROM users
+             ) duplicates
+             WHERE n > 1
+         )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to deduplicate usernames");
+
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_users_username_lower ON users (lower(username))")
+        .execute(&pool)
+        .await
+        .expect("Failed to create unique index on username");
+
+    // События для других сервисов; пишутся в одной транзакции с изменением данных.
+    // processed_at один на событие: у каждого топика ровно один сервис-потребитель
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS outbox_events (
+            id UUID PRIMARY KEY,
+            topic VARCHAR(100) NOT NULL,
+            payload JSONB NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            processed_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create outbox_events table");
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_outbox_events_pending ON outbox_events(topic, created_at)
+         WHERE processed_at IS NULL"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create index on pending outbox events");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS data_erasure_log (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL,
+            service VARCHAR(50) NOT NULL,
+            details JSONB NOT NULL,
+            erased_at TIMESTAMPTZ NOT NULL
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create data_erasure_log table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS data_exports (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            status VARCHAR(20) NOT NULL,
+            archive BYTEA,
+            error TEXT,
+            created_at TIMESTAMPTZ NOT NULL,
+            started_at TIMESTAMPTZ,
+            completed_at TIMESTAMPTZ,
+            expires_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create data_exports table");
+
+    // Не больше одной выгрузки в работе на пользователя
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_data_exports_active ON data_exports(user_id)
+         WHERE status IN ('pending', 'running')"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create index on active data exports");
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_personal_access_tokens_user_id ON personal_access_tokens(user_id)")
+        .execute(&pool)
+        .await
+        .expect("Failed to create index on personal_access_tokens.user_id");
+
+    // `auth-service grant-role <email> <role>` выдает роль и завершается, сервер не запускается
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("grant-role") {
+        let (Some(email), Some(role)) = (args.get(2), args.get(3).and_then(|r| roles::Role::parse(r))) else {
+            eprintln!("Usage: auth-service grant-role <email> <user|support|admin>");
+            std::process::exit(2);
+        };
+
+        match roles::grant(&pool, email, role).await.expect("Failed to grant role") {
+            true => {
+                tracing::warn!(email = %email, role = role.as_str(), event = "role_granted", "Role granted from CLI");
+                println!("{} is now {}", email, role.as_str());
+                return;
+            }
+            false => {
+                eprintln!("User {} not found or already has role {}", email, role.as_str());
+                std::process::exit(1);
+            }
+        }
+    }
+
+    roles::bootstrap_admins(&pool).await;
+
+    revocation::spawn_purge(pool.clone());
+    throttle::spawn_purge(pool.clone());
+    deletion::spawn_worker(pool.clone());
+    export::spawn_worker(pool.clone());
+    oauth::spawn_purge(pool.clone());
+    audit::spawn_purge(pool.clone());
+
+    let state = AppState {
+        pool,
+        mailer: mail::from_env(),
+    };
+
     let app = Router::new()
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .route("/auth/register", post(handlers::auth::register))
         .route("/auth/login", post(handlers::auth::login))
+        .route("/auth/login/2fa", post(handlers::mfa::login_mfa))
+        .route("/auth/refresh", post(handlers::auth::refresh))
         .route("/auth/verify", get(handlers::auth::verify_token))
+        .route("/.well-known/jwks.json", get(handlers::auth::jwks))
+        .route("/auth/logout", post(handlers::auth::logout))
+        .route("/auth/logout-all", post(handlers::auth::logout_all))
         .route("/auth/profile", put(handlers::auth::update_profile))
         .route("/auth/password", put(handlers::auth::change_password))
-        .with_state(pool);
+        .route("/auth/password/forgot", post(handlers::password::forgot_password))
+        .route("/auth/password/reset", post(handlers::password::reset_password))
+        .route("/auth/events", get(handlers::events::list_my_events))
+        .route("/auth/magic-link", post(handlers::magic_link::request_magic_link))
+        .route("/auth/magic-link/consume", post(handlers::magic_link::consume_magic_link))
+        .route("/auth/verify-email", post(handlers::verification::verify_email))
+        .route("/auth/verify-email/resend", post(handlers::verification::resend_verification))
+        .route("/auth/email/change", post(handlers::email::request_email_change))
+        .route("/auth/email/confirm", post(handlers::email::confirm_email_change))
+        .route("/auth/oidc/providers", get(handlers::oidc::list_providers))
+        .route("/auth/oidc/:provider/authorize", post(handlers::oidc::authorize))
+        .route("/auth/oidc/:provider/callback", post(handlers::oidc::callback))
+        .route("/auth/identities", get(handlers::oidc::list_identities))
+        .route("/auth/identities/:id", delete(handlers::oidc::delete_identity))
+        .route("/auth/oauth/authorize", get(handlers::oauth::get_authorize).post(handlers::oauth::decide_authorize))
+        .route("/auth/oauth/token", post(handlers::oauth::token))
+        .route("/auth/oauth/device/code", post(handlers::oauth::device_code))
+        .route("/auth/oauth/device", get(handlers::oauth::get_device).post(handlers::oauth::decide_device))
+        .route("/auth/oauth/consents", get(handlers::oauth::list_consents))
+        .route("/auth/oauth/consents/:client_id", delete(handlers::oauth::revoke_consent))
+        .route("/auth/2fa/setup", post(handlers::mfa::setup_totp))
+        .route("/auth/2fa/confirm", post(handlers::mfa::confirm_totp))
+        .route("/auth/2fa/disable", post(handlers::mfa::disable_totp))
+        .route("/auth/sessions", get(handlers::sessions::list_sessions))
+        .route("/auth/sessions/:id", delete(handlers::sessions::delete_session))
+        .route("/auth/tokens", get(handlers::tokens::list_tokens).post(handlers::tokens::create_token))
+        .route("/auth/tokens/:id", delete(handlers::tokens::revoke_token))
+        .route("/auth/session/organization", put(handlers::organizations::switch_organization))
+        .route("/auth/account", delete(handlers::account::delete_account))
+        .route("/auth/export", post(handlers::export::create_export))
+        .route("/auth/export/:id", get(handlers::export::get_export))
+        .route("/auth/export/:id/download", get(handlers::export::download_export))
+        .route("/orgs", get(handlers::organizations::list_organizations).post(handlers::organizations::create_organization))
+        .route("/orgs/:id/members", get(handlers::organizations::list_members))
+        .route("/orgs/:id/members/:user_id", delete(handlers::organizations::remove_member))
+        .route("/orgs/:id/members/:user_id/role", put(handlers::organizations::update_member_role))
+        .route("/orgs/:id/transfer-ownership", post(handlers::organizations::transfer_ownership))
+        .route("/orgs/:id/invites", get(handlers::organizations::list_invites).post(handlers::organizations::create_invite))
+        .route("/orgs/:id/invites/:invite_id", delete(handlers::organizations::revoke_invite))
+        .route("/invites", get(handlers::organizations::get_invite))
+        .route("/invites/accept", post(handlers::organizations::accept_invite))
+        .route("/users", get(handlers::users::search_users))
+        .route("/users/me", get(handlers::users::get_me))
+        .route("/users/me/avatar", put(handlers::users::update_avatar).delete(handlers::users::delete_avatar))
+        .route("/users/:id", get(handlers::users::get_user))
+        .route("/admin/users", get(handlers::admin::list_users))
+        .route("/admin/users/:id", get(handlers::admin::get_user))
+        .route("/admin/users/:id/disable", post(handlers::admin::disable_user))
+        .route("/admin/users/:id/enable", post(handlers::admin::enable_user))
+        .route("/admin/users/:id/password-reset", post(handlers::admin::reset_user_password))
+        .route("/admin/users/:id/role", put(handlers::admin::update_role))
+        .route("/admin/auth-events", get(handlers::events::admin_list_events))
+        .route("/admin/oauth/clients", get(handlers::oauth_clients::list_clients).post(handlers::oauth_clients::create_client))
+        .route("/admin/oauth/clients/:id", delete(handlers::oauth_clients::revoke_client))
+        .with_state(state);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3001").await.unwrap();
     tracing::info!("Auth service running on port 3001");
@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::models::user::User;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminUserResponse {
+    pub id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub two_factor_enabled: bool,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub deletion_scheduled_at: Option<DateTime<Utc>>,
+}
+
+impl From<User> for AdminUserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            email: user.email,
+            username: user.username,
+            role: user.role,
+            created_at: user.created_at,
+            email_verified_at: user.email_verified_at,
+            two_factor_enabled: user.totp_enabled_at.is_some(),
+            disabled_at: user.disabled_at,
+            deletion_scheduled_at: user.deletion_scheduled_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct UserSearchQuery {
+    /// Подстрока email или имени пользователя
+    pub q: Option<String>,
+    /// Номер страницы, с 1
+    pub page: Option<i64>,
+    /// Размер страницы, по умолчанию 20, не больше 100
+    pub per_page: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserListResponse {
+    pub items: Vec<AdminUserResponse>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateRoleRequest {
+    /// user, support или admin
+    pub role: String,
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct AuthEvent {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub event_type: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuthEventResponse {
+    pub id: Uuid,
+    /// Отсутствует, если событие не удалось связать с аккаунтом (например, вход с неизвестным email)
+    pub user_id: Option<Uuid>,
+    /// register, login_succeeded, login_failed, password_changed, password_reset,
+    /// profile_updated, token_rejected или session_revoked
+    pub event_type: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    /// Подробности события: способ входа, причина отказа и т.д.
+    #[schema(value_type = Object)]
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AuthEvent> for AuthEventResponse {
+    fn from(event: AuthEvent) -> Self {
+        Self {
+            id: event.id,
+            user_id: event.user_id,
+            event_type: event.event_type,
+            ip_address: event.ip_address,
+            user_agent: event.user_agent,
+            details: event.details,
+            created_at: event.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AuthEventQuery {
+    /// Только события этого типа
+    pub event_type: Option<String>,
+    /// Номер страницы, с 1
+    pub page: Option<i64>,
+    /// Размер страницы, по умолчанию 20, не больше 100
+    pub per_page: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AdminAuthEventQuery {
+    pub user_id: Option<Uuid>,
+    pub event_type: Option<String>,
+    /// Точное совпадение IP-адреса
+    pub ip_address: Option<String>,
+    /// События не раньше этого момента
+    pub from: Option<DateTime<Utc>>,
+    /// События раньше этого момента
+    pub to: Option<DateTime<Utc>>,
+    /// Номер страницы, с 1
+    pub page: Option<i64>,
+    /// Размер страницы, по умолчанию 20, не больше 100
+    pub per_page: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuthEventListResponse {
+    pub items: Vec<AuthEventResponse>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Задание на выгрузку без самого архива: он хранится в строке до `expires_at`
+/// и читается только при скачивании
+#[derive(Debug, FromRow)]
+pub struct DataExport {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// pending, running, ready, failed или expired
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DataExportResponse {
+    pub id: Uuid,
+    /// pending, running, ready, failed или expired
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// До этого момента архив можно скачать
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Короткоживущая ссылка на архив, только в статусе ready
+    pub download_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadQuery {
+    pub token: String,
+}
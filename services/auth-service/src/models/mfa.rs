@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TotpSetupResponse {
+    /// Секрет в base32 для ручного ввода
+    pub secret: String,
+    /// otpauth:// URI для QR-кода
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TotpCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecoveryCodesResponse {
+    /// Показываются один раз, в базе хранятся только хэши
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DisableTotpRequest {
+    pub password: String,
+    /// Код из приложения или код восстановления
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MfaLoginRequest {
+    pub mfa_token: String,
+    /// Код из приложения или код восстановления
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MfaRequiredResponse {
+    pub mfa_required: bool,
+    pub mfa_token: String,
+    /// Время жизни mfa_token в секундах
+    pub expires_in: i64,
+}
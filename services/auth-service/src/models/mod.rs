@@ -1 +1,10 @@
 pub mod user;
+pub mod session;
+pub mod mfa;
+pub mod token;
+pub mod export;
+pub mod admin;
+pub mod audit;
+pub mod oidc;
+pub mod oauth;
+pub mod organization;
@@ -0,0 +1,202 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Префикс access-токена, выданного стороннему приложению
+pub const ACCESS_TOKEN_PREFIX: &str = "tso_";
+/// Префикс refresh-токена стороннего приложения
+pub const REFRESH_TOKEN_PREFIX: &str = "tsr_";
+
+pub const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Стороннее приложение. `id` — это `client_id`
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct OAuthClient {
+    pub id: Uuid,
+    pub name: String,
+    /// NULL у публичных клиентов (CLI, SPA): они доказывают себя только через PKCE
+    pub secret_hash: Option<String>,
+    pub redirect_uris: Vec<String>,
+    /// Права, которые приложение вообще может запросить
+    pub scopes: Vec<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Выданная приложению пара токенов, аналог сессии
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct OAuthToken {
+    pub id: Uuid,
+    pub client_id: Uuid,
+    pub user_id: Uuid,
+    pub scopes: Vec<String>,
+    pub access_token_hash: String,
+    pub access_expires_at: DateTime<Utc>,
+    pub refresh_token_hash: String,
+    pub refresh_expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateClientRequest {
+    pub name: String,
+    /// Точное совпадение; для loopback-адресов (`http://127.0.0.1/...`) порт не сравнивается
+    pub redirect_uris: Vec<String>,
+    pub scopes: Vec<String>,
+    /// Выдать секрет. Для CLI и других приложений, которые не могут его хранить, — false
+    pub confidential: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClientResponse {
+    pub client_id: Uuid,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub scopes: Vec<String>,
+    pub confidential: bool,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<OAuthClient> for ClientResponse {
+    fn from(client: OAuthClient) -> Self {
+        Self {
+            client_id: client.id,
+            name: client.name,
+            redirect_uris: client.redirect_uris,
+            scopes: client.scopes,
+            confidential: client.secret_hash.is_some(),
+            created_at: client.created_at,
+            revoked_at: client.revoked_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedClientResponse {
+    /// Показывается только один раз; у публичного клиента отсутствует
+    pub client_secret: Option<String>,
+    #[serde(flatten)]
+    pub details: ClientResponse,
+}
+
+/// Параметры запроса авторизации (RFC 6749, PKCE обязателен)
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct AuthorizeQuery {
+    /// Только `code`
+    pub response_type: String,
+    pub client_id: Uuid,
+    pub redirect_uri: String,
+    /// Права через пробел; по умолчанию — все права приложения
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub code_challenge: String,
+    /// Только `S256`
+    pub code_challenge_method: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuthorizeInfoResponse {
+    pub client_id: Uuid,
+    pub client_name: String,
+    pub scopes: Vec<String>,
+    /// Пользователь уже разрешил эти права, экран согласия можно не показывать
+    pub consented: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AuthorizeDecisionRequest {
+    #[serde(flatten)]
+    pub params: AuthorizeQuery,
+    pub approve: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuthorizeDecisionResponse {
+    /// Адрес приложения с `code` или `error` — туда нужно отправить браузер
+    pub redirect_url: String,
+}
+
+/// Тело запроса к `/auth/oauth/token` (application/x-www-form-urlencoded)
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TokenRequest {
+    pub grant_type: String,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    pub refresh_token: Option<String>,
+    pub device_code: Option<String>,
+    /// Можно передать и через HTTP Basic
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OAuthTokenResponse {
+    pub access_token: String,
+    /// Всегда `Bearer`
+    pub token_type: String,
+    pub expires_in: i64,
+    pub refresh_token: String,
+    /// Выданные права через пробел
+    pub scope: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DeviceCodeRequest {
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeviceCodeResponse {
+    pub device_code: String,
+    /// Код, который пользователь вводит на `verification_uri`
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: String,
+    pub expires_in: i64,
+    /// Не чаще раза в столько секунд опрашивать `/auth/oauth/token`
+    pub interval: i64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DeviceQuery {
+    pub user_code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeviceInfoResponse {
+    pub client_id: Uuid,
+    pub client_name: String,
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DeviceDecisionRequest {
+    pub user_code: String,
+    pub approve: bool,
+}
+
+/// Приложение, которому пользователь дал доступ
+#[derive(Debug, Serialize, ToSchema, FromRow)]
+pub struct ConsentResponse {
+    pub client_id: Uuid,
+    pub client_name: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Ошибка в формате RFC 6749 для token- и device-endpoint'ов
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OAuthErrorResponse {
+    pub error: String,
+    pub error_description: String,
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Связь аккаунта с учетной записью у внешнего провайдера
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct UserIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: String,
+    /// `sub` из ID-токена, уникален в пределах провайдера
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OidcProviderResponse {
+    pub id: String,
+    /// Название для кнопки входа
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OidcAuthorizeResponse {
+    /// Куда отправить браузер
+    pub authorization_url: String,
+    /// Клиент сохраняет его и сверяет с `state` в адресе возврата
+    pub state: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct OidcCallbackRequest {
+    pub code: String,
+    pub state: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IdentityResponse {
+    pub id: Uuid,
+    pub provider: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: DateTime<Utc>,
+}
+
+impl From<UserIdentity> for IdentityResponse {
+    fn from(identity: UserIdentity) -> Self {
+        Self {
+            id: identity.id,
+            provider: identity.provider,
+            email: identity.email,
+            created_at: identity.created_at,
+            last_login_at: identity.last_login_at,
+        }
+    }
+}
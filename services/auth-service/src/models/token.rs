@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Права, которые можно выдать персональному токену
+pub const SCOPES: &[&str] = &["tasks:read", "tasks:write", "settings:read", "settings:write"];
+
+/// Префикс, по которому сервисы отличают персональный токен от JWT
+pub const TOKEN_PREFIX: &str = "tsp_";
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct PersonalAccessToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateTokenRequest {
+    pub name: String,
+    /// Например `tasks:read`, `tasks:write`, `settings:read`, `settings:write`
+    pub scopes: Vec<String>,
+    /// Срок действия в днях, по умолчанию 90, не больше 365
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TokenResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<PersonalAccessToken> for TokenResponse {
+    fn from(token: PersonalAccessToken) -> Self {
+        Self {
+            id: token.id,
+            name: token.name,
+            scopes: token.scopes,
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedTokenResponse {
+    /// Показывается только один раз, в базе хранится лишь хэш
+    pub token: String,
+    #[serde(flatten)]
+    pub details: TokenResponse,
+}
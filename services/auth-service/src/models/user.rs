@@ -1,9 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use crate::models::mfa::MfaRequiredResponse;
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct User {
     pub id: Uuid,
@@ -11,6 +13,20 @@ pub struct User {
     pub username: String,
     pub password_hash: String,
     pub created_at: DateTime<Utc>,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub totp_secret_encrypted: Option<String>,
+    /// Пока NULL, секрет выдан, но 2FA не подтверждена первым кодом
+    pub totp_enabled_at: Option<DateTime<Utc>>,
+    /// Последний принятый шаг TOTP, защищает от повторного использования кода
+    pub totp_last_step: Option<i64>,
+    /// Запрошено удаление: до этого момента вход отменяет удаление, после — данные стираются
+    pub deletion_scheduled_at: Option<DateTime<Utc>>,
+    /// user, support или admin
+    pub role: String,
+    /// Аккаунт заблокирован администратором
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub avatar_url: Option<String>,
+    pub avatar_updated_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -29,10 +45,21 @@ pub struct LoginRequest {
 #[derive(Debug, Serialize, ToSchema)]
 pub struct AuthResponse {
     pub token: String,
+    pub refresh_token: String,
+    /// Время жизни access-токена в секундах
+    pub expires_in: i64,
     pub user_id: Uuid,
     pub username: String,
 }
 
+/// Ответ на вход: либо токены, либо запрос второго фактора
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Authenticated(AuthResponse),
+    MfaRequired(MfaRequiredResponse),
+}
+
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateProfileRequest {
     pub username: String,
@@ -42,6 +69,59 @@ pub struct UpdateProfileRequest {
 pub struct ChangePasswordRequest {
     pub current_password: String,
     pub new_password: String,
+    /// Завершить все сессии, кроме текущей
+    #[serde(default)]
+    pub revoke_other_sessions: bool,
+    /// Обязателен, если включена 2FA
+    pub totp_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MagicLinkRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MagicLinkResponse {
+    /// Привязка к браузеру: сохраняется на клиенте и передается вместе с токеном
+    /// из письма. Выдается и для несуществующего адреса.
+    pub browser_token: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ConsumeMagicLinkRequest {
+    /// Токен из ссылки в письме
+    pub token: String,
+    pub browser_token: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChangeEmailRequest {
+    pub new_email: String,
+    pub password: String,
+    /// Обязателен, если включена двухфакторная аутентификация
+    pub totp_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ConfirmEmailChangeRequest {
+    pub token: String,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -51,3 +131,103 @@ pub struct UpdateProfileResponse {
     pub email: String,
     pub token: String,
 }
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DeleteAccountRequest {
+    pub password: String,
+    /// Обязателен, если включена двухфакторная аутентификация
+    pub totp_code: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeleteAccountResponse {
+    /// Когда аккаунт будет удален окончательно
+    pub deletion_scheduled_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Avatar {
+    pub url: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl User {
+    pub fn avatar(&self) -> Option<Avatar> {
+        Some(Avatar {
+            url: self.avatar_url.clone()?,
+            updated_at: self.avatar_updated_at?,
+        })
+    }
+}
+
+/// Собственный профиль, `GET /users/me`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserProfileResponse {
+    pub id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub role: String,
+    pub email_verified: bool,
+    pub two_factor_enabled: bool,
+    pub avatar: Option<Avatar>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<User> for UserProfileResponse {
+    fn from(user: User) -> Self {
+        Self {
+            avatar: user.avatar(),
+            id: user.id,
+            email: user.email,
+            username: user.username,
+            role: user.role,
+            email_verified: user.email_verified_at.is_some(),
+            two_factor_enabled: user.totp_enabled_at.is_some(),
+            created_at: user.created_at,
+        }
+    }
+}
+
+/// То, что видно о пользователе другим: без email и настроек безопасности
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PublicProfileResponse {
+    pub id: Uuid,
+    pub username: String,
+    pub avatar: Option<Avatar>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<User> for PublicProfileResponse {
+    fn from(user: User) -> Self {
+        Self {
+            avatar: user.avatar(),
+            id: user.id,
+            username: user.username,
+            created_at: user.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct UsernameSearchQuery {
+    /// Начало или часть имени пользователя
+    pub q: String,
+    /// Номер страницы, с 1
+    pub page: Option<i64>,
+    /// Размер страницы, по умолчанию 20, не больше 50
+    pub per_page: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PublicProfileListResponse {
+    pub items: Vec<PublicProfileResponse>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateAvatarRequest {
+    /// Ссылка на изображение, только https
+    pub url: String,
+}
@@ -0,0 +1,162 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Utc};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+use crate::models::oauth::OAuthClient;
+use crate::models::token::SCOPES;
+
+/// Код авторизации нужно обменять на токены сразу после возврата в приложение
+pub const AUTHORIZATION_CODE_TTL_MINUTES: i64 = 5;
+pub const ACCESS_TOKEN_TTL_MINUTES: i64 = 60;
+pub const REFRESH_TOKEN_TTL_DAYS: i64 = 30;
+pub const DEVICE_CODE_TTL_MINUTES: i64 = 10;
+/// Стартовый интервал опроса; на каждый slow_down он растет на столько же
+pub const DEVICE_POLL_INTERVAL_SECONDS: i32 = 5;
+
+/// Без гласных и похожих символов, чтобы код не складывался в слова и легко набирался
+const USER_CODE_ALPHABET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+const USER_CODE_LENGTH: usize = 8;
+
+/// Права из параметра `scope` (через пробел). Без параметра — все права приложения.
+/// Запросить можно только то, что разрешено приложению при регистрации.
+pub fn parse_scopes(requested: Option<&str>, client: &OAuthClient) -> Result<Vec<String>, String> {
+    let mut scopes: Vec<String> = match requested.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(requested) => requested.split_whitespace().map(str::to_string).collect(),
+        None => client.scopes.clone(),
+    };
+
+    if let Some(scope) = scopes.iter().find(|s| !SCOPES.contains(&s.as_str())) {
+        return Err(format!("Unknown scope: {}", scope));
+    }
+    if let Some(scope) = scopes.iter().find(|s| !client.scopes.contains(s)) {
+        return Err(format!("Scope {} is not allowed for this client", scope));
+    }
+
+    scopes.sort();
+    scopes.dedup();
+    Ok(scopes)
+}
+
+/// Точное совпадение с зарегистрированным адресом. Для loopback-адресов порт
+/// не сравнивается: CLI слушает на случайном свободном порту (RFC 8252, 7.3).
+pub fn redirect_uri_allowed(client: &OAuthClient, redirect_uri: &str) -> bool {
+    if client.redirect_uris.iter().any(|uri| uri == redirect_uri) {
+        return true;
+    }
+
+    let Ok(requested) = reqwest::Url::parse(redirect_uri) else {
+        return false;
+    };
+    if !is_loopback(&requested) {
+        return false;
+    }
+
+    client.redirect_uris.iter()
+        .filter_map(|uri| reqwest::Url::parse(uri).ok())
+        .any(|registered| {
+            is_loopback(&registered)
+                && registered.host() == requested.host()
+                && registered.path() == requested.path()
+                && registered.query() == requested.query()
+        })
+}
+
+fn is_loopback(url: &reqwest::Url) -> bool {
+    url.scheme() == "http" && matches!(url.host_str(), Some("127.0.0.1") | Some("[::1]"))
+}
+
+/// Зарегистрировать можно https-адрес, loopback для CLI или собственную схему
+/// нативного приложения; фрагмент запрещен спецификацией
+pub fn valid_redirect_uri(uri: &str) -> bool {
+    match reqwest::Url::parse(uri) {
+        Ok(url) => {
+            url.fragment().is_none()
+                && match url.scheme() {
+                    "https" => true,
+                    "http" => is_loopback(&url),
+                    scheme => scheme.contains('.'),
+                }
+        }
+        Err(_) => false,
+    }
+}
+
+/// PKCE S256: base64url(sha256(code_verifier))
+pub fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// Код для ввода на странице подтверждения устройства, например `BDFG-HJKL`
+pub fn generate_user_code() -> String {
+    let mut rng = rand::thread_rng();
+    let code: String = (0..USER_CODE_LENGTH)
+        .map(|_| USER_CODE_ALPHABET[rng.gen_range(0..USER_CODE_ALPHABET.len())] as char)
+        .collect();
+    format!("{}-{}", &code[..4], &code[4..])
+}
+
+/// Пользователь может ввести код без дефиса и в нижнем регистре
+pub fn normalize_user_code(code: &str) -> String {
+    let code: String = code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    if code.len() == USER_CODE_LENGTH {
+        format!("{}-{}", &code[..4], &code[4..])
+    } else {
+        code
+    }
+}
+
+/// Добавляет параметры к адресу возврата приложения
+pub fn redirect_with(redirect_uri: &str, params: &[(&str, &str)]) -> String {
+    match reqwest::Url::parse(redirect_uri) {
+        Ok(mut url) => {
+            url.query_pairs_mut().extend_pairs(params);
+            url.into()
+        }
+        Err(_) => redirect_uri.to_string(),
+    }
+}
+
+/// Удаляет истекшие коды и токены приложений
+pub fn spawn_purge(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            if let Err(e) = purge(&pool).await {
+                tracing::error!(error = %e, "Failed to purge OAuth grants");
+            }
+        }
+    });
+}
+
+async fn purge(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+
+    sqlx::query("DELETE FROM oauth_authorization_codes WHERE expires_at <= $1")
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+    sqlx::query("DELETE FROM oauth_device_codes WHERE expires_at <= $1")
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+    // Отозванные храним еще сутки, чтобы в логах было видно, откуда взялся отклоненный токен
+    let tokens = sqlx::query(
+        "DELETE FROM oauth_tokens WHERE refresh_expires_at <= $1 OR revoked_at <= $2"
+    )
+    .bind(now)
+    .bind(now - Duration::days(1))
+    .execute(pool)
+    .await?;
+
+    tracing::debug!(purged_tokens = tokens.rows_affected(), "Expired OAuth grants purged");
+    Ok(())
+}
@@ -0,0 +1,339 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+use crate::mail;
+
+/// Вход через внешних OpenID Connect провайдеров (authorization code + PKCE).
+///
+/// Провайдеры описываются в JSON-файле `OIDC_PROVIDERS_FILE`:
+///
+/// ```json
+/// {
+///   "providers": [{
+///     "id": "corp",
+///     "name": "Корпоративный SSO",
+///     "issuer": "https://sso.example.com",
+///     "client_id": "taspla",
+///     "client_secret_env": "OIDC_CORP_CLIENT_SECRET",
+///     "scopes": ["openid", "email", "profile"],
+///     "link_by_email": true,
+///     "allow_signup": true
+///   }]
+/// }
+/// ```
+///
+/// Адреса endpoint'ов берутся из `{issuer}/.well-known/openid-configuration`.
+/// `authorization_endpoint` в файле переопределяет адрес для браузера — нужно,
+/// когда сервис ходит к провайдеру по внутреннему имени (mock-сервер в compose).
+/// Провайдер должен разрешать возврат на `{APP_URL}/sso/callback`.
+static PROVIDERS: OnceLock<Vec<Provider>> = OnceLock::new();
+
+/// Метаданные и ключи провайдера перечитываются не реже этого
+const METADATA_TTL: Duration = Duration::from_secs(3600);
+/// Неизвестный `kid` вызывает перечитывание ключей не чаще этого
+const MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(30);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Подписи, которые принимаются у ID-токенов
+const ALLOWED_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+#[derive(Deserialize)]
+struct ProvidersFile {
+    providers: Vec<ProviderConfig>,
+}
+
+#[derive(Deserialize)]
+pub struct ProviderConfig {
+    pub id: String,
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    /// Имя переменной окружения с секретом; без него клиент считается публичным
+    client_secret_env: Option<String>,
+    #[serde(default = "default_scopes")]
+    scopes: Vec<String>,
+    authorization_endpoint: Option<String>,
+    /// Привязывать вход к существующему аккаунту с тем же email, если провайдер его подтвердил
+    #[serde(default)]
+    pub link_by_email: bool,
+    /// Создавать аккаунт при первом входе
+    #[serde(default = "default_true")]
+    pub allow_signup: bool,
+}
+
+fn default_scopes() -> Vec<String> {
+    vec!["openid".to_string(), "email".to_string(), "profile".to_string()]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Clone, Deserialize)]
+struct Metadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+struct Discovered {
+    metadata: Metadata,
+    jwks: JwkSet,
+    fetched_at: Instant,
+}
+
+pub struct Provider {
+    pub config: ProviderConfig,
+    client_secret: Option<String>,
+    discovered: Mutex<Option<Discovered>>,
+}
+
+/// Claims ID-токена, которые нужны для входа
+#[derive(Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    pub email: Option<String>,
+    /// Некоторые провайдеры присылают строку "true"
+    #[serde(default)]
+    email_verified: Option<serde_json::Value>,
+    pub preferred_username: Option<String>,
+    pub name: Option<String>,
+    nonce: Option<String>,
+}
+
+impl IdTokenClaims {
+    pub fn email_verified(&self) -> bool {
+        match &self.email_verified {
+            Some(serde_json::Value::Bool(verified)) => *verified,
+            Some(serde_json::Value::String(verified)) => verified == "true",
+            _ => false,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+pub fn init() {
+    let providers = match std::env::var("OIDC_PROVIDERS_FILE") {
+        Ok(path) => load_file(&path),
+        Err(_) => Vec::new(),
+    };
+
+    tracing::info!(providers = providers.len(), "OIDC providers loaded");
+    PROVIDERS.set(providers).ok().expect("OIDC providers are already initialized");
+}
+
+fn load_file(path: &str) -> Vec<Provider> {
+    let content = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Failed to read OIDC_PROVIDERS_FILE {}: {}", path, e));
+    let file: ProvidersFile = serde_json::from_str(&content)
+        .unwrap_or_else(|e| panic!("Invalid OIDC_PROVIDERS_FILE {}: {}", path, e));
+
+    let mut providers: Vec<Provider> = Vec::new();
+    for config in file.providers {
+        if providers.iter().any(|p| p.config.id == config.id) {
+            panic!("Duplicate OIDC provider id {}", config.id);
+        }
+        if !config.scopes.iter().any(|scope| scope == "openid") {
+            panic!("OIDC provider {} must request the openid scope", config.id);
+        }
+
+        let client_secret = config.client_secret_env.as_ref().map(|name| {
+            std::env::var(name).unwrap_or_else(|_| panic!("{} must be set for OIDC provider {}", name, config.id))
+        });
+
+        providers.push(Provider { config, client_secret, discovered: Mutex::new(None) });
+    }
+    providers
+}
+
+pub fn providers() -> &'static [Provider] {
+    PROVIDERS.get().map(Vec::as_slice).unwrap_or_default()
+}
+
+pub fn provider(id: &str) -> Option<&'static Provider> {
+    providers().iter().find(|p| p.config.id == id)
+}
+
+pub fn redirect_uri() -> String {
+    format!("{}/sso/callback", mail::app_url())
+}
+
+/// Случайная строка для `state`, `nonce` и `code_verifier`
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+impl Provider {
+    /// Метаданные и ключи из кэша; `refresh` — перечитать, если кэш старше минимального интервала
+    async fn discover(&self, refresh: bool) -> Result<(Metadata, JwkSet), String> {
+        let mut discovered = self.discovered.lock().await;
+
+        if let Some(cached) = discovered.as_ref() {
+            let age = cached.fetched_at.elapsed();
+            let fresh = age < METADATA_TTL && !(refresh && age > MIN_REFETCH_INTERVAL);
+            if fresh {
+                return Ok((cached.metadata.clone(), cached.jwks.clone()));
+            }
+        }
+
+        let client = http_client()?;
+        let url = format!("{}/.well-known/openid-configuration", self.config.issuer.trim_end_matches('/'));
+
+        let metadata = client.get(&url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("discovery request failed: {}", e))?
+            .json::<Metadata>()
+            .await
+            .map_err(|e| format!("invalid discovery document: {}", e))?;
+
+        if metadata.issuer != self.config.issuer {
+            return Err(format!("discovery issuer {} does not match {}", metadata.issuer, self.config.issuer));
+        }
+
+        let jwks = client.get(&metadata.jwks_uri)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("JWKS request failed: {}", e))?
+            .json::<JwkSet>()
+            .await
+            .map_err(|e| format!("invalid JWKS: {}", e))?;
+
+        tracing::info!(provider = %self.config.id, keys = jwks.keys.len(), "OIDC provider metadata refreshed");
+
+        *discovered = Some(Discovered {
+            metadata: metadata.clone(),
+            jwks: jwks.clone(),
+            fetched_at: Instant::now(),
+        });
+        Ok((metadata, jwks))
+    }
+
+    /// Адрес страницы входа провайдера
+    pub async fn authorization_url(&self, state: &str, nonce: &str, code_verifier: &str) -> Result<String, String> {
+        let endpoint = match &self.config.authorization_endpoint {
+            Some(endpoint) => endpoint.clone(),
+            None => self.discover(false).await?.0.authorization_endpoint,
+        };
+
+        let mut url = reqwest::Url::parse(&endpoint).map_err(|e| e.to_string())?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &redirect_uri())
+            .append_pair("scope", &self.config.scopes.join(" "))
+            .append_pair("state", state)
+            .append_pair("nonce", nonce)
+            .append_pair("code_challenge", &code_challenge(code_verifier))
+            .append_pair("code_challenge_method", "S256");
+
+        Ok(url.into())
+    }
+
+    /// Меняет код на токены и проверяет ID-токен: подпись, `iss`, `aud`, срок и `nonce`
+    pub async fn exchange_code(&self, code: &str, code_verifier: &str, nonce: &str) -> Result<IdTokenClaims, String> {
+        let (metadata, _) = self.discover(false).await?;
+        let redirect_uri = redirect_uri();
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &redirect_uri),
+            ("client_id", &self.config.client_id),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = &self.client_secret {
+            form.push(("client_secret", secret));
+        }
+
+        let tokens = http_client()?
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("token request failed: {}", e))?
+            .json::<TokenResponse>()
+            .await
+            .map_err(|e| format!("invalid token response: {}", e))?;
+
+        let header = decode_header(&tokens.id_token).map_err(|e| format!("invalid ID token: {}", e))?;
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            return Err(format!("ID token algorithm {:?} is not allowed", header.alg));
+        }
+
+        let key = self.decoding_key(header.kid.as_deref()).await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<IdTokenClaims>(&tokens.id_token, &key, &validation)
+            .map_err(|e| format!("ID token rejected: {}", e))?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err("ID token nonce mismatch".to_string());
+        }
+
+        Ok(claims)
+    }
+
+    /// Ключ из JWKS провайдера; при незнакомом `kid` набор перечитывается — провайдер мог ротировать ключи
+    async fn decoding_key(&self, kid: Option<&str>) -> Result<DecodingKey, String> {
+        let find = |jwks: &JwkSet| match kid {
+            Some(kid) => jwks.find(kid).cloned(),
+            // Без kid допустим только единственный ключ в наборе
+            None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+            None => None,
+        };
+
+        let (_, jwks) = self.discover(false).await?;
+        let jwk = match find(&jwks) {
+            Some(jwk) => jwk,
+            None => find(&self.discover(true).await?.1)
+                .ok_or_else(|| format!("no signing key {:?} in provider JWKS", kid))?,
+        };
+
+        DecodingKey::from_jwk(&jwk).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,162 @@
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use axum::http::StatusCode;
+use sha1::{Digest, Sha1};
+use std::{path::PathBuf, sync::OnceLock};
+
+/// Хэширование паролей и парольная политика.
+///
+/// Новые хэши — argon2id с параметрами из `ARGON2_MEMORY_KIB`, `ARGON2_ITERATIONS`
+/// и `ARGON2_PARALLELISM` (по умолчанию 19 МиБ, 2 прохода, 1 поток — минимум по OWASP).
+/// Старые bcrypt-хэши по-прежнему проверяются; и они, и argon2-хэши с устаревшими
+/// параметрами помечаются как требующие перехэширования при следующем входе.
+///
+/// Утекшие пароли ищутся в `BREACHED_PASSWORDS_DIR` — локальной копии Pwned Passwords
+/// в разбивке по диапазонам: файл `<первые 5 символов SHA-1>.txt` со строками
+/// `<остаток SHA-1>:<сколько раз встречался>`. Читается только файл своего диапазона.
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+const MAX_LENGTH: usize = 256;
+
+struct Config {
+    params: Params,
+    min_length: usize,
+    breached_dir: Option<PathBuf>,
+}
+
+pub struct Verification {
+    pub valid: bool,
+    /// Хэш сделан не argon2id или не с текущими параметрами
+    pub needs_rehash: bool,
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match std::env::var(name) {
+        Ok(value) => value.parse().unwrap_or_else(|_| panic!("{} must be a number", name)),
+        Err(_) => default,
+    }
+}
+
+pub fn init() {
+    let params = Params::new(
+        env_or("ARGON2_MEMORY_KIB", Params::DEFAULT_M_COST),
+        env_or("ARGON2_ITERATIONS", Params::DEFAULT_T_COST),
+        env_or("ARGON2_PARALLELISM", Params::DEFAULT_P_COST),
+        None,
+    )
+    .unwrap_or_else(|e| panic!("Invalid argon2 parameters: {}", e));
+
+    let breached_dir = std::env::var("BREACHED_PASSWORDS_DIR").ok().map(PathBuf::from);
+    if breached_dir.is_none() {
+        tracing::warn!("BREACHED_PASSWORDS_DIR is not set, breached password check is disabled");
+    }
+
+    let config = Config {
+        params,
+        min_length: env_or("PASSWORD_MIN_LENGTH", 8),
+        breached_dir,
+    };
+
+    tracing::info!(
+        memory_kib = config.params.m_cost(),
+        iterations = config.params.t_cost(),
+        parallelism = config.params.p_cost(),
+        min_length = config.min_length,
+        "Password hashing configured"
+    );
+    CONFIG.set(config).ok().expect("Password hashing is already initialized");
+}
+
+fn config() -> &'static Config {
+    CONFIG.get().expect("Password hashing is not initialized")
+}
+
+fn argon2() -> Argon2<'static> {
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, config().params.clone())
+}
+
+pub fn hash(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    argon2()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+pub fn verify(password: &str, stored: &str) -> Result<Verification, String> {
+    if stored.starts_with("$2") {
+        let valid = bcrypt::verify(password, stored).map_err(|e| e.to_string())?;
+        return Ok(Verification { valid, needs_rehash: true });
+    }
+
+    let parsed = PasswordHash::new(stored).map_err(|e| e.to_string())?;
+
+    // Проверка идет по параметрам из самого хэша, текущие нужны только для решения о перехэшировании
+    let valid = match Argon2::default().verify_password(password.as_bytes(), &parsed) {
+        Ok(()) => true,
+        Err(argon2::password_hash::Error::Password) => false,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let needs_rehash = parsed.algorithm != Algorithm::Argon2id.ident()
+        || parsed.version != Some(Version::V0x13.into())
+        || Params::try_from(&parsed).map_or(true, |params| params != config().params);
+
+    Ok(Verification { valid, needs_rehash })
+}
+
+/// Проверяет новый пароль перед сохранением
+pub async fn check_policy(password: &str) -> Result<(), (StatusCode, String)> {
+    let length = password.chars().count();
+    let min_length = config().min_length;
+    if length < min_length {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Password must be at least {} characters", min_length),
+        ));
+    }
+    if length > MAX_LENGTH {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Password must be at most {} characters", MAX_LENGTH),
+        ));
+    }
+
+    if is_breached(password).await {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "This password has appeared in a data breach, choose a different one".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn is_breached(password: &str) -> bool {
+    let Some(dir) = &config().breached_dir else {
+        return false;
+    };
+
+    let digest = hex::encode_upper(Sha1::digest(password.as_bytes()));
+    let (prefix, suffix) = digest.split_at(5);
+
+    // Нет файла диапазона — в списке нет ни одного пароля с таким префиксом.
+    // Ошибка чтения не должна мешать сменить пароль, поэтому только логируется.
+    let range = match tokio::fs::read_to_string(dir.join(format!("{}.txt", prefix))).await {
+        Ok(range) => range,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return false,
+        Err(e) => {
+            tracing::error!(error = %e, prefix, "Failed to read breached password range");
+            return false;
+        }
+    };
+
+    range.lines().any(|line| {
+        line.split_once(':')
+            .map_or(line, |(hash, _)| hash)
+            .trim()
+            .eq_ignore_ascii_case(suffix)
+    })
+}
@@ -0,0 +1,107 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::handlers::auth::ACCESS_TOKEN_TTL_MINUTES;
+
+/// Канал LISTEN/NOTIFY, через который tasks-service узнает об отзыве токенов
+const REVOCATION_CHANNEL: &str = "token_revoked";
+
+/// Добавляет jti токена или id сессии в общий денайлист и оповещает другие сервисы.
+/// Запись нужна только до `expires_at`: после этого токен отклоняется и без нее.
+pub async fn deny(
+    conn: &mut PgConnection,
+    id: Uuid,
+    user_id: Uuid,
+    expires_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO token_denylist (id, user_id, expires_at, created_at)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (id) DO NOTHING"
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(expires_at)
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+
+    // NOTIFY внутри транзакции доставляется только после commit
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(REVOCATION_CHANNEL)
+        .bind(format!("{}:{}", id, expires_at.timestamp()))
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Отзывает сессию: refresh-токен перестает работать, а выданные по ней
+/// access-токены попадают в денайлист до истечения своего срока.
+pub async fn revoke_session(
+    conn: &mut PgConnection,
+    session_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+
+    sqlx::query("UPDATE sessions SET revoked_at = $2 WHERE id = $1 AND revoked_at IS NULL")
+        .bind(session_id)
+        .bind(now)
+        .execute(&mut *conn)
+        .await?;
+
+    deny(conn, session_id, user_id, now + Duration::minutes(ACCESS_TOKEN_TTL_MINUTES)).await
+}
+
+/// Отзывает все активные сессии пользователя ("выйти везде", принудительный выход),
+/// кроме `except`, если она указана. Возвращает количество отозванных сессий.
+pub async fn revoke_user_sessions(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    except: Option<Uuid>,
+) -> Result<usize, sqlx::Error> {
+    let session_ids = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM sessions
+         WHERE user_id = $1 AND revoked_at IS NULL AND ($2::uuid IS NULL OR id <> $2)"
+    )
+    .bind(user_id)
+    .bind(except)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for session_id in &session_ids {
+        revoke_session(conn, *session_id, user_id).await?;
+    }
+
+    Ok(session_ids.len())
+}
+
+pub async fn is_denied(pool: &PgPool, ids: &[Uuid]) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM token_denylist WHERE id = ANY($1) AND expires_at > $2)"
+    )
+    .bind(ids)
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await
+}
+
+/// Периодически удаляет из денайлиста записи, срок которых истек
+pub fn spawn_purge(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            match sqlx::query("DELETE FROM token_denylist WHERE expires_at <= $1")
+                .bind(Utc::now())
+                .execute(&pool)
+                .await
+            {
+                Ok(result) => tracing::debug!(purged = result.rows_affected(), "Expired denylist entries purged"),
+                Err(e) => tracing::error!(error = %e, "Failed to purge token denylist"),
+            }
+        }
+    });
+}
@@ -0,0 +1,125 @@
+use axum::http::StatusCode;
+
+/// Роль пользователя, хранится в `users.role` и попадает в access-токен
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    User,
+    /// Поддержка: видит пользователей, их статистику и журнал входов, может отправить ссылку для сброса пароля
+    Support,
+    Admin,
+}
+
+/// Действия в админке
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    ViewUsers,
+    ResetPasswords,
+    DisableUsers,
+    ManageRoles,
+    /// Регистрация и отзыв сторонних OAuth-приложений
+    ManageOAuthClients,
+    /// Журнал событий безопасности всех пользователей
+    ViewAuditLog,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::User, Role::Support, Role::Admin];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Support => "support",
+            Self::Admin => "admin",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.as_str() == role)
+    }
+
+    pub fn has(self, permission: Permission) -> bool {
+        match self {
+            Self::User => false,
+            Self::Support => matches!(permission, Permission::ViewUsers | Permission::ResetPasswords | Permission::ViewAuditLog),
+            Self::Admin => true,
+        }
+    }
+
+    pub fn require(self, permission: Permission) -> Result<(), (StatusCode, String)> {
+        if self.has(permission) {
+            Ok(())
+        } else {
+            Err((StatusCode::FORBIDDEN, "Insufficient permissions".to_string()))
+        }
+    }
+}
+
+/// Роль участника организации, хранится в `organization_members.role`.
+/// Владелец у организации ровно один.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrgRole {
+    Owner,
+    Admin,
+    Member,
+}
+
+impl OrgRole {
+    pub const ALL: [OrgRole; 3] = [OrgRole::Owner, OrgRole::Admin, OrgRole::Member];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Owner => "owner",
+            Self::Admin => "admin",
+            Self::Member => "member",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.as_str() == role)
+    }
+
+    /// Приглашать участников и исключать тех, у кого роль ниже
+    pub fn can_manage_members(self) -> bool {
+        matches!(self, Self::Owner | Self::Admin)
+    }
+
+    pub fn outranks(self, other: OrgRole) -> bool {
+        self.rank() > other.rank()
+    }
+
+    fn rank(self) -> u8 {
+        match self {
+            Self::Owner => 2,
+            Self::Admin => 1,
+            Self::Member => 0,
+        }
+    }
+}
+
+/// Выдает роль по email. Используется для первого администратора: из `ADMIN_EMAILS`
+/// при старте или командой `auth-service grant-role <email> <role>`.
+pub async fn grant(pool: &sqlx::PgPool, email: &str, role: Role) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE users SET role = $2 WHERE email = $1 AND role <> $2")
+        .bind(email)
+        .bind(role.as_str())
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Назначает администраторами пользователей из `ADMIN_EMAILS` (через запятую).
+/// Аккаунт должен уже существовать; роль не снимается, если адрес убрали из списка.
+pub async fn bootstrap_admins(pool: &sqlx::PgPool) {
+    let Ok(emails) = std::env::var("ADMIN_EMAILS") else {
+        return;
+    };
+
+    for email in emails.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        match grant(pool, email, Role::Admin).await {
+            Ok(true) => tracing::warn!(email = %email, event = "role_granted", role = "admin", "Admin role granted from ADMIN_EMAILS"),
+            Ok(false) => tracing::debug!(email = %email, "ADMIN_EMAILS entry is already admin or not registered"),
+            Err(e) => tracing::error!(error = %e, email = %email, "Failed to grant admin role"),
+        }
+    }
+}
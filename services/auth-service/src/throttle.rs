@@ -0,0 +1,159 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgConnection, PgPool};
+
+/// Правила торможения для одного вида ключа
+pub struct Policy {
+    /// Сколько ошибок подряд допускается без задержки
+    free_attempts: i32,
+    /// Потолок экспоненциальной задержки
+    max_delay: Duration,
+    /// После стольких ошибок ключ блокируется на `lockout`
+    lockout_after: i32,
+    lockout: Duration,
+    /// Счетчик обнуляется, если ошибок не было дольше этого времени
+    window: Duration,
+}
+
+/// По email, а не по id пользователя: несуществующий адрес тормозится так же,
+/// как существующий, и блокировка не выдает, есть ли такой аккаунт
+pub const ACCOUNT: Policy = Policy {
+    free_attempts: 3,
+    max_delay: Duration::minutes(5),
+    lockout_after: 10,
+    lockout: Duration::minutes(30),
+    window: Duration::hours(24),
+};
+
+pub const IP: Policy = Policy {
+    free_attempts: 20,
+    max_delay: Duration::minutes(5),
+    lockout_after: 100,
+    lockout: Duration::hours(1),
+    window: Duration::hours(1),
+};
+
+/// Каждый запрос ссылки для входа считается попыткой: ограничивает поток писем
+/// на один адрес, даже если ссылки никто не открывает
+pub const MAGIC_LINK_ACCOUNT: Policy = Policy {
+    free_attempts: 3,
+    max_delay: Duration::minutes(10),
+    lockout_after: 10,
+    lockout: Duration::hours(1),
+    window: Duration::hours(1),
+};
+
+pub const MAGIC_LINK_IP: Policy = Policy {
+    free_attempts: 10,
+    max_delay: Duration::minutes(5),
+    lockout_after: 50,
+    lockout: Duration::hours(1),
+    window: Duration::hours(1),
+};
+
+pub fn account_key(email: &str) -> String {
+    format!("account:{}", email.trim().to_lowercase())
+}
+
+pub fn ip_key(ip: &str) -> String {
+    format!("ip:{}", ip)
+}
+
+/// Отдельное пространство ключей, чтобы запросы ссылок не блокировали вход по паролю
+pub fn magic_link_key(key: &str) -> String {
+    format!("magic-link:{}", key)
+}
+
+/// Сколько еще ждать, если хотя бы один из ключей заблокирован
+pub async fn locked_for(pool: &PgPool, keys: &[String]) -> Result<Option<Duration>, sqlx::Error> {
+    let now = Utc::now();
+    let locked_until = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+        "SELECT MAX(locked_until) FROM login_throttle WHERE key = ANY($1) AND locked_until > $2"
+    )
+    .bind(keys)
+    .bind(now)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(locked_until.map(|until| until - now))
+}
+
+/// Итог учета неудачной попытки
+pub struct Failure {
+    pub failures: i32,
+    pub delay: Option<Duration>,
+    /// Достигнут порог полной блокировки
+    pub locked_out: bool,
+}
+
+pub async fn record_failure(pool: &PgPool, key: &str, policy: &Policy) -> Result<Failure, sqlx::Error> {
+    let now = Utc::now();
+
+    let failures = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO login_throttle (key, failures, last_failure_at)
+         VALUES ($1, 1, $2)
+         ON CONFLICT (key) DO UPDATE SET
+             failures = CASE WHEN login_throttle.last_failure_at < $3 THEN 1
+                             ELSE login_throttle.failures + 1 END,
+             last_failure_at = $2
+         RETURNING failures"
+    )
+    .bind(key)
+    .bind(now)
+    .bind(now - policy.window)
+    .fetch_one(pool)
+    .await?;
+
+    let locked_out = failures >= policy.lockout_after;
+    let delay = if locked_out {
+        Some(policy.lockout)
+    } else if failures > policy.free_attempts {
+        // 1, 2, 4, 8... секунд, но не больше max_delay
+        let exponent = (failures - policy.free_attempts - 1).min(20) as u32;
+        Some(Duration::seconds(1 << exponent).min(policy.max_delay))
+    } else {
+        None
+    };
+
+    if let Some(delay) = delay {
+        sqlx::query("UPDATE login_throttle SET locked_until = $2 WHERE key = $1")
+            .bind(key)
+            .bind(now + delay)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(Failure { failures, delay, locked_out })
+}
+
+/// Снимает блокировку: после успешного входа или сброса пароля
+pub async fn clear(conn: &mut PgConnection, key: &str) -> Result<u64, sqlx::Error> {
+    let cleared = sqlx::query("DELETE FROM login_throttle WHERE key = $1")
+        .bind(key)
+        .execute(conn)
+        .await?
+        .rows_affected();
+    Ok(cleared)
+}
+
+/// Периодически удаляет счетчики, которые уже обнулились бы сами
+pub fn spawn_purge(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            let now = Utc::now();
+            match sqlx::query(
+                "DELETE FROM login_throttle
+                 WHERE last_failure_at < $1 AND (locked_until IS NULL OR locked_until <= $2)"
+            )
+            .bind(now - [&ACCOUNT, &IP, &MAGIC_LINK_ACCOUNT, &MAGIC_LINK_IP].iter().map(|p| p.window).max().unwrap_or(IP.window))
+            .bind(now)
+            .execute(&pool)
+            .await
+            {
+                Ok(result) => tracing::debug!(purged = result.rows_affected(), "Stale login throttle entries purged"),
+                Err(e) => tracing::error!(error = %e, "Failed to purge login throttle"),
+            }
+        }
+    });
+}
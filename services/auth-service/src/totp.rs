@@ -0,0 +1,76 @@
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use rand::RngCore;
+use totp_rs::{Algorithm, TOTP};
+
+const ISSUER: &str = "Taspla";
+const STEP_SECONDS: u64 = 30;
+/// Допускаем расхождение часов на один шаг в обе стороны
+const ALLOWED_SKEW_STEPS: i64 = 1;
+const NONCE_LEN: usize = 12;
+
+/// Новый случайный секрет на 160 бит, как рекомендует RFC 4226
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; 20];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret
+}
+
+pub fn build(secret: Vec<u8>, account_name: &str) -> Result<TOTP, String> {
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        ALLOWED_SKEW_STEPS as u8,
+        STEP_SECONDS,
+        secret,
+        Some(ISSUER.to_string()),
+        account_name.replace(':', ""),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Проверяет код и возвращает номер шага, которому он соответствует.
+/// Номер шага нужен вызывающему, чтобы не принять один и тот же код дважды.
+pub fn verify(totp: &TOTP, code: &str, unix_time: i64) -> Option<i64> {
+    let code = code.trim();
+    let current_step = unix_time / STEP_SECONDS as i64;
+
+    (current_step - ALLOWED_SKEW_STEPS..=current_step + ALLOWED_SKEW_STEPS)
+        .find(|step| totp.generate((*step as u64) * STEP_SECONDS) == code)
+}
+
+/// Секреты хранятся зашифрованными AES-256-GCM ключом из `TOTP_ENCRYPTION_KEY` (64 hex-символа).
+/// Формат: hex(nonce || ciphertext).
+pub fn encrypt_secret(secret: &[u8]) -> Result<String, String> {
+    let cipher = cipher()?;
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), secret)
+        .map_err(|e| e.to_string())?;
+
+    Ok(hex::encode([nonce.as_slice(), ciphertext.as_slice()].concat()))
+}
+
+pub fn decrypt_secret(encrypted: &str) -> Result<Vec<u8>, String> {
+    let bytes = hex::decode(encrypted).map_err(|e| e.to_string())?;
+    if bytes.len() <= NONCE_LEN {
+        return Err("Encrypted TOTP secret is too short".to_string());
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+
+    cipher()?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|e| e.to_string())
+}
+
+fn cipher() -> Result<Aes256Gcm, String> {
+    let key = std::env::var("TOTP_ENCRYPTION_KEY")
+        .map_err(|_| "TOTP_ENCRYPTION_KEY is not set".to_string())?;
+    let key = hex::decode(key).map_err(|e| format!("Invalid TOTP_ENCRYPTION_KEY: {}", e))?;
+
+    Aes256Gcm::new_from_slice(&key).map_err(|_| "TOTP_ENCRYPTION_KEY must be 32 bytes".to_string())
+}
@@ -1,31 +1,153 @@
-use axum::{extract::FromRequestParts, http::{request::Parts, StatusCode}, async_trait};
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use axum::{extract::{FromRef, FromRequestParts}, http::{request::Parts, Method, StatusCode}, async_trait};
+use chrono::Utc;
+use jsonwebtoken::{decode, decode_header, Algorithm, Validation};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+use crate::jwks::{JwksCache, JwksError};
+use crate::revocation::Denylist;
+
+/// Префикс персональных токенов доступа, которые выдает auth-service
+const PERSONAL_TOKEN_PREFIX: &str = "tsp_";
+/// Префикс access-токенов сторонних приложений (OAuth)
+const OAUTH_TOKEN_PREFIX: &str = "tso_";
+/// Аудитория сервисных токенов, которые принимает внутренний API
+const SERVICE_AUDIENCE: &str = "tasks-service";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub sub: String,
     pub username: String,
     pub email: String,
+    pub email_verified: bool,
+    pub sid: String,
+    pub jti: String,
+    pub role: String,
+    /// Активная организация; задачи пока ей не разграничены
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<String>,
     pub exp: usize,
 }
 
+/// Что разрешено пользователю с неподтвержденной почтой (UNVERIFIED_EMAIL_POLICY)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnverifiedEmailPolicy {
+    /// `block` — любые запросы отклоняются
+    Block,
+    /// `limited` — только чтение
+    Limited,
+}
+
+impl UnverifiedEmailPolicy {
+    pub fn from_env() -> Self {
+        match std::env::var("UNVERIFIED_EMAIL_POLICY").as_deref() {
+            Ok("block") => Self::Block,
+            _ => Self::Limited,
+        }
+    }
+
+    fn allows(self, method: &Method) -> bool {
+        match self {
+            Self::Block => false,
+            Self::Limited => method == Method::GET || method == Method::HEAD,
+        }
+    }
+}
+
+/// Права персонального токена или токена стороннего приложения
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    TasksRead,
+    TasksWrite,
+    SettingsRead,
+    SettingsWrite,
+}
+
+impl Scope {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::TasksRead => "tasks:read",
+            Self::TasksWrite => "tasks:write",
+            Self::SettingsRead => "settings:read",
+            Self::SettingsWrite => "settings:write",
+        }
+    }
+}
+
+/// Роль пользователя из auth-service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    User,
+    Support,
+    Admin,
+}
+
+/// Действия, доступные не только владельцу данных
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    ViewTaskStats,
+}
+
+impl Role {
+    fn parse(role: &str) -> Self {
+        match role {
+            "admin" => Self::Admin,
+            "support" => Self::Support,
+            _ => Self::User,
+        }
+    }
+
+    fn has(self, permission: Permission) -> bool {
+        match permission {
+            Permission::ViewTaskStats => matches!(self, Self::Support | Self::Admin),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AuthUser {
     pub user_id: uuid::Uuid,
     pub username: String,
+    pub role: Role,
+    /// `None` для входа через сессию — такому токену разрешено все
+    pub scopes: Option<Vec<String>>,
+}
+
+impl AuthUser {
+    pub fn require(&self, scope: Scope) -> Result<(), (StatusCode, String)> {
+        match &self.scopes {
+            Some(scopes) if !scopes.iter().any(|s| s == scope.as_str()) => {
+                tracing::warn!(user_id = %self.user_id, scope = scope.as_str(), "Token lacks required scope");
+                Err((StatusCode::FORBIDDEN, format!("Token lacks scope {}", scope.as_str())))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Права роли действуют только при входе через сессию, не через персональный токен
+    pub fn require_permission(&self, permission: Permission) -> Result<(), (StatusCode, String)> {
+        if self.scopes.is_none() && self.role.has(permission) {
+            return Ok(());
+        }
+
+        tracing::warn!(user_id = %self.user_id, role = ?self.role, permission = ?permission, "Insufficient permissions");
+        Err((StatusCode::FORBIDDEN, "Insufficient permissions".to_string()))
+    }
 }
 
 #[async_trait]
 impl<S> FromRequestParts<S> for AuthUser
 where
+    PgPool: FromRef<S>,
+    JwksCache: FromRef<S>,
+    Denylist: FromRef<S>,
+    UnverifiedEmailPolicy: FromRef<S>,
     S: Send + Sync,
 {
     type Rejection = (StatusCode, String);
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let auth_header = parts
             .headers
             .get("authorization")
@@ -35,8 +157,6 @@ where
                 (StatusCode::UNAUTHORIZED, "Missing authorization header".to_string())
             })?;
 
-        tracing::debug!(auth_header = %auth_header, "Authorization header received");
-
         let token = auth_header
             .strip_prefix("Bearer ")
             .ok_or_else(|| {
@@ -44,27 +164,230 @@ where
                 (StatusCode::UNAUTHORIZED, "Invalid authorization format".to_string())
             })?;
 
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(secret.as_bytes()),
-            &Validation::default(),
-        )
+        let (user, email_verified) = if let Some(token) = token.strip_prefix(PERSONAL_TOKEN_PREFIX) {
+            personal_token_user(&PgPool::from_ref(state), token).await?
+        } else if let Some(token) = token.strip_prefix(OAUTH_TOKEN_PREFIX) {
+            oauth_token_user(&PgPool::from_ref(state), token).await?
+        } else {
+            session_token_user(state, token).await?
+        };
+
+        if !email_verified && !UnverifiedEmailPolicy::from_ref(state).allows(&parts.method) {
+            tracing::warn!(user_id = %user.user_id, method = %parts.method, "Request rejected: email not verified");
+            return Err((StatusCode::FORBIDDEN, "Email not verified".to_string()));
+        }
+
+        tracing::info!(user_id = %user.user_id, username = %user.username, "User authenticated successfully");
+
+        Ok(user)
+    }
+}
+
+/// Access-токен (JWT), выданный при входе
+async fn session_token_user<S>(state: &S, token: &str) -> Result<(AuthUser, bool), (StatusCode, String)>
+where
+    JwksCache: FromRef<S>,
+    Denylist: FromRef<S>,
+{
+    let kid = decode_header(token)
+        .ok()
+        .and_then(|header| header.kid)
+        .ok_or_else(|| {
+            tracing::warn!("Token header has no kid");
+            (StatusCode::UNAUTHORIZED, "Invalid or expired token".to_string())
+        })?;
+
+    let key = JwksCache::from_ref(state).key(&kid).await.map_err(|e| match e {
+        JwksError::UnknownKid => {
+            tracing::warn!(kid = %kid, "Token signed with unknown key");
+            (StatusCode::UNAUTHORIZED, "Invalid or expired token".to_string())
+        }
+        JwksError::Unavailable(e) => {
+            tracing::error!(error = %e, "Signing keys are unavailable");
+            (StatusCode::SERVICE_UNAVAILABLE, "Authentication is temporarily unavailable".to_string())
+        }
+    })?;
+
+    let token_data = decode::<Claims>(token, &key, &Validation::new(Algorithm::EdDSA))
         .map_err(|e| {
             tracing::error!(error = %e, "Failed to decode JWT token");
             (StatusCode::UNAUTHORIZED, "Invalid or expired token".to_string())
         })?;
 
-        let user_id = token_data.claims.sub.parse::<uuid::Uuid>()
-            .map_err(|e| {
-                tracing::error!(error = %e, sub = %token_data.claims.sub, "Invalid user id in token");
-                (StatusCode::UNAUTHORIZED, "Invalid user id in token".to_string())
-            })?;
+    let user_id = token_data.claims.sub.parse::<uuid::Uuid>()
+        .map_err(|e| {
+            tracing::error!(error = %e, sub = %token_data.claims.sub, "Invalid user id in token");
+            (StatusCode::UNAUTHORIZED, "Invalid user id in token".to_string())
+        })?;
+
+    // Отозванным может быть как сам токен (logout), так и вся его сессия
+    let revocable_ids = [&token_data.claims.jti, &token_data.claims.sid]
+        .into_iter()
+        .map(|id| id.parse::<uuid::Uuid>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            tracing::error!(error = %e, "Invalid jti or sid in token");
+            (StatusCode::UNAUTHORIZED, "Invalid or expired token".to_string())
+        })?;
 
-        tracing::info!(user_id = %user_id, username = %token_data.claims.username, "User authenticated successfully");
+    if Denylist::from_ref(state).is_revoked(&revocable_ids) {
+        tracing::warn!(user_id = %user_id, jti = %token_data.claims.jti, "Revoked token rejected");
+        return Err((StatusCode::UNAUTHORIZED, "Token has been revoked".to_string()));
+    }
 
-        Ok(AuthUser {
+    let email_verified = token_data.claims.email_verified;
+    Ok((
+        AuthUser {
             user_id,
             username: token_data.claims.username,
-        })
+            role: Role::parse(&token_data.claims.role),
+            scopes: None,
+        },
+        email_verified,
+    ))
+}
+
+/// Персональный токен `tsp_<id>.<secret>`: проверяется по базе, поэтому отзыв действует сразу
+async fn personal_token_user(pool: &PgPool, token: &str) -> Result<(AuthUser, bool), (StatusCode, String)> {
+    let invalid = || (StatusCode::UNAUTHORIZED, "Invalid or expired token".to_string());
+
+    let (id, secret) = token.split_once('.').ok_or_else(invalid)?;
+    let id = id.parse::<uuid::Uuid>().map_err(|_| invalid())?;
+    let secret_hash = hex::encode(Sha256::digest(secret.as_bytes()));
+
+    let row = sqlx::query_as::<_, (uuid::Uuid, String, String, Vec<String>, bool)>(
+        "UPDATE personal_access_tokens t SET last_used_at = $3
+         FROM users u
+         WHERE t.id = $1 AND t.token_hash = $2 AND t.revoked_at IS NULL AND t.expires_at > $3
+           AND u.id = t.user_id AND u.deletion_scheduled_at IS NULL AND u.disabled_at IS NULL
+         RETURNING t.user_id, u.username, u.role, t.scopes, u.email_verified_at IS NOT NULL"
+    )
+    .bind(id)
+    .bind(secret_hash)
+    .bind(Utc::now())
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error checking personal access token");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    let Some((user_id, username, role, scopes, email_verified)) = row else {
+        tracing::warn!(token_id = %id, "Invalid, expired or revoked personal access token");
+        return Err(invalid());
+    };
+
+    Ok((
+        AuthUser {
+            user_id,
+            username,
+            role: Role::parse(&role),
+            scopes: Some(scopes),
+        },
+        email_verified,
+    ))
+}
+
+/// Access-токен стороннего приложения `tso_<id>.<secret>`. Отзыв согласия
+/// или отключение приложения действуют сразу, как и у персональных токенов
+async fn oauth_token_user(pool: &PgPool, token: &str) -> Result<(AuthUser, bool), (StatusCode, String)> {
+    let invalid = || (StatusCode::UNAUTHORIZED, "Invalid or expired token".to_string());
+
+    let (id, secret) = token.split_once('.').ok_or_else(invalid)?;
+    let id = id.parse::<uuid::Uuid>().map_err(|_| invalid())?;
+    let secret_hash = hex::encode(Sha256::digest(secret.as_bytes()));
+
+    let row = sqlx::query_as::<_, (uuid::Uuid, String, String, Vec<String>, bool)>(
+        "UPDATE oauth_tokens t SET last_used_at = $3
+         FROM users u, oauth_clients c
+         WHERE t.id = $1 AND t.access_token_hash = $2 AND t.revoked_at IS NULL AND t.access_expires_at > $3
+           AND c.id = t.client_id AND c.revoked_at IS NULL
+           AND u.id = t.user_id AND u.deletion_scheduled_at IS NULL AND u.disabled_at IS NULL
+         RETURNING t.user_id, u.username, u.role, t.scopes, u.email_verified_at IS NOT NULL"
+    )
+    .bind(id)
+    .bind(secret_hash)
+    .bind(Utc::now())
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Database error checking OAuth access token");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    let Some((user_id, username, role, scopes, email_verified)) = row else {
+        tracing::warn!(grant_id = %id, "Invalid, expired or revoked OAuth access token");
+        return Err(invalid());
+    };
+
+    Ok((
+        AuthUser {
+            user_id,
+            username,
+            role: Role::parse(&role),
+            scopes: Some(scopes),
+        },
+        email_verified,
+    ))
+}
+
+/// Claims токена, которым auth-service подписывает вызовы внутреннего API
+#[derive(Debug, Deserialize)]
+struct ServiceClaims {
+    sub: String,
+}
+
+/// Вызов внутреннего API (`/internal/*`) от другого сервиса. Токен подписан ключом
+/// auth-service и выдан для аудитории `tasks-service`; пользовательский токен сюда не подходит.
+#[derive(Debug, Clone)]
+pub struct ServiceCaller {
+    pub service: String,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ServiceCaller
+where
+    JwksCache: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get("authorization")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .ok_or_else(|| {
+                tracing::warn!("Internal call without service token");
+                (StatusCode::UNAUTHORIZED, "Missing service token".to_string())
+            })?;
+
+        let kid = decode_header(token)
+            .ok()
+            .and_then(|header| header.kid)
+            .ok_or((StatusCode::UNAUTHORIZED, "Invalid service token".to_string()))?;
+
+        let key = JwksCache::from_ref(state).key(&kid).await.map_err(|e| match e {
+            JwksError::UnknownKid => (StatusCode::UNAUTHORIZED, "Invalid service token".to_string()),
+            JwksError::Unavailable(e) => {
+                tracing::error!(error = %e, "Signing keys are unavailable");
+                (StatusCode::SERVICE_UNAVAILABLE, "Authentication is temporarily unavailable".to_string())
+            }
+        })?;
+
+        let mut validation = Validation::new(Algorithm::EdDSA);
+        validation.set_audience(&[SERVICE_AUDIENCE]);
+        validation.set_issuer(&["auth-service"]);
+        validation.set_required_spec_claims(&["exp", "aud", "iss"]);
+
+        let claims = decode::<ServiceClaims>(token, &key, &validation)
+            .map_err(|e| {
+                tracing::warn!(error = %e, "Invalid service token");
+                (StatusCode::UNAUTHORIZED, "Invalid service token".to_string())
+            })?
+            .claims;
+
+        Ok(ServiceCaller { service: claims.sub })
     }
-}
\ No newline at end of file
+}
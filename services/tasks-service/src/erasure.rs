@@ -0,0 +1,133 @@
+use chrono::Utc;
+use serde::Deserialize;
+use sqlx::{postgres::PgListener, PgPool};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Канал, которым auth-service будит потребителей outbox
+const OUTBOX_CHANNEL: &str = "outbox";
+const ACCOUNT_DELETED_TOPIC: &str = "account_deleted";
+/// Страховочный опрос на случай пропущенных уведомлений
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct AccountDeleted {
+    user_id: Uuid,
+}
+
+/// Запускает потребителя событий `account_deleted` из outbox auth-service:
+/// по каждому удаленному аккаунту стирает его задачи и настройки.
+pub fn spawn_consumer(pool: PgPool) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = consume(&pool).await {
+                tracing::error!(error = %e, "Account erasure consumer failed, reconnecting");
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
+
+async fn consume(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(OUTBOX_CHANNEL).await?;
+
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        // Сначала разбираем накопившееся, затем ждем уведомления или опроса
+        while process_next(pool).await? {}
+
+        tokio::select! {
+            notification = listener.recv() => { notification?; }
+            _ = poll.tick() => {}
+        }
+    }
+}
+
+/// Обрабатывает одно событие. Событие помечается обработанным в той же транзакции,
+/// что и удаление данных, поэтому при сбое оно просто будет обработано повторно.
+async fn process_next(pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let event = sqlx::query_as::<_, (Uuid, serde_json::Value)>(
+        "SELECT id, payload FROM outbox_events
+         WHERE topic = $1 AND processed_at IS NULL
+         ORDER BY created_at
+         LIMIT 1
+         FOR UPDATE SKIP LOCKED"
+    )
+    .bind(ACCOUNT_DELETED_TOPIC)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some((event_id, payload)) = event else {
+        return Ok(false);
+    };
+
+    match serde_json::from_value::<AccountDeleted>(payload) {
+        Ok(AccountDeleted { user_id }) => {
+            let tasks = sqlx::query("DELETE FROM tasks WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+            let series = sqlx::query("DELETE FROM task_series WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+            // Связи с задачами уже удалены каскадом вместе с задачами
+            let labels = sqlx::query("DELETE FROM labels WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+            let projects = sqlx::query("DELETE FROM projects WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+            let notifications = sqlx::query("DELETE FROM notifications WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+            let settings = sqlx::query("DELETE FROM user_settings WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+            // Журнал для GDPR: только id и объемы, без содержимого
+            sqlx::query(
+                "INSERT INTO data_erasure_log (id, user_id, service, details, erased_at)
+                 VALUES ($1, $2, 'tasks-service', $3, $4)"
+            )
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(serde_json::json!({ "tasks": tasks, "task_series": series, "projects": projects, "labels": labels, "notifications": notifications, "user_settings": settings }))
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+
+            tracing::info!(user_id = %user_id, tasks, notifications, settings, event = "account_erased", "User data erased");
+        }
+        Err(e) => tracing::error!(error = %e, event_id = %event_id, "Malformed account_deleted event, skipping"),
+    }
+
+    sqlx::query("UPDATE outbox_events SET processed_at = $2 WHERE id = $1")
+        .bind(event_id)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
@@ -0,0 +1,44 @@
+use axum::{extract::{Query, State}, http::StatusCode, Json};
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::{
+    auth::{AuthUser, Permission},
+    models::task::{TaskStats, TaskStatsQuery},
+};
+
+#[utoipa::path(
+    get, path = "/admin/tasks/stats",
+    params(("user_id" = Uuid, Query, description = "ID пользователя")),
+    responses(
+        (status = 200, description = "Количество задач пользователя", body = TaskStats),
+        (status = 403, description = "Недостаточно прав"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "admin"
+)]
+pub async fn task_stats(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Query(query): Query<TaskStatsQuery>,
+) -> Result<Json<TaskStats>, (StatusCode, String)> {
+    auth.require_permission(Permission::ViewTaskStats)?;
+
+    tracing::info!(admin_id = %auth.user_id, user_id = %query.user_id, "Fetching task stats");
+
+    let stats = sqlx::query_as::<_, TaskStats>(
+        "SELECT $1 AS user_id,
+                COUNT(*) AS total,
+                COUNT(*) FILTER (WHERE status = 'active') AS active,
+                COUNT(*) FILTER (WHERE status = 'completed') AS completed,
+                COUNT(*) FILTER (WHERE status = 'active' AND due_date < $2) AS overdue
+         FROM tasks WHERE user_id = $1"
+    )
+    .bind(query.user_id)
+    .bind(Utc::now().date_naive())
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(stats))
+}
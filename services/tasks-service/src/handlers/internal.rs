@@ -0,0 +1,64 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    auth::ServiceCaller,
+    handlers::labels::load_labels,
+    models::{export::UserDataExport, label::Label, notification::Notification, project::Project, settings::UserSettings, task::Task},
+};
+
+/// Все данные пользователя для выгрузки. Доступно только другим сервисам.
+pub async fn export_user_data(
+    caller: ServiceCaller,
+    State(pool): State<PgPool>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<UserDataExport>, (StatusCode, String)> {
+    tracing::info!(user_id = %user_id, caller = %caller.service, "Exporting user data");
+
+    let mut tasks = sqlx::query_as::<_, Task>(
+        "SELECT * FROM tasks WHERE user_id = $1 ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    load_labels(&pool, &mut tasks)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let labels = sqlx::query_as::<_, Label>(
+        "SELECT * FROM labels WHERE user_id = $1 ORDER BY lower(name)"
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let projects = sqlx::query_as::<_, Project>(
+        "SELECT * FROM projects WHERE user_id = $1 ORDER BY position, created_at"
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let settings = sqlx::query_as::<_, UserSettings>(
+        "SELECT * FROM user_settings WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let notifications = sqlx::query_as::<_, Notification>(
+        "SELECT * FROM notifications WHERE user_id = $1 ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(UserDataExport { tasks, projects, labels, settings, notifications }))
+}
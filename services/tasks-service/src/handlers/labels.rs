@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    auth::{AuthUser, Scope},
+    handlers::projects::validate_color,
+    models::{
+        label::{AttachLabelsRequest, CreateLabelRequest, Label, MergeLabelRequest, TaskLabel, UpdateLabelRequest, DEFAULT_COLOR},
+        task::Task,
+    },
+};
+
+fn validate_name(name: &str) -> Result<&str, (StatusCode, String)> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 50 {
+        return Err((StatusCode::BAD_REQUEST, "Label name must be 1-50 characters".to_string()));
+    }
+    Ok(name)
+}
+
+/// Нарушение уникальности имени метки превращается в 409
+fn map_name_conflict(e: sqlx::Error) -> (StatusCode, String) {
+    if e.as_database_error().is_some_and(|db| db.is_unique_violation()) {
+        return (StatusCode::CONFLICT, "Label with this name already exists".to_string());
+    }
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+/// Заполняет `labels` у задач одним запросом на весь список
+pub async fn load_labels<'e, E: PgExecutor<'e>>(executor: E, tasks: &mut [Task]) -> Result<(), sqlx::Error> {
+    if tasks.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<Uuid> = tasks.iter().map(|task| task.id).collect();
+    let rows = sqlx::query_as::<_, TaskLabel>(
+        "SELECT tl.task_id, l.id, l.name, l.color
+         FROM task_labels tl
+         JOIN labels l ON l.id = tl.label_id
+         WHERE tl.task_id = ANY($1)
+         ORDER BY lower(l.name)"
+    )
+    .bind(&ids)
+    .fetch_all(executor)
+    .await?;
+
+    let mut by_task: HashMap<Uuid, Vec<TaskLabel>> = HashMap::new();
+    for row in rows {
+        by_task.entry(row.task_id).or_default().push(row);
+    }
+    for task in tasks {
+        task.labels = by_task.remove(&task.id).unwrap_or_default();
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    get, path = "/labels",
+    responses((status = 200, description = "Метки пользователя по алфавиту", body = Vec<Label>)),
+    security(("bearer_auth" = [])),
+    tag = "labels"
+)]
+pub async fn list_labels(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<Label>>, (StatusCode, String)> {
+    auth.require(Scope::TasksRead)?;
+
+    let labels = sqlx::query_as::<_, Label>(
+        "SELECT * FROM labels WHERE user_id = $1 ORDER BY lower(name)"
+    )
+    .bind(auth.user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(labels))
+}
+
+#[utoipa::path(
+    post, path = "/labels",
+    request_body = CreateLabelRequest,
+    responses(
+        (status = 201, description = "Метка создана", body = Label),
+        (status = 400, description = "Неверное имя или цвет"),
+        (status = 409, description = "Метка с таким именем уже есть"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "labels"
+)]
+pub async fn create_label(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Json(req): Json<CreateLabelRequest>,
+) -> Result<(StatusCode, Json<Label>), (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let name = validate_name(&req.name)?;
+    let color = validate_color(req.color.as_deref().unwrap_or(DEFAULT_COLOR))?;
+    let now = Utc::now();
+
+    let label = sqlx::query_as::<_, Label>(
+        "INSERT INTO labels (id, user_id, name, color, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $5)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(auth.user_id)
+    .bind(name)
+    .bind(&color)
+    .bind(now)
+    .fetch_one(&pool)
+    .await
+    .map_err(map_name_conflict)?;
+
+    tracing::info!(user_id = %auth.user_id, label_id = %label.id, "Label created");
+    Ok((StatusCode::CREATED, Json(label)))
+}
+
+#[utoipa::path(
+    put, path = "/labels/{id}",
+    params(("id" = Uuid, Path, description = "ID метки")),
+    request_body = UpdateLabelRequest,
+    responses(
+        (status = 200, description = "Метка переименована или перекрашена", body = Label),
+        (status = 400, description = "Неверное имя или цвет"),
+        (status = 404, description = "Метка не найдена"),
+        (status = 409, description = "Метка с таким именем уже есть — используйте слияние"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "labels"
+)]
+pub async fn update_label(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateLabelRequest>,
+) -> Result<Json<Label>, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let name = req.name.as_deref().map(validate_name).transpose()?;
+    let color = req.color.as_deref().map(validate_color).transpose()?;
+
+    // Задачи ссылаются на метку по id, поэтому переименование — одна строка
+    let label = sqlx::query_as::<_, Label>(
+        "UPDATE labels SET
+            name = COALESCE($3, name),
+            color = COALESCE($4, color),
+            updated_at = $5
+         WHERE id = $1 AND user_id = $2
+         RETURNING *"
+    )
+    .bind(id)
+    .bind(auth.user_id)
+    .bind(name)
+    .bind(color)
+    .bind(Utc::now())
+    .fetch_optional(&pool)
+    .await
+    .map_err(map_name_conflict)?
+    .ok_or((StatusCode::NOT_FOUND, "Label not found".to_string()))?;
+
+    Ok(Json(label))
+}
+
+#[utoipa::path(
+    post, path = "/labels/{id}/merge",
+    params(("id" = Uuid, Path, description = "ID метки, которая исчезнет")),
+    request_body = MergeLabelRequest,
+    responses(
+        (status = 200, description = "Задачи перенесены в целевую метку, исходная удалена", body = Label),
+        (status = 400, description = "Нельзя слить метку саму с собой"),
+        (status = 404, description = "Метка не найдена"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "labels"
+)]
+pub async fn merge_label(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<MergeLabelRequest>,
+) -> Result<Json<Label>, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    if id == req.into {
+        return Err((StatusCode::BAD_REQUEST, "Cannot merge a label into itself".to_string()));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Блокируем обе метки в одном порядке, чтобы встречные слияния не зависли
+    let locked = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM labels WHERE id = ANY($1) AND user_id = $2 ORDER BY id FOR UPDATE"
+    )
+    .bind([id, req.into])
+    .bind(auth.user_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if locked.len() != 2 {
+        return Err((StatusCode::NOT_FOUND, "Label not found".to_string()));
+    }
+
+    let moved = sqlx::query(
+        "INSERT INTO task_labels (task_id, label_id)
+         SELECT task_id, $2 FROM task_labels WHERE label_id = $1
+         ON CONFLICT DO NOTHING"
+    )
+    .bind(id)
+    .bind(req.into)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .rows_affected();
+
+    sqlx::query("DELETE FROM labels WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let target = sqlx::query_as::<_, Label>(
+        "UPDATE labels SET updated_at = $2 WHERE id = $1 RETURNING *"
+    )
+    .bind(req.into)
+    .bind(Utc::now())
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(user_id = %auth.user_id, from = %id, into = %req.into, moved, "Labels merged");
+    Ok(Json(target))
+}
+
+#[utoipa::path(
+    delete, path = "/labels/{id}",
+    params(("id" = Uuid, Path, description = "ID метки")),
+    responses(
+        (status = 204, description = "Метка удалена и снята со всех задач"),
+        (status = 404, description = "Метка не найдена"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "labels"
+)]
+pub async fn delete_label(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let result = sqlx::query("DELETE FROM labels WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(auth.user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Label not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post, path = "/tasks/{id}/labels",
+    params(("id" = Uuid, Path, description = "ID задачи")),
+    request_body = AttachLabelsRequest,
+    responses(
+        (status = 200, description = "Метки добавлены к задаче", body = Task),
+        (status = 404, description = "Задача или метка не найдены"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "labels"
+)]
+pub async fn attach_labels(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<AttachLabelsRequest>,
+) -> Result<Json<Task>, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let mut label_ids = req.label_ids;
+    label_ids.sort();
+    label_ids.dedup();
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut task = sqlx::query_as::<_, Task>(
+        "SELECT * FROM tasks WHERE id = $1 AND user_id = $2"
+    )
+    .bind(id)
+    .bind(auth.user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, "Task not found".to_string()))?;
+
+    // Чужие и несуществующие метки не отличаются: обе дают 404
+    let owned = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM labels WHERE id = ANY($1) AND user_id = $2"
+    )
+    .bind(&label_ids)
+    .bind(auth.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if owned as usize != label_ids.len() {
+        return Err((StatusCode::NOT_FOUND, "Label not found".to_string()));
+    }
+
+    let attached = sqlx::query(
+        "INSERT INTO task_labels (task_id, label_id)
+         SELECT $1, id FROM labels WHERE id = ANY($2) AND user_id = $3
+         ON CONFLICT DO NOTHING"
+    )
+    .bind(id)
+    .bind(&label_ids)
+    .bind(auth.user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .rows_affected();
+
+    load_labels(&mut *tx, std::slice::from_mut(&mut task))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(user_id = %auth.user_id, task_id = %id, attached, "Labels attached to task");
+    Ok(Json(task))
+}
+
+#[utoipa::path(
+    delete, path = "/tasks/{id}/labels/{label_id}",
+    params(
+        ("id" = Uuid, Path, description = "ID задачи"),
+        ("label_id" = Uuid, Path, description = "ID метки"),
+    ),
+    responses(
+        (status = 204, description = "Метка снята с задачи"),
+        (status = 404, description = "Задача не найдена или метка на ней не стоит"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "labels"
+)]
+pub async fn detach_label(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((id, label_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let result = sqlx::query(
+        "DELETE FROM task_labels tl USING tasks t
+         WHERE tl.task_id = $1 AND tl.label_id = $2
+           AND t.id = tl.task_id AND t.user_id = $3"
+    )
+    .bind(id)
+    .bind(label_id)
+    .bind(auth.user_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Label is not attached to this task".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,2 +1,7 @@
 pub mod tasks;
-pub mod settings;
\ No newline at end of file
+pub mod settings;
+pub mod internal;
+pub mod admin;
+pub mod notifications;
+pub mod projects;
+pub mod labels;
@@ -0,0 +1,176 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    auth::{AuthUser, Scope},
+    models::notification::{MarkAllReadResponse, Notification, NotificationListResponse, NotificationQuery},
+};
+
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 100;
+
+/// Курсор — время создания (мкс) и id последнего уведомления страницы.
+/// Для клиента это непрозрачная строка.
+fn encode_cursor(notification: &Notification) -> String {
+    format!("{}_{}", notification.created_at.timestamp_micros(), notification.id)
+}
+
+fn decode_cursor(cursor: &str) -> Result<(DateTime<Utc>, Uuid), (StatusCode, String)> {
+    cursor.split_once('_')
+        .and_then(|(micros, id)| {
+            let created_at = DateTime::from_timestamp_micros(micros.parse().ok()?)?;
+            Some((created_at, id.parse().ok()?))
+        })
+        .ok_or((StatusCode::BAD_REQUEST, "Invalid cursor".to_string()))
+}
+
+#[utoipa::path(
+    get, path = "/notifications",
+    params(NotificationQuery),
+    responses(
+        (status = 200, description = "Уведомления, новые сначала", body = NotificationListResponse),
+        (status = 400, description = "Неверный курсор"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "notifications"
+)]
+pub async fn list_notifications(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Query(query): Query<NotificationQuery>,
+) -> Result<Json<NotificationListResponse>, (StatusCode, String)> {
+    auth.require(Scope::TasksRead)?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let cursor = query.cursor.as_deref().map(decode_cursor).transpose()?;
+
+    // Берем на одну запись больше, чтобы понять, есть ли следующая страница
+    let mut items = sqlx::query_as::<_, Notification>(
+        "SELECT * FROM notifications WHERE user_id = $1
+         AND ($2::bool IS NOT TRUE OR read_at IS NULL)
+         AND ($3::timestamptz IS NULL OR (created_at, id) < ($3, $4))
+         ORDER BY created_at DESC, id DESC
+         LIMIT $5"
+    )
+    .bind(auth.user_id)
+    .bind(query.unread_only)
+    .bind(cursor.map(|(created_at, _)| created_at))
+    .bind(cursor.map(|(_, id)| id))
+    .bind(limit + 1)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let next_cursor = if items.len() as i64 > limit {
+        items.truncate(limit as usize);
+        items.last().map(encode_cursor)
+    } else {
+        None
+    };
+
+    let unread_count = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND read_at IS NULL"
+    )
+    .bind(auth.user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(NotificationListResponse { items, unread_count, next_cursor }))
+}
+
+#[utoipa::path(
+    patch, path = "/notifications/{id}/read",
+    params(("id" = Uuid, Path, description = "ID уведомления")),
+    responses(
+        (status = 200, description = "Уведомление прочитано", body = Notification),
+        (status = 404, description = "Уведомление не найдено"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "notifications"
+)]
+pub async fn mark_read(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Notification>, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let notification = sqlx::query_as::<_, Notification>(
+        "UPDATE notifications SET read_at = COALESCE(read_at, $3)
+         WHERE id = $1 AND user_id = $2
+         RETURNING *"
+    )
+    .bind(id)
+    .bind(auth.user_id)
+    .bind(Utc::now())
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, "Notification not found".to_string()))?;
+
+    Ok(Json(notification))
+}
+
+#[utoipa::path(
+    post, path = "/notifications/read-all",
+    responses((status = 200, description = "Все уведомления прочитаны", body = MarkAllReadResponse)),
+    security(("bearer_auth" = [])),
+    tag = "notifications"
+)]
+pub async fn mark_all_read(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+) -> Result<Json<MarkAllReadResponse>, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let updated = sqlx::query(
+        "UPDATE notifications SET read_at = $2 WHERE user_id = $1 AND read_at IS NULL"
+    )
+    .bind(auth.user_id)
+    .bind(Utc::now())
+    .execute(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .rows_affected();
+
+    tracing::info!(user_id = %auth.user_id, updated, "All notifications marked as read");
+    Ok(Json(MarkAllReadResponse { updated }))
+}
+
+#[utoipa::path(
+    delete, path = "/notifications/{id}",
+    params(("id" = Uuid, Path, description = "ID уведомления")),
+    responses(
+        (status = 204, description = "Уведомление удалено"),
+        (status = 404, description = "Уведомление не найдено"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "notifications"
+)]
+pub async fn delete_notification(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let result = sqlx::query("DELETE FROM notifications WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(auth.user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Notification not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
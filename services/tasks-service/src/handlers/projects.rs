@@ -0,0 +1,348 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    auth::{AuthUser, Scope},
+    models::project::{
+        CreateProjectRequest, DeleteProjectQuery, Project, ProjectListQuery, ProjectTasksAction,
+        ReorderProjectsRequest, UpdateProjectRequest, DEFAULT_COLOR,
+    },
+};
+
+fn validate_name(name: &str) -> Result<&str, (StatusCode, String)> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err((StatusCode::BAD_REQUEST, "Project name must be 1-100 characters".to_string()));
+    }
+    Ok(name)
+}
+
+/// Цвет в виде `#RRGGBB`, хранится в верхнем регистре
+pub fn validate_color(color: &str) -> Result<String, (StatusCode, String)> {
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if !valid {
+        return Err((StatusCode::BAD_REQUEST, "Color must be in #RRGGBB format".to_string()));
+    }
+    Ok(color.to_ascii_uppercase())
+}
+
+/// Проверяет, что в проект можно положить задачу: он принадлежит пользователю и не в архиве
+pub async fn ensure_assignable(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    project_id: Uuid,
+) -> Result<(), (StatusCode, String)> {
+    let archived = sqlx::query_scalar::<_, bool>(
+        "SELECT archived_at IS NOT NULL FROM projects WHERE id = $1 AND user_id = $2"
+    )
+    .bind(project_id)
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, "Project not found".to_string()))?;
+
+    if archived {
+        return Err((StatusCode::BAD_REQUEST, "Project is archived".to_string()));
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get, path = "/projects",
+    params(ProjectListQuery),
+    responses((status = 200, description = "Проекты в порядке пользователя", body = Vec<Project>)),
+    security(("bearer_auth" = [])),
+    tag = "projects"
+)]
+pub async fn list_projects(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Query(query): Query<ProjectListQuery>,
+) -> Result<Json<Vec<Project>>, (StatusCode, String)> {
+    auth.require(Scope::TasksRead)?;
+
+    let projects = sqlx::query_as::<_, Project>(
+        "SELECT * FROM projects WHERE user_id = $1
+         AND ($2::bool IS TRUE OR archived_at IS NULL)
+         ORDER BY position, created_at"
+    )
+    .bind(auth.user_id)
+    .bind(query.include_archived)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(projects))
+}
+
+#[utoipa::path(
+    post, path = "/projects",
+    request_body = CreateProjectRequest,
+    responses(
+        (status = 201, description = "Проект создан и добавлен в конец списка", body = Project),
+        (status = 400, description = "Неверное имя или цвет"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "projects"
+)]
+pub async fn create_project(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Json(req): Json<CreateProjectRequest>,
+) -> Result<(StatusCode, Json<Project>), (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let name = validate_name(&req.name)?;
+    let color = validate_color(req.color.as_deref().unwrap_or(DEFAULT_COLOR))?;
+    let now = Utc::now();
+
+    let project = sqlx::query_as::<_, Project>(
+        "INSERT INTO projects (id, user_id, name, color, position, created_at, updated_at)
+         VALUES ($1, $2, $3, $4,
+                 (SELECT COALESCE(MAX(position) + 1, 0) FROM projects WHERE user_id = $2),
+                 $5, $5)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(auth.user_id)
+    .bind(name)
+    .bind(&color)
+    .bind(now)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Failed to create project");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    tracing::info!(user_id = %auth.user_id, project_id = %project.id, "Project created");
+    Ok((StatusCode::CREATED, Json(project)))
+}
+
+#[utoipa::path(
+    get, path = "/projects/{id}",
+    params(("id" = Uuid, Path, description = "ID проекта")),
+    responses(
+        (status = 200, description = "Проект", body = Project),
+        (status = 404, description = "Проект не найден"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "projects"
+)]
+pub async fn get_project(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Project>, (StatusCode, String)> {
+    auth.require(Scope::TasksRead)?;
+
+    let project = sqlx::query_as::<_, Project>(
+        "SELECT * FROM projects WHERE id = $1 AND user_id = $2"
+    )
+    .bind(id)
+    .bind(auth.user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, "Project not found".to_string()))?;
+
+    Ok(Json(project))
+}
+
+#[utoipa::path(
+    put, path = "/projects/{id}",
+    params(("id" = Uuid, Path, description = "ID проекта")),
+    request_body = UpdateProjectRequest,
+    responses(
+        (status = 200, description = "Проект обновлен", body = Project),
+        (status = 400, description = "Неверное имя или цвет"),
+        (status = 404, description = "Проект не найден"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "projects"
+)]
+pub async fn update_project(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateProjectRequest>,
+) -> Result<Json<Project>, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let name = req.name.as_deref().map(validate_name).transpose()?;
+    let color = req.color.as_deref().map(validate_color).transpose()?;
+    let now = Utc::now();
+
+    // Повторная архивация не сдвигает archived_at
+    let project = sqlx::query_as::<_, Project>(
+        "UPDATE projects SET
+            name = COALESCE($3, name),
+            color = COALESCE($4, color),
+            archived_at = CASE
+                WHEN $5::bool IS NULL THEN archived_at
+                WHEN $5 THEN COALESCE(archived_at, $6)
+                ELSE NULL
+            END,
+            updated_at = $6
+         WHERE id = $1 AND user_id = $2
+         RETURNING *"
+    )
+    .bind(id)
+    .bind(auth.user_id)
+    .bind(name)
+    .bind(color)
+    .bind(req.archived)
+    .bind(now)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, "Project not found".to_string()))?;
+
+    tracing::info!(
+        user_id = %auth.user_id,
+        project_id = %id,
+        archived = project.archived_at.is_some(),
+        "Project updated"
+    );
+
+    Ok(Json(project))
+}
+
+#[utoipa::path(
+    put, path = "/projects/order",
+    request_body = ReorderProjectsRequest,
+    responses(
+        (status = 200, description = "Проекты в новом порядке", body = Vec<Project>),
+        (status = 400, description = "Список не совпадает с проектами пользователя"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "projects"
+)]
+pub async fn reorder_projects(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Json(req): Json<ReorderProjectsRequest>,
+) -> Result<Json<Vec<Project>>, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Блокировка не дает параллельно созданному проекту получить занятую позицию
+    let mut current = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM projects WHERE user_id = $1 FOR UPDATE"
+    )
+    .bind(auth.user_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut requested = req.project_ids.clone();
+    current.sort();
+    requested.sort();
+    if current != requested {
+        return Err((StatusCode::BAD_REQUEST, "project_ids must list each of your projects exactly once".to_string()));
+    }
+
+    sqlx::query(
+        "UPDATE projects p SET position = o.ordinality - 1, updated_at = $3
+         FROM unnest($2::uuid[]) WITH ORDINALITY AS o(id, ordinality)
+         WHERE p.id = o.id AND p.user_id = $1"
+    )
+    .bind(auth.user_id)
+    .bind(&req.project_ids)
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let projects = sqlx::query_as::<_, Project>(
+        "SELECT * FROM projects WHERE user_id = $1 ORDER BY position, created_at"
+    )
+    .bind(auth.user_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(projects))
+}
+
+#[utoipa::path(
+    delete, path = "/projects/{id}",
+    params(("id" = Uuid, Path, description = "ID проекта"), DeleteProjectQuery),
+    responses(
+        (status = 204, description = "Проект удален, задачи перенесены во \"Входящие\" или удалены"),
+        (status = 404, description = "Проект не найден"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "projects"
+)]
+pub async fn delete_project(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DeleteProjectQuery>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let found = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM projects WHERE id = $1 AND user_id = $2 FOR UPDATE"
+    )
+    .bind(id)
+    .bind(auth.user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if found.is_none() {
+        return Err((StatusCode::NOT_FOUND, "Project not found".to_string()));
+    }
+
+    // При переносе задачи и серии уходят во "Входящие" через ON DELETE SET NULL
+    let deleted_tasks = if query.tasks == ProjectTasksAction::Delete {
+        sqlx::query("DELETE FROM task_series WHERE project_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        sqlx::query("DELETE FROM tasks WHERE project_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .rows_affected()
+    } else {
+        0
+    };
+
+    sqlx::query("DELETE FROM projects WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(
+        user_id = %auth.user_id,
+        project_id = %id,
+        tasks = ?query.tasks,
+        deleted_tasks,
+        "Project deleted"
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -3,7 +3,11 @@ use chrono::Utc;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::{auth::AuthUser, models::settings::{UpdateSettingsRequest, UserSettings}};
+use crate::{
+    auth::{AuthUser, Scope},
+    models::settings::{UpdateSettingsRequest, UserSettings},
+    notifications::Category,
+};
 
 #[utoipa::path(
     get, path = "/settings",
@@ -15,6 +19,8 @@ pub async fn get_settings(
     auth: AuthUser,
     State(pool): State<PgPool>,
 ) -> Result<Json<UserSettings>, (StatusCode, String)> {
+    auth.require(Scope::SettingsRead)?;
+
     let settings = sqlx::query_as::<_, UserSettings>(
         "INSERT INTO user_settings (id, user_id, theme, notifications_enabled, updated_at)
          VALUES ($1, $2, 'light', true, $3)
@@ -34,7 +40,10 @@ pub async fn get_settings(
 #[utoipa::path(
     put, path = "/settings",
     request_body = UpdateSettingsRequest,
-    responses((status = 200, description = "Настройки обновлены", body = UserSettings)),
+    responses(
+        (status = 200, description = "Настройки обновлены", body = UserSettings),
+        (status = 400, description = "Неизвестная категория уведомлений"),
+    ),
     security(("bearer_auth" = [])),
     tag = "settings"
 )]
@@ -43,12 +52,28 @@ pub async fn update_settings(
     State(pool): State<PgPool>,
     Json(req): Json<UpdateSettingsRequest>,
 ) -> Result<Json<UserSettings>, (StatusCode, String)> {
+    auth.require(Scope::SettingsWrite)?;
+
+    let muted_categories = match req.muted_categories {
+        Some(categories) => {
+            if let Some(unknown) = categories.iter().find(|c| Category::parse(c).is_none()) {
+                return Err((StatusCode::BAD_REQUEST, format!("Unknown notification category: {}", unknown)));
+            }
+            let mut categories = categories;
+            categories.sort();
+            categories.dedup();
+            Some(categories)
+        }
+        None => None,
+    };
+
     let settings = sqlx::query_as::<_, UserSettings>(
-        "INSERT INTO user_settings (id, user_id, theme, notifications_enabled, updated_at)
-         VALUES ($1, $2, COALESCE($3, 'light'), COALESCE($4, true), $5)
+        "INSERT INTO user_settings (id, user_id, theme, notifications_enabled, muted_categories, updated_at)
+         VALUES ($1, $2, COALESCE($3, 'light'), COALESCE($4, true), COALESCE($6, '{}'), $5)
          ON CONFLICT (user_id) DO UPDATE SET
              theme = COALESCE($3, user_settings.theme),
              notifications_enabled = COALESCE($4, user_settings.notifications_enabled),
+             muted_categories = COALESCE($6, user_settings.muted_categories),
              updated_at = $5
          RETURNING *"
     )
@@ -57,6 +82,7 @@ pub async fn update_settings(
     .bind(req.theme)
     .bind(req.notifications_enabled)
     .bind(Utc::now())
+    .bind(muted_categories)
     .fetch_one(&pool)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
@@ -3,20 +3,31 @@ use axum::{
     http::StatusCode,
     Json,
 };
-use chrono::Utc;
-use sqlx::PgPool;
+use chrono::{NaiveDate, Utc};
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 
 use crate::{
-    auth::AuthUser,
-    models::task::{CreateTaskRequest, Task, TaskFilters, UpdateTaskRequest},
+    auth::{AuthUser, Scope},
+    handlers::{labels, projects},
+    models::{
+        label::LabelMatch,
+        task::{CreateTaskRequest, EditScope, EditScopeQuery, Task, TaskFilters, TaskSeries, UpdateTaskRequest},
+    },
+    recurrence::RRule,
 };
 
 #[utoipa::path(
     get, path = "/tasks",
     params(("status" = Option<String>, Query, description = "Фильтр по статусу"),
-           ("priority" = Option<String>, Query, description = "Фильтр по приоритету")),
-    responses((status = 200, description = "Список задач", body = Vec<Task>)),
+           ("priority" = Option<String>, Query, description = "Фильтр по приоритету"),
+           ("project_id" = Option<String>, Query, description = "ID проекта или `inbox`; без фильтра задачи архивных проектов не показываются"),
+           ("labels" = Option<String>, Query, description = "ID меток через запятую"),
+           ("label_match" = Option<LabelMatch>, Query, description = "`any` (по умолчанию) — хотя бы одна метка, `all` — все")),
+    responses(
+        (status = 200, description = "Список задач с метками", body = Vec<Task>),
+        (status = 400, description = "Неверный project_id или labels"),
+    ),
     security(("bearer_auth" = [])),
     tag = "tasks"
 )]
@@ -25,26 +36,74 @@ pub async fn list_tasks(
     State(pool): State<PgPool>,
     Query(filters): Query<TaskFilters>,
 ) -> Result<Json<Vec<Task>>, (StatusCode, String)> {
-    let tasks = sqlx::query_as::<_, Task>(
-        "SELECT * FROM tasks WHERE user_id = $1
+    auth.require(Scope::TasksRead)?;
+
+    let project = match filters.project_id.as_deref() {
+        None => ProjectFilter::Any,
+        Some("inbox") => ProjectFilter::Inbox,
+        Some(id) => ProjectFilter::Project(
+            id.parse().map_err(|_| (StatusCode::BAD_REQUEST, "project_id must be a UUID or inbox".to_string()))?,
+        ),
+    };
+
+    let mut label_ids = filters.labels.as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| id.parse::<Uuid>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "labels must be comma-separated UUIDs".to_string()))?;
+    label_ids.sort();
+    label_ids.dedup();
+
+    // Для all задача должна нести каждую из меток, для any — хотя бы одну
+    let required_labels = match filters.label_match {
+        LabelMatch::Any => label_ids.len().min(1),
+        LabelMatch::All => label_ids.len(),
+    } as i64;
+
+    let mut tasks = sqlx::query_as::<_, Task>(
+        "SELECT * FROM tasks t WHERE user_id = $1
          AND ($2::text IS NULL OR status = $2)
          AND ($3::text IS NULL OR priority = $3)
+         AND CASE $4
+             WHEN 'inbox' THEN project_id IS NULL
+             WHEN 'project' THEN project_id = $5
+             ELSE NOT EXISTS (
+                 SELECT 1 FROM projects p WHERE p.id = t.project_id AND p.archived_at IS NOT NULL
+             )
+         END
+         AND (
+             SELECT COUNT(*) FROM task_labels tl WHERE tl.task_id = t.id AND tl.label_id = ANY($6)
+         ) >= $7
          ORDER BY created_at DESC"
     )
     .bind(auth.user_id)
     .bind(filters.status)
     .bind(filters.priority)
+    .bind(project.kind())
+    .bind(project.id())
+    .bind(&label_ids)
+    .bind(required_labels)
     .fetch_all(&pool)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
+    labels::load_labels(&pool, &mut tasks)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
     Ok(Json(tasks))
 }
 
 #[utoipa::path(
     post, path = "/tasks",
     request_body = CreateTaskRequest,
-    responses((status = 201, description = "Задача создана", body = Task)),
+    responses(
+        (status = 201, description = "Задача создана", body = Task),
+        (status = 400, description = "Неверное правило повторения"),
+    ),
     security(("bearer_auth" = [])),
     tag = "tasks"
 )]
@@ -53,18 +112,32 @@ pub async fn create_task(
     State(pool): State<PgPool>,
     Json(req): Json<CreateTaskRequest>,
 ) -> Result<(StatusCode, Json<Task>), (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let rule = match RuleChange::from_request(req.recurrence_rule.as_deref())? {
+        RuleChange::Set(rule) => Some(rule),
+        RuleChange::Keep | RuleChange::Clear => None,
+    };
+
     tracing::info!(
         user_id = %auth.user_id,
         title = %req.title,
         priority = %req.priority,
         due_date = %req.due_date,
+        recurring = rule.is_some(),
         "Creating task"
     );
-    
-    let task = sqlx::query_as::<_, Task>(
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if let Some(project_id) = req.project_id {
+        projects::ensure_assignable(&mut tx, auth.user_id, project_id).await?;
+    }
+
+    let mut task = sqlx::query_as::<_, Task>(
         "INSERT INTO tasks (id, user_id, title, description, priority, due_date,
-                            reminder_days, reminder_hours, status, created_at)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'active', $9)
+                            reminder_days, reminder_hours, status, created_at, project_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'active', $9, $10)
          RETURNING *"
     )
     .bind(Uuid::new_v4())
@@ -76,13 +149,25 @@ pub async fn create_task(
     .bind(req.reminder_days)
     .bind(req.reminder_hours)
     .bind(Utc::now())
-    .fetch_one(&pool)
+    .bind(req.project_id)
+    .fetch_one(&mut *tx)
     .await
     .map_err(|e| {
         tracing::error!(error = %e, "Failed to create task");
         (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
     })?;
 
+    if let Some(rule) = rule {
+        task = start_series(&mut tx, &task, &rule, req.recurrence_from_completion)
+            .await
+            .map_err(|e| {
+                tracing::error!(error = %e, "Failed to create task series");
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            })?;
+    }
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
     tracing::info!(task_id = %task.id, "Task created successfully");
     Ok((StatusCode::CREATED, Json(task)))
 }
@@ -102,7 +187,9 @@ pub async fn get_task(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<Task>, (StatusCode, String)> {
-    let task = sqlx::query_as::<_, Task>(
+    auth.require(Scope::TasksRead)?;
+
+    let mut task = sqlx::query_as::<_, Task>(
         "SELECT * FROM tasks WHERE id = $1 AND user_id = $2"
     )
     .bind(id)
@@ -112,16 +199,21 @@ pub async fn get_task(
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
     .ok_or((StatusCode::NOT_FOUND, "Task not found".to_string()))?;
 
+    labels::load_labels(&pool, std::slice::from_mut(&mut task))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
     Ok(Json(task))
 }
 
 #[utoipa::path(
     put, path = "/tasks/{id}",
-    params(("id" = Uuid, Path, description = "ID задачи")),
+    params(("id" = Uuid, Path, description = "ID задачи"), EditScopeQuery),
     request_body = UpdateTaskRequest,
     responses(
         (status = 200, description = "Задача обновлена", body = Task),
-        (status = 404, description = "Задача не найдена"),
+        (status = 400, description = "Неверное правило повторения, его смена без scope=future или архивный проект"),
+        (status = 404, description = "Задача или проект не найдены"),
     ),
     security(("bearer_auth" = [])),
     tag = "tasks"
@@ -130,37 +222,143 @@ pub async fn update_task(
     auth: AuthUser,
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
+    Query(query): Query<EditScopeQuery>,
     Json(req): Json<UpdateTaskRequest>,
 ) -> Result<Json<Task>, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let rule = RuleChange::from_request(req.recurrence_rule.as_deref())?;
+
     tracing::info!(
         user_id = %auth.user_id,
         task_id = %id,
+        scope = ?query.scope,
         "Updating task"
     );
 
-    let task = sqlx::query_as::<_, Task>(
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let current = find_task_for_update(&mut tx, id, auth.user_id).await?;
+
+    if current.series_id.is_some()
+        && query.scope == EditScope::This
+        && (rule != RuleChange::Keep || req.recurrence_from_completion.is_some())
+    {
+        return Err((StatusCode::BAD_REQUEST, "Use scope=future to change the recurrence".to_string()));
+    }
+
+    if let Some(Some(project_id)) = req.project_id {
+        if current.project_id != Some(project_id) {
+            projects::ensure_assignable(&mut tx, auth.user_id, project_id).await?;
+        }
+    }
+
+    let mut task = sqlx::query_as::<_, Task>(
         "UPDATE tasks SET
             title = COALESCE($3, title),
             description = COALESCE($4, description),
             priority = COALESCE($5, priority),
             due_date = COALESCE($6, due_date),
             reminder_days = COALESCE($7, reminder_days),
-            reminder_hours = COALESCE($8, reminder_hours)
+            reminder_hours = COALESCE($8, reminder_hours),
+            project_id = CASE WHEN $9 THEN $10 ELSE project_id END
          WHERE id = $1 AND user_id = $2
          RETURNING *"
     )
     .bind(id)
     .bind(auth.user_id)
-    .bind(req.title)
-    .bind(req.description)
-    .bind(req.priority)
+    .bind(&req.title)
+    .bind(&req.description)
+    .bind(&req.priority)
     .bind(req.due_date)
     .bind(req.reminder_days)
     .bind(req.reminder_hours)
-    .fetch_optional(&pool)
+    .bind(req.project_id.is_some())
+    .bind(req.project_id.flatten())
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-    .ok_or((StatusCode::NOT_FOUND, "Task not found".to_string()))?;
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    match (task.series_id, rule) {
+        (Some(series_id), RuleChange::Clear) => {
+            // Удаление серии отвязывает от нее все экземпляры (ON DELETE SET NULL)
+            sqlx::query("DELETE FROM task_series WHERE id = $1")
+                .bind(series_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+            task = sqlx::query_as::<_, Task>(
+                "UPDATE tasks SET recurrence_rule = NULL, recurrence_from_completion = false, occurrence_date = NULL
+                 WHERE id = $1
+                 RETURNING *"
+            )
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+            tracing::info!(task_id = %id, series_id = %series_id, "Task recurrence stopped");
+        }
+        (Some(series_id), rule) if query.scope == EditScope::Future => {
+            let rule = match rule {
+                RuleChange::Set(rule) => Some(rule),
+                RuleChange::Keep | RuleChange::Clear => None,
+            };
+
+            // Новый срок со scope=future переносит всю серию: правило отсчитывается от него
+            sqlx::query(
+                "UPDATE task_series SET
+                    title = COALESCE($2, title),
+                    description = COALESCE($3, description),
+                    priority = COALESCE($4, priority),
+                    reminder_days = COALESCE($5, reminder_days),
+                    reminder_hours = COALESCE($6, reminder_hours),
+                    recurrence_rule = COALESCE($7, recurrence_rule),
+                    from_completion = COALESCE($8, from_completion),
+                    dtstart = COALESCE($9, dtstart),
+                    project_id = CASE WHEN $10 THEN $11 ELSE project_id END
+                 WHERE id = $1"
+            )
+            .bind(series_id)
+            .bind(&req.title)
+            .bind(&req.description)
+            .bind(&req.priority)
+            .bind(req.reminder_days)
+            .bind(req.reminder_hours)
+            .bind(rule)
+            .bind(req.recurrence_from_completion)
+            .bind(req.due_date)
+            .bind(req.project_id.is_some())
+            .bind(req.project_id.flatten())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+            task = sqlx::query_as::<_, Task>(
+                "UPDATE tasks t SET
+                    occurrence_date = COALESCE($2, t.occurrence_date),
+                    recurrence_rule = s.recurrence_rule,
+                    recurrence_from_completion = s.from_completion
+                 FROM task_series s
+                 WHERE t.id = $1 AND s.id = t.series_id
+                 RETURNING t.*"
+            )
+            .bind(id)
+            .bind(req.due_date)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        }
+        (None, RuleChange::Set(rule)) => {
+            task = start_series(&mut tx, &task, &rule, req.recurrence_from_completion.unwrap_or(false))
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        }
+        _ => {}
+    }
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     tracing::info!(
         user_id = %auth.user_id,
@@ -168,12 +366,16 @@ pub async fn update_task(
         "Task updated successfully"
     );
 
+    labels::load_labels(&pool, std::slice::from_mut(&mut task))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
     Ok(Json(task))
 }
 
 #[utoipa::path(
     delete, path = "/tasks/{id}",
-    params(("id" = Uuid, Path, description = "ID задачи")),
+    params(("id" = Uuid, Path, description = "ID задачи"), EditScopeQuery),
     responses(
         (status = 204, description = "Задача удалена"),
         (status = 404, description = "Задача не найдена"),
@@ -185,20 +387,39 @@ pub async fn delete_task(
     auth: AuthUser,
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
+    Query(query): Query<EditScopeQuery>,
 ) -> Result<StatusCode, (StatusCode, String)> {
-    let result = sqlx::query(
-        "DELETE FROM tasks WHERE id = $1 AND user_id = $2"
-    )
-    .bind(id)
-    .bind(auth.user_id)
-    .execute(&pool)
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    auth.require(Scope::TasksWrite)?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let task = find_task_for_update(&mut tx, id, auth.user_id).await?;
 
-    if result.rows_affected() == 0 {
-        return Err((StatusCode::NOT_FOUND, "Task not found".to_string()));
+    match task.series_id {
+        // Удаление одного экземпляра не обрывает серию: на его место встает следующий
+        Some(_) if query.scope == EditScope::This && task.status == "active" => {
+            next_occurrence(&mut tx, &task, Utc::now().date_naive())
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        }
+        Some(series_id) if query.scope == EditScope::Future => {
+            sqlx::query("DELETE FROM task_series WHERE id = $1")
+                .bind(series_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        }
+        _ => {}
     }
 
+    sqlx::query("DELETE FROM tasks WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -206,7 +427,7 @@ pub async fn delete_task(
     patch, path = "/tasks/{id}/complete",
     params(("id" = Uuid, Path, description = "ID задачи")),
     responses(
-        (status = 200, description = "Задача выполнена", body = Task),
+        (status = 200, description = "Задача выполнена; для повторяющейся создан следующий экземпляр", body = Task),
         (status = 404, description = "Задача не найдена"),
     ),
     security(("bearer_auth" = [])),
@@ -217,24 +438,38 @@ pub async fn complete_task(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<Task>, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
     tracing::info!(
         user_id = %auth.user_id,
         task_id = %id,
         "Marking task as complete"
     );
 
-    let task = sqlx::query_as::<_, Task>(
-        "UPDATE tasks SET status = 'completed', completed_at = $3
-         WHERE id = $1 AND user_id = $2
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let current = find_task_for_update(&mut tx, id, auth.user_id).await?;
+    let now = Utc::now();
+
+    let mut task = sqlx::query_as::<_, Task>(
+        "UPDATE tasks SET status = 'completed', completed_at = $2
+         WHERE id = $1
          RETURNING *"
     )
     .bind(id)
-    .bind(auth.user_id)
-    .bind(Utc::now())
-    .fetch_optional(&pool)
+    .bind(now)
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-    .ok_or((StatusCode::NOT_FOUND, "Task not found".to_string()))?;
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Повторное выполнение уже выполненной задачи не должно плодить экземпляры
+    if current.status == "active" {
+        next_occurrence(&mut tx, &task, now.date_naive())
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     tracing::info!(
         user_id = %auth.user_id,
@@ -242,6 +477,62 @@ pub async fn complete_task(
         "Task marked as complete"
     );
 
+    labels::load_labels(&pool, std::slice::from_mut(&mut task))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(task))
+}
+
+#[utoipa::path(
+    patch, path = "/tasks/{id}/skip",
+    params(("id" = Uuid, Path, description = "ID задачи")),
+    responses(
+        (status = 200, description = "Экземпляр пропущен, создан следующий", body = Task),
+        (status = 400, description = "Задача не повторяющаяся"),
+        (status = 404, description = "Задача не найдена"),
+        (status = 409, description = "Задача уже выполнена или пропущена"),
+    ),
+    security(("bearer_auth" = [])),
+    tag = "tasks"
+)]
+pub async fn skip_task(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Task>, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let current = find_task_for_update(&mut tx, id, auth.user_id).await?;
+
+    if current.series_id.is_none() {
+        return Err((StatusCode::BAD_REQUEST, "Only recurring tasks can be skipped".to_string()));
+    }
+    if current.status != "active" {
+        return Err((StatusCode::CONFLICT, "Task is not active".to_string()));
+    }
+
+    let mut task = sqlx::query_as::<_, Task>(
+        "UPDATE tasks SET status = 'skipped' WHERE id = $1 RETURNING *"
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    next_occurrence(&mut tx, &task, Utc::now().date_naive())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(user_id = %auth.user_id, task_id = %id, "Task occurrence skipped");
+    labels::load_labels(&pool, std::slice::from_mut(&mut task))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
     Ok(Json(task))
 }
 
@@ -251,6 +542,7 @@ pub async fn complete_task(
     responses(
         (status = 200, description = "Задача восстановлена", body = Task),
         (status = 404, description = "Задача не найдена"),
+        (status = 409, description = "Следующий экземпляр серии уже изменён"),
     ),
     security(("bearer_auth" = [])),
     tag = "tasks"
@@ -260,23 +552,34 @@ pub async fn restore_task(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<Task>, (StatusCode, String)> {
+    auth.require(Scope::TasksWrite)?;
+
     tracing::info!(
         user_id = %auth.user_id,
         task_id = %id,
         "Restoring completed task"
     );
 
-    let task = sqlx::query_as::<_, Task>(
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let current = find_task_for_update(&mut tx, id, auth.user_id).await?;
+
+    // Иначе повторное выполнение создаст второй следующий экземпляр и собьёт счётчик COUNT
+    if current.status != "active" {
+        undo_next_occurrence(&mut tx, &current).await?;
+    }
+
+    let mut task = sqlx::query_as::<_, Task>(
         "UPDATE tasks SET status = 'active', completed_at = NULL
-         WHERE id = $1 AND user_id = $2
+         WHERE id = $1
          RETURNING *"
     )
     .bind(id)
-    .bind(auth.user_id)
-    .fetch_optional(&pool)
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-    .ok_or((StatusCode::NOT_FOUND, "Task not found".to_string()))?;
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     tracing::info!(
         user_id = %auth.user_id,
@@ -284,5 +587,253 @@ pub async fn restore_task(
         "Task restored successfully"
     );
 
+    labels::load_labels(&pool, std::slice::from_mut(&mut task))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
     Ok(Json(task))
-}
\ No newline at end of file
+}
+
+/// Фильтр задач по проекту
+enum ProjectFilter {
+    Any,
+    Inbox,
+    Project(Uuid),
+}
+
+impl ProjectFilter {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Any => "any",
+            Self::Inbox => "inbox",
+            Self::Project(_) => "project",
+        }
+    }
+
+    fn id(&self) -> Option<Uuid> {
+        match self {
+            Self::Project(id) => Some(*id),
+            Self::Any | Self::Inbox => None,
+        }
+    }
+}
+
+/// Изменение правила повторения из запроса
+#[derive(Debug, PartialEq, Eq)]
+enum RuleChange {
+    Keep,
+    Clear,
+    Set(String),
+}
+
+impl RuleChange {
+    /// Пустая строка убирает повторение, иначе правило должно разбираться
+    fn from_request(rule: Option<&str>) -> Result<Self, (StatusCode, String)> {
+        let Some(rule) = rule.map(str::trim) else {
+            return Ok(Self::Keep);
+        };
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+        if rule.is_empty() {
+            return Ok(Self::Clear);
+        }
+
+        RRule::parse(rule).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        Ok(Self::Set(rule.to_string()))
+    }
+}
+
+async fn find_task_for_update(
+    conn: &mut PgConnection,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<Task, (StatusCode, String)> {
+    sqlx::query_as::<_, Task>(
+        "SELECT * FROM tasks WHERE id = $1 AND user_id = $2 FOR UPDATE"
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, "Task not found".to_string()))
+}
+
+/// Делает задачу первым экземпляром новой серии
+async fn start_series(
+    conn: &mut PgConnection,
+    task: &Task,
+    rule: &str,
+    from_completion: bool,
+) -> Result<Task, sqlx::Error> {
+    let series_id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO task_series (id, user_id, title, description, priority, reminder_days, reminder_hours,
+                                  recurrence_rule, from_completion, dtstart, occurrences, created_at, project_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, 1, $11, $12)
+         RETURNING id"
+    )
+    .bind(Uuid::new_v4())
+    .bind(task.user_id)
+    .bind(&task.title)
+    .bind(&task.description)
+    .bind(&task.priority)
+    .bind(task.reminder_days)
+    .bind(task.reminder_hours)
+    .bind(rule)
+    .bind(from_completion)
+    .bind(task.due_date)
+    .bind(Utc::now())
+    .bind(task.project_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query_as::<_, Task>(
+        "UPDATE tasks SET series_id = $2, occurrence_date = due_date, recurrence_rule = $3, recurrence_from_completion = $4
+         WHERE id = $1
+         RETURNING *"
+    )
+    .bind(task.id)
+    .bind(series_id)
+    .bind(rule)
+    .bind(from_completion)
+    .fetch_one(&mut *conn)
+    .await
+}
+
+/// Создает следующий экземпляр серии после выполнения или пропуска `task`.
+/// Календарное правило идет от даты экземпляра по правилу (перенос срока одного
+/// экземпляра серию не сдвигает), правило "после выполнения" — от `today`.
+async fn next_occurrence(
+    conn: &mut PgConnection,
+    task: &Task,
+    today: NaiveDate,
+) -> Result<Option<Task>, sqlx::Error> {
+    let Some(series_id) = task.series_id else {
+        return Ok(None);
+    };
+
+    let Some(series) = sqlx::query_as::<_, TaskSeries>(
+        "SELECT * FROM task_series WHERE id = $1 FOR UPDATE"
+    )
+    .bind(series_id)
+    .fetch_optional(&mut *conn)
+    .await? else {
+        return Ok(None);
+    };
+
+    let rule = match RRule::parse(&series.recurrence_rule) {
+        Ok(rule) => rule,
+        Err(e) => {
+            tracing::error!(series_id = %series.id, error = %e, "Stored recurrence rule is invalid");
+            return Ok(None);
+        }
+    };
+
+    let exhausted = rule.is_exhausted(series.occurrences);
+    let next_date = if exhausted {
+        None
+    } else if series.from_completion {
+        rule.next_after(today, today)
+    } else {
+        rule.next_after(series.dtstart, task.occurrence_date.unwrap_or(task.due_date))
+    };
+
+    let Some(due_date) = next_date else {
+        tracing::info!(series_id = %series.id, occurrences = series.occurrences, "Task series finished");
+        return Ok(None);
+    };
+
+    let next = sqlx::query_as::<_, Task>(
+        "INSERT INTO tasks (id, user_id, title, description, priority, due_date, reminder_days, reminder_hours,
+                            status, created_at, series_id, occurrence_date, recurrence_rule, recurrence_from_completion,
+                            project_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'active', $9, $10, $6, $11, $12, $13)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(series.user_id)
+    .bind(&series.title)
+    .bind(&series.description)
+    .bind(&series.priority)
+    .bind(due_date)
+    .bind(series.reminder_days)
+    .bind(series.reminder_hours)
+    .bind(Utc::now())
+    .bind(series.id)
+    .bind(&series.recurrence_rule)
+    .bind(series.from_completion)
+    .bind(series.project_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    // Метки переходят к следующему экземпляру вместе с задачей
+    sqlx::query(
+        "INSERT INTO task_labels (task_id, label_id)
+         SELECT $2, label_id FROM task_labels WHERE task_id = $1"
+    )
+    .bind(task.id)
+    .bind(next.id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("UPDATE task_series SET occurrences = occurrences + 1 WHERE id = $1")
+        .bind(series.id)
+        .execute(&mut *conn)
+        .await?;
+
+    tracing::info!(series_id = %series.id, task_id = %next.id, due_date = %due_date, "Next task occurrence created");
+    Ok(Some(next))
+}
+
+/// Откатывает экземпляр, созданный при выполнении или пропуске задачи
+async fn undo_next_occurrence(
+    conn: &mut PgConnection,
+    task: &Task,
+) -> Result<(), (StatusCode, String)> {
+    let Some(series_id) = task.series_id else {
+        return Ok(());
+    };
+
+    sqlx::query("SELECT id FROM task_series WHERE id = $1 FOR UPDATE")
+        .bind(series_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let later = sqlx::query_as::<_, Task>(
+        "SELECT * FROM tasks
+         WHERE series_id = $1 AND id <> $2 AND created_at > $3
+         ORDER BY created_at
+         FOR UPDATE"
+    )
+    .bind(series_id)
+    .bind(task.id)
+    .bind(task.created_at)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    match later.as_slice() {
+        // Серия завершилась, откатывать нечего
+        [] => Ok(()),
+        [next] if next.status == "active" => {
+            sqlx::query("DELETE FROM tasks WHERE id = $1")
+                .bind(next.id)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+            sqlx::query("UPDATE task_series SET occurrences = occurrences - 1 WHERE id = $1")
+                .bind(series_id)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+            tracing::info!(series_id = %series_id, task_id = %next.id, "Next task occurrence removed on restore");
+            Ok(())
+        }
+        _ => Err((
+            StatusCode::CONFLICT,
+            "A later occurrence of this series is already completed or skipped".to_string(),
+        )),
+    }
+}
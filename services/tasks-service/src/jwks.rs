@@ -0,0 +1,127 @@
+use jsonwebtoken::{jwk::JwkSet, DecodingKey};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+/// Сколько живет загруженный набор ключей
+const JWKS_TTL: Duration = Duration::from_secs(600);
+/// Не чаще одного запроса за это время, даже если приходят токены с неизвестным `kid`
+const MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(30);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub enum JwksError {
+    /// Ключа с таким `kid` нет даже в свежем JWKS
+    UnknownKid,
+    /// auth-service недоступен, а ключа в кэше нет
+    Unavailable(String),
+}
+
+/// Кэш публичных ключей auth-service (`AUTH_JWKS_URL`).
+/// Набор перечитывается по истечении TTL или при встрече неизвестного `kid` — так
+/// новый ключ после ротации подхватывается без рестарта.
+#[derive(Clone)]
+pub struct JwksCache {
+    url: Arc<str>,
+    client: reqwest::Client,
+    state: Arc<RwLock<KeySet>>,
+    /// Не дает грузить набор параллельно; на попадание в свежий кэш не берется
+    refresh: Arc<Mutex<()>>,
+}
+
+#[derive(Default)]
+struct KeySet {
+    keys: HashMap<String, DecodingKey>,
+    /// Время последней попытки загрузки
+    fetched_at: Option<Instant>,
+}
+
+impl JwksCache {
+    pub fn from_env() -> Self {
+        let url = std::env::var("AUTH_JWKS_URL")
+            .unwrap_or_else(|_| "http://localhost:3001/.well-known/jwks.json".to_string());
+
+        Self {
+            url: url.into(),
+            client: reqwest::Client::builder()
+                .timeout(FETCH_TIMEOUT)
+                .build()
+                .expect("Failed to build HTTP client"),
+            state: Arc::default(),
+            refresh: Arc::default(),
+        }
+    }
+
+    pub async fn key(&self, kid: &str) -> Result<DecodingKey, JwksError> {
+        if let Some(key) = self.fresh_key(kid) {
+            return Ok(key);
+        }
+
+        let _refresh = self.refresh.lock().await;
+
+        // Пока ждали мьютекс, набор мог обновить другой запрос
+        let (cached, elapsed) = {
+            let state = self.state.read().unwrap();
+            (state.keys.get(kid).cloned(), state.fetched_at.map(|at| at.elapsed()))
+        };
+
+        let stale = elapsed.is_none_or(|elapsed| elapsed > JWKS_TTL);
+        let may_refetch = elapsed.is_none_or(|elapsed| elapsed > MIN_REFETCH_INTERVAL);
+
+        if let Some(key) = &cached {
+            if !stale {
+                return Ok(key.clone());
+            }
+        }
+
+        if stale || (cached.is_none() && may_refetch) {
+            self.state.write().unwrap().fetched_at = Some(Instant::now());
+
+            if let Err(e) = self.fetch().await {
+                tracing::error!(error = %e, url = %self.url, "Failed to fetch JWKS");
+                // Пока auth-service недоступен, продолжаем доверять уже известным ключам
+                return cached.ok_or(JwksError::Unavailable(e));
+            }
+        }
+
+        self.state.read().unwrap().keys.get(kid).cloned().ok_or(JwksError::UnknownKid)
+    }
+
+    fn fresh_key(&self, kid: &str) -> Option<DecodingKey> {
+        let state = self.state.read().unwrap();
+        let fresh = state.fetched_at.is_some_and(|at| at.elapsed() <= JWKS_TTL);
+        if fresh { state.keys.get(kid).cloned() } else { None }
+    }
+
+    async fn fetch(&self) -> Result<(), String> {
+        let set = self.client
+            .get(&*self.url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json::<JwkSet>()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let keys = set.keys.iter()
+            .filter_map(|jwk| {
+                let kid = jwk.common.key_id.clone()?;
+                match DecodingKey::from_jwk(jwk) {
+                    Ok(key) => Some((kid, key)),
+                    Err(e) => {
+                        tracing::warn!(error = %e, kid = %kid, "Skipping unsupported JWK");
+                        None
+                    }
+                }
+            })
+            .collect::<HashMap<_, _>>();
+
+        tracing::info!(keys = keys.len(), "JWKS refreshed");
+        self.state.write().unwrap().keys = keys;
+        Ok(())
+    }
+}
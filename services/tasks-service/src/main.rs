@@ -1,15 +1,57 @@
-use axum::{routing::{delete, get, patch, post, put}, Router, middleware};
-use axum::extract::Request;
+use axum::{routing::{delete, get, patch, post, put}, Router};
+use axum::extract::{FromRef, Request};
 use axum::response::Response;
 use dotenvy::dotenv;
-use sqlx::postgres::PgPoolOptions;
+use sqlx::{postgres::PgPoolOptions, PgPool};
 use std::env;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 mod auth;
+mod erasure;
 mod models;
 mod handlers;
+mod jwks;
+mod notifications;
+mod recurrence;
+mod reminders;
+mod revocation;
+
+use auth::UnverifiedEmailPolicy;
+use jwks::JwksCache;
+use revocation::Denylist;
+
+#[derive(Clone)]
+struct AppState {
+    pool: PgPool,
+    jwks: JwksCache,
+    denylist: Denylist,
+    unverified_email_policy: UnverifiedEmailPolicy,
+}
+
+impl FromRef<AppState> for PgPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for JwksCache {
+    fn from_ref(state: &AppState) -> Self {
+        state.jwks.clone()
+    }
+}
+
+impl FromRef<AppState> for Denylist {
+    fn from_ref(state: &AppState) -> Self {
+        state.denylist.clone()
+    }
+}
+
+impl FromRef<AppState> for UnverifiedEmailPolicy {
+    fn from_ref(state: &AppState) -> Self {
+        state.unverified_email_policy
+    }
+}
 
 #[derive(OpenApi)]
 #[openapi(
@@ -20,20 +62,60 @@ mod handlers;
         handlers::tasks::update_task,
         handlers::tasks::delete_task,
         handlers::tasks::complete_task,
+        handlers::tasks::skip_task,
         handlers::tasks::restore_task,
+        handlers::projects::list_projects,
+        handlers::projects::create_project,
+        handlers::projects::get_project,
+        handlers::projects::update_project,
+        handlers::projects::reorder_projects,
+        handlers::projects::delete_project,
+        handlers::labels::list_labels,
+        handlers::labels::create_label,
+        handlers::labels::update_label,
+        handlers::labels::merge_label,
+        handlers::labels::delete_label,
+        handlers::labels::attach_labels,
+        handlers::labels::detach_label,
         handlers::settings::get_settings,
         handlers::settings::update_settings,
+        handlers::admin::task_stats,
+        handlers::notifications::list_notifications,
+        handlers::notifications::mark_read,
+        handlers::notifications::mark_all_read,
+        handlers::notifications::delete_notification,
     ),
     components(schemas(
         models::task::Task,
         models::task::CreateTaskRequest,
         models::task::UpdateTaskRequest,
+        models::task::EditScope,
+        models::project::Project,
+        models::project::CreateProjectRequest,
+        models::project::UpdateProjectRequest,
+        models::project::ReorderProjectsRequest,
+        models::project::ProjectTasksAction,
+        models::label::Label,
+        models::label::TaskLabel,
+        models::label::CreateLabelRequest,
+        models::label::UpdateLabelRequest,
+        models::label::MergeLabelRequest,
+        models::label::AttachLabelsRequest,
+        models::label::LabelMatch,
         models::settings::UserSettings,
         models::settings::UpdateSettingsRequest,
+        models::task::TaskStats,
+        models::notification::Notification,
+        models::notification::NotificationListResponse,
+        models::notification::MarkAllReadResponse,
     )),
     tags(
         (name = "tasks", description = "Управление задачами"),
+        (name = "projects", description = "Проекты и порядок списков"),
+        (name = "labels", description = "Метки задач"),
         (name = "settings", description = "Настройки пользователя"),
+        (name = "notifications", description = "Входящие уведомления"),
+        (name = "admin", description = "Статистика для поддержки"),
     ),
     info(title = "Tasks Service API", version = "1.0.0"),
     modifiers(&SecurityAddon)
@@ -133,6 +215,115 @@ async fn main() {
         .await
         .expect("Failed to create index on priority");
 
+    // Шаблон повторяющейся задачи: из него создается каждый следующий экземпляр
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS task_series (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL,
+            title VARCHAR(500) NOT NULL,
+            description TEXT NOT NULL,
+            priority VARCHAR(20) NOT NULL,
+            reminder_days INTEGER,
+            reminder_hours INTEGER,
+            recurrence_rule TEXT NOT NULL,
+            from_completion BOOLEAN NOT NULL DEFAULT false,
+            dtstart DATE NOT NULL,
+            occurrences INTEGER NOT NULL DEFAULT 1,
+            created_at TIMESTAMPTZ NOT NULL
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create task_series table");
+
+    sqlx::query(
+        "ALTER TABLE tasks
+            ADD COLUMN IF NOT EXISTS series_id UUID REFERENCES task_series(id) ON DELETE SET NULL,
+            ADD COLUMN IF NOT EXISTS occurrence_date DATE,
+            ADD COLUMN IF NOT EXISTS recurrence_rule TEXT,
+            ADD COLUMN IF NOT EXISTS recurrence_from_completion BOOLEAN NOT NULL DEFAULT false"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to add recurrence columns to tasks");
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_tasks_series_id ON tasks(series_id)")
+        .execute(&pool)
+        .await
+        .expect("Failed to create index on series_id");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS projects (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL,
+            name VARCHAR(100) NOT NULL,
+            color VARCHAR(7) NOT NULL,
+            position INTEGER NOT NULL DEFAULT 0,
+            archived_at TIMESTAMPTZ,
+            created_at TIMESTAMPTZ NOT NULL,
+            updated_at TIMESTAMPTZ NOT NULL
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create projects table");
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_projects_user_id ON projects(user_id, position)")
+        .execute(&pool)
+        .await
+        .expect("Failed to create index on projects");
+
+    // Задачи без проекта — во "Входящих"
+    sqlx::query("ALTER TABLE tasks ADD COLUMN IF NOT EXISTS project_id UUID REFERENCES projects(id) ON DELETE SET NULL")
+        .execute(&pool)
+        .await
+        .expect("Failed to add project_id to tasks");
+
+    sqlx::query("ALTER TABLE task_series ADD COLUMN IF NOT EXISTS project_id UUID REFERENCES projects(id) ON DELETE SET NULL")
+        .execute(&pool)
+        .await
+        .expect("Failed to add project_id to task_series");
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_tasks_project_id ON tasks(project_id)")
+        .execute(&pool)
+        .await
+        .expect("Failed to create index on project_id");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS labels (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL,
+            name VARCHAR(50) NOT NULL,
+            color VARCHAR(7) NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            updated_at TIMESTAMPTZ NOT NULL
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create labels table");
+
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_labels_user_name ON labels(user_id, lower(name))")
+        .execute(&pool)
+        .await
+        .expect("Failed to create unique index on label names");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS task_labels (
+            task_id UUID NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+            label_id UUID NOT NULL REFERENCES labels(id) ON DELETE CASCADE,
+            PRIMARY KEY (task_id, label_id)
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create task_labels table");
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_task_labels_label_id ON task_labels(label_id)")
+        .execute(&pool)
+        .await
+        .expect("Failed to create index on task_labels");
+
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS user_settings (
             id UUID PRIMARY KEY,
@@ -146,14 +337,107 @@ async fn main() {
     .await
     .expect("Failed to create user_settings table");
 
+    sqlx::query(
+        "ALTER TABLE user_settings
+            ADD COLUMN IF NOT EXISTS muted_categories TEXT[] NOT NULL DEFAULT '{}'"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to add muted_categories column to user_settings table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS notifications (
+            id UUID PRIMARY KEY,
+            user_id UUID NOT NULL,
+            category VARCHAR(32) NOT NULL,
+            title VARCHAR(500) NOT NULL,
+            body TEXT NOT NULL,
+            task_id UUID REFERENCES tasks(id) ON DELETE CASCADE,
+            created_at TIMESTAMPTZ NOT NULL,
+            read_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create notifications table");
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_notifications_user_created ON notifications(user_id, created_at DESC, id DESC)")
+        .execute(&pool)
+        .await
+        .expect("Failed to create index on notifications");
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_notifications_unread ON notifications(user_id) WHERE read_at IS NULL")
+        .execute(&pool)
+        .await
+        .expect("Failed to create index on unread notifications");
+
+    // История напоминаний: по строке на задачу, момент напоминания и канал
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS task_reminders (
+            id UUID PRIMARY KEY,
+            task_id UUID NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+            channel VARCHAR(32) NOT NULL,
+            remind_at TIMESTAMPTZ NOT NULL,
+            status VARCHAR(20) NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at TIMESTAMPTZ NOT NULL,
+            claimed_at TIMESTAMPTZ,
+            sent_at TIMESTAMPTZ,
+            last_error TEXT,
+            created_at TIMESTAMPTZ NOT NULL,
+            UNIQUE (task_id, remind_at, channel)
+        )"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create task_reminders table");
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_task_reminders_pending ON task_reminders(next_attempt_at)
+         WHERE status = 'pending'"
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create index on pending task reminders");
+
+    let jwks = JwksCache::from_env();
+
+    let denylist = Denylist::default();
+    denylist.spawn_sync(pool.clone());
+
+    erasure::spawn_consumer(pool.clone());
+    reminders::spawn_scheduler(pool.clone(), reminders::channels_from_env(&pool));
+    notifications::spawn_purge(pool.clone());
+
+    let unverified_email_policy = UnverifiedEmailPolicy::from_env();
+    tracing::info!(policy = ?unverified_email_policy, "Unverified email policy");
+
+    let state = AppState { pool, jwks, denylist, unverified_email_policy };
+
     let app = Router::new()
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .route("/tasks", get(handlers::tasks::list_tasks).post(handlers::tasks::create_task))
         .route("/tasks/:id", get(handlers::tasks::get_task).put(handlers::tasks::update_task).delete(handlers::tasks::delete_task))
         .route("/tasks/:id/complete", patch(handlers::tasks::complete_task))
+        .route("/tasks/:id/skip", patch(handlers::tasks::skip_task))
         .route("/tasks/:id/restore", patch(handlers::tasks::restore_task))
+        .route("/projects", get(handlers::projects::list_projects).post(handlers::projects::create_project))
+        .route("/projects/order", put(handlers::projects::reorder_projects))
+        .route("/projects/:id", get(handlers::projects::get_project).put(handlers::projects::update_project).delete(handlers::projects::delete_project))
+        .route("/labels", get(handlers::labels::list_labels).post(handlers::labels::create_label))
+        .route("/labels/:id", put(handlers::labels::update_label).delete(handlers::labels::delete_label))
+        .route("/labels/:id/merge", post(handlers::labels::merge_label))
+        .route("/tasks/:id/labels", post(handlers::labels::attach_labels))
+        .route("/tasks/:id/labels/:label_id", delete(handlers::labels::detach_label))
         .route("/settings", get(handlers::settings::get_settings).put(handlers::settings::update_settings))
-        .with_state(pool)
+        .route("/notifications", get(handlers::notifications::list_notifications))
+        .route("/notifications/read-all", post(handlers::notifications::mark_all_read))
+        .route("/notifications/:id", delete(handlers::notifications::delete_notification))
+        .route("/notifications/:id/read", patch(handlers::notifications::mark_read))
+        .route("/admin/tasks/stats", get(handlers::admin::task_stats))
+        // Внутренний API для других сервисов, через api-gateway не проксируется
+        .route("/internal/users/:id/export", get(handlers::internal::export_user_data))
+        .with_state(state)
         .layer(axum::middleware::from_fn(log_middleware));
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3002").await.unwrap();
@@ -0,0 +1,13 @@
+use serde::Serialize;
+
+use crate::models::{label::Label, notification::Notification, project::Project, settings::UserSettings, task::Task};
+
+/// Данные пользователя для GDPR-выгрузки
+#[derive(Debug, Serialize)]
+pub struct UserDataExport {
+    pub tasks: Vec<Task>,
+    pub projects: Vec<Project>,
+    pub labels: Vec<Label>,
+    pub settings: Option<UserSettings>,
+    pub notifications: Vec<Notification>,
+}
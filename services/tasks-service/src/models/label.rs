@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Цвет метки по умолчанию
+pub const DEFAULT_COLOR: &str = "#6B7280";
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Label {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Уникально у пользователя без учета регистра
+    pub name: String,
+    /// `#RRGGBB`
+    pub color: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Метка в составе задачи
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TaskLabel {
+    #[serde(skip)]
+    pub task_id: Uuid,
+    pub id: Uuid,
+    pub name: String,
+    pub color: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateLabelRequest {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateLabelRequest {
+    pub name: Option<String>,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MergeLabelRequest {
+    /// Метка, в которую переходят задачи; исходная удаляется
+    pub into: Uuid,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AttachLabelsRequest {
+    pub label_ids: Vec<Uuid>,
+}
+
+/// Как сочетать несколько меток в фильтре задач
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelMatch {
+    /// Хотя бы одна из меток
+    #[default]
+    Any,
+    /// Все метки сразу
+    All,
+}
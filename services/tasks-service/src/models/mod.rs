@@ -1,2 +1,6 @@
 pub mod task;
-pub mod settings;
\ No newline at end of file
+pub mod settings;
+pub mod export;
+pub mod notification;
+pub mod project;
+pub mod label;
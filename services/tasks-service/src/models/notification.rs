@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Notification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// reminder, overdue или share
+    pub category: String,
+    pub title: String,
+    pub body: String,
+    /// Задача, к которой относится уведомление
+    pub task_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct NotificationQuery {
+    /// `next_cursor` из предыдущей страницы
+    pub cursor: Option<String>,
+    /// По умолчанию 20, максимум 100
+    pub limit: Option<i64>,
+    /// Только непрочитанные
+    pub unread_only: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NotificationListResponse {
+    pub items: Vec<Notification>,
+    /// Всего непрочитанных, независимо от страницы
+    pub unread_count: i64,
+    /// Нет — это последняя страница
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MarkAllReadResponse {
+    pub updated: u64,
+}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Цвет проекта по умолчанию
+pub const DEFAULT_COLOR: &str = "#7C3AED";
+
+/// Проект (список) задач. Задачи без проекта лежат во "Входящих".
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Project {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// `#RRGGBB`
+    pub color: String,
+    /// Порядок в списке проектов, по возрастанию
+    pub position: i32,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateProjectRequest {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateProjectRequest {
+    pub name: Option<String>,
+    pub color: Option<String>,
+    /// `true` — в архив, `false` — вернуть из архива
+    pub archived: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReorderProjectsRequest {
+    /// Все проекты пользователя в новом порядке
+    pub project_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ProjectListQuery {
+    /// Показать и архивные проекты
+    pub include_archived: Option<bool>,
+}
+
+/// Что сделать с задачами удаляемого проекта
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectTasksAction {
+    /// Перенести во "Входящие"
+    #[default]
+    Move,
+    /// Удалить вместе с проектом
+    Delete,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DeleteProjectQuery {
+    /// `move` (по умолчанию) или `delete`
+    #[serde(default)]
+    pub tasks: ProjectTasksAction,
+}
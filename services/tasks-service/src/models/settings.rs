@@ -10,6 +10,8 @@ pub struct UserSettings {
     pub user_id: Uuid,
     pub theme: String,
     pub notifications_enabled: bool,
+    /// Категории уведомлений, которые не нужно создавать
+    pub muted_categories: Vec<String>,
     pub updated_at: DateTime<Utc>,
 }
 
@@ -17,4 +19,6 @@ pub struct UserSettings {
 pub struct UpdateSettingsRequest {
     pub theme: Option<String>,
     pub notifications_enabled: Option<bool>,
+    /// Заменяет список целиком: reminder, overdue, share
+    pub muted_categories: Option<Vec<String>>,
 }
\ No newline at end of file
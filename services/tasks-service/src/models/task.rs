@@ -1,9 +1,11 @@
 use chrono::{DateTime, NaiveDate, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::FromRow;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use crate::models::label::{LabelMatch, TaskLabel};
+
 #[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Task {
     pub id: Uuid,
@@ -12,11 +14,47 @@ pub struct Task {
     pub description: String,
     pub priority: String,
     pub due_date: NaiveDate,
+    /// За сколько дней до срока напомнить. Часовой пояс пользователя не хранится,
+    /// поэтому отсчет идет от 00:00 UTC дня `due_date`
     pub reminder_days: Option<i32>,
+    /// За сколько часов до 00:00 UTC дня `due_date` напомнить, дополнительно к `reminder_days`
     pub reminder_hours: Option<i32>,
     pub status: String,
     pub created_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
+    /// Серия, к которой относится повторяющаяся задача
+    pub series_id: Option<Uuid>,
+    /// Дата по правилу повторения; отличается от `due_date`, если срок этого экземпляра перенесли
+    pub occurrence_date: Option<NaiveDate>,
+    /// RRULE из RFC 5545, например `FREQ=WEEKLY;BYDAY=MO`
+    pub recurrence_rule: Option<String>,
+    /// Следующий срок считается от даты выполнения, а не по календарю
+    pub recurrence_from_completion: bool,
+    /// Проект; нет — задача во "Входящих"
+    pub project_id: Option<Uuid>,
+    /// Загружаются одним запросом сразу для всех задач ответа
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub labels: Vec<TaskLabel>,
+}
+
+/// Шаблон повторяющейся задачи: из него создается каждый следующий экземпляр
+#[derive(Debug, FromRow)]
+pub struct TaskSeries {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub priority: String,
+    pub reminder_days: Option<i32>,
+    pub reminder_hours: Option<i32>,
+    pub recurrence_rule: String,
+    pub from_completion: bool,
+    /// Первая дата серии, от нее отсчитывается календарное правило
+    pub dtstart: NaiveDate,
+    /// Сколько экземпляров уже создано, для COUNT
+    pub occurrences: i32,
+    pub project_id: Option<Uuid>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -25,8 +63,17 @@ pub struct CreateTaskRequest {
     pub description: String,
     pub priority: String,
     pub due_date: NaiveDate,
+    /// Дни до срока; срок — 00:00 UTC дня `due_date`
     pub reminder_days: Option<i32>,
+    /// Часы до срока; срок — 00:00 UTC дня `due_date`
     pub reminder_hours: Option<i32>,
+    /// RRULE, например `FREQ=MONTHLY;BYMONTHDAY=-1`; для "каждые N дней после выполнения" —
+    /// `FREQ=DAILY;INTERVAL=N` вместе с `recurrence_from_completion`
+    pub recurrence_rule: Option<String>,
+    #[serde(default)]
+    pub recurrence_from_completion: bool,
+    /// Нет — во "Входящие"
+    pub project_id: Option<Uuid>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -35,12 +82,70 @@ pub struct UpdateTaskRequest {
     pub description: Option<String>,
     pub priority: Option<String>,
     pub due_date: Option<NaiveDate>,
+    /// Дни до срока; срок — 00:00 UTC дня `due_date`
     pub reminder_days: Option<i32>,
+    /// Часы до срока; срок — 00:00 UTC дня `due_date`
     pub reminder_hours: Option<i32>,
+    /// Пустая строка прекращает повторение. Менять правило можно только со `scope=future`
+    pub recurrence_rule: Option<String>,
+    pub recurrence_from_completion: Option<bool>,
+    /// `null` переносит задачу во "Входящие", отсутствие поля — оставляет как есть
+    #[serde(default, deserialize_with = "double_option")]
+    #[schema(value_type = Option<Uuid>, nullable)]
+    pub project_id: Option<Option<Uuid>>,
+}
+
+/// Что меняет правка повторяющейся задачи
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EditScope {
+    /// Только этот экземпляр
+    #[default]
+    This,
+    /// Этот и все следующие экземпляры серии
+    Future,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct EditScopeQuery {
+    /// `this` (по умолчанию) или `future`
+    #[serde(default)]
+    pub scope: EditScope,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct TaskFilters {
     pub status: Option<String>,
     pub priority: Option<String>,
-}
\ No newline at end of file
+    /// ID проекта или `inbox`
+    pub project_id: Option<String>,
+    /// ID меток через запятую
+    pub labels: Option<String>,
+    #[serde(default)]
+    pub label_match: LabelMatch,
+}
+
+/// Отличает отсутствующее поле (`None`) от явного `null` (`Some(None)`)
+fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Счетчики задач пользователя для поддержки
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct TaskStats {
+    pub user_id: Uuid,
+    pub total: i64,
+    pub active: i64,
+    pub completed: i64,
+    /// Активные задачи с прошедшим сроком
+    pub overdue: i64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TaskStatsQuery {
+    pub user_id: Uuid,
+}
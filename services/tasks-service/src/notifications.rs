@@ -0,0 +1,101 @@
+use chrono::{Duration, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+/// Категории уведомлений; каждую можно заглушить в настройках (`muted_categories`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Reminder,
+    Overdue,
+    Share,
+}
+
+impl Category {
+    pub const ALL: [Category; 3] = [Category::Reminder, Category::Overdue, Category::Share];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Reminder => "reminder",
+            Self::Overdue => "overdue",
+            Self::Share => "share",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|category| category.as_str() == value)
+    }
+}
+
+pub struct NewNotification {
+    pub user_id: Uuid,
+    pub category: Category,
+    pub title: String,
+    pub body: String,
+    pub task_id: Option<Uuid>,
+}
+
+/// Единая точка, через которую подсистемы tasks-service создают уведомления.
+/// Учитывает настройки пользователя: при выключенных уведомлениях или заглушенной
+/// категории ничего не создается и возвращается `None`. Один запрос, поэтому
+/// можно вызывать и внутри транзакции вызывающего кода.
+pub async fn notify<'e, E: PgExecutor<'e>>(
+    executor: E,
+    notification: NewNotification,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO notifications (id, user_id, category, title, body, task_id, created_at)
+         SELECT $1, $2, $3, $4, $5, $6, $7
+         WHERE NOT EXISTS (
+             SELECT 1 FROM user_settings
+             WHERE user_id = $2 AND (NOT notifications_enabled OR $3 = ANY(muted_categories))
+         )
+         RETURNING id"
+    )
+    .bind(Uuid::new_v4())
+    .bind(notification.user_id)
+    .bind(notification.category.as_str())
+    .bind(&notification.title)
+    .bind(&notification.body)
+    .bind(notification.task_id)
+    .bind(Utc::now())
+    .fetch_optional(executor)
+    .await?;
+
+    if id.is_none() {
+        tracing::debug!(
+            user_id = %notification.user_id,
+            category = notification.category.as_str(),
+            "Notification suppressed by user settings"
+        );
+    }
+
+    Ok(id)
+}
+
+/// Сколько дней хранятся уведомления (NOTIFICATIONS_RETENTION_DAYS, по умолчанию 90)
+fn retention_days() -> i64 {
+    std::env::var("NOTIFICATIONS_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(90)
+}
+
+/// Раз в сутки удаляет старые уведомления
+pub fn spawn_purge(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(24 * 3600));
+        loop {
+            interval.tick().await;
+
+            let result = sqlx::query("DELETE FROM notifications WHERE created_at < $1")
+                .bind(Utc::now() - Duration::days(retention_days()))
+                .execute(&pool)
+                .await;
+
+            match result {
+                Ok(result) => tracing::debug!(purged = result.rows_affected(), "Old notifications purged"),
+                Err(e) => tracing::error!(error = %e, "Failed to purge notifications"),
+            }
+        }
+    });
+}
@@ -0,0 +1,394 @@
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+
+/// Сколько периодов правила просматривается в поисках следующей даты. Защищает
+/// от правил без единого совпадения, например `FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30`.
+const MAX_PERIODS: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// День недели в BYDAY: `MO` — каждый понедельник, `1MO` — первый, `-1FR` — последняя пятница
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ByDay {
+    ordinal: Option<i32>,
+    weekday: Weekday,
+}
+
+/// Подмножество RRULE из RFC 5545 для задач с датой без времени:
+/// FREQ, INTERVAL, BYDAY, BYMONTHDAY, BYMONTH, COUNT, UNTIL и WKST=MO.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    by_day: Vec<ByDay>,
+    by_month_day: Vec<i32>,
+    by_month: Vec<u32>,
+    /// Сколько всего экземпляров, включая первый
+    pub count: Option<u32>,
+    pub until: Option<NaiveDate>,
+}
+
+impl RRule {
+    /// Разбирает правило вида `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR`, префикс `RRULE:` допустим
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let rule = rule.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut by_day = Vec::new();
+        let mut by_month_day = Vec::new();
+        let mut by_month = Vec::new();
+        let mut count = None;
+        let mut until = None;
+
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part.split_once('=')
+                .ok_or_else(|| format!("Invalid RRULE part: {}", part))?;
+
+            match name.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        other => return Err(format!("Unsupported FREQ: {}", other)),
+                    });
+                }
+                "INTERVAL" => {
+                    interval = value.parse().ok().filter(|n| *n >= 1)
+                        .ok_or_else(|| format!("Invalid INTERVAL: {}", value))?;
+                }
+                "BYDAY" => {
+                    by_day = value.split(',').map(parse_by_day).collect::<Result<_, _>>()?;
+                }
+                "BYMONTHDAY" => {
+                    by_month_day = value.split(',')
+                        .map(|day| day.parse::<i32>().ok()
+                            .filter(|d| *d != 0 && (-31..=31).contains(d))
+                            .ok_or_else(|| format!("Invalid BYMONTHDAY: {}", day)))
+                        .collect::<Result<_, _>>()?;
+                }
+                "BYMONTH" => {
+                    by_month = value.split(',')
+                        .map(|month| month.parse::<u32>().ok()
+                            .filter(|m| (1..=12).contains(m))
+                            .ok_or_else(|| format!("Invalid BYMONTH: {}", month)))
+                        .collect::<Result<_, _>>()?;
+                }
+                "COUNT" => {
+                    count = Some(value.parse().ok().filter(|n| *n >= 1)
+                        .ok_or_else(|| format!("Invalid COUNT: {}", value))?);
+                }
+                "UNTIL" => {
+                    // Время, если оно есть, отбрасываем: срок задачи — это дата
+                    let date = value.get(..8).unwrap_or(value);
+                    until = Some(NaiveDate::parse_from_str(date, "%Y%m%d")
+                        .map_err(|_| format!("Invalid UNTIL: {}", value))?);
+                }
+                "WKST" if value.eq_ignore_ascii_case("MO") => {}
+                other => return Err(format!("Unsupported RRULE part: {}", other)),
+            }
+        }
+
+        let frequency = frequency.ok_or("RRULE must contain FREQ")?;
+
+        if count.is_some() && until.is_some() {
+            return Err("COUNT and UNTIL cannot be used together".to_string());
+        }
+        if by_day.iter().any(|day| day.ordinal.is_some())
+            && !matches!(frequency, Frequency::Monthly | Frequency::Yearly)
+        {
+            return Err("Numbered BYDAY is only allowed with FREQ=MONTHLY or FREQ=YEARLY".to_string());
+        }
+        if !by_month_day.is_empty() && frequency == Frequency::Weekly {
+            return Err("BYMONTHDAY is not allowed with FREQ=WEEKLY".to_string());
+        }
+        // Дни недели и числа в пределах всего года не поддерживаются — только внутри BYMONTH
+        if frequency == Frequency::Yearly && by_month.is_empty() && !(by_day.is_empty() && by_month_day.is_empty()) {
+            return Err("FREQ=YEARLY with BYDAY or BYMONTHDAY requires BYMONTH".to_string());
+        }
+
+        Ok(Self { frequency, interval, by_day, by_month_day, by_month, count, until })
+    }
+
+    /// Первая дата серии, начатой `dtstart`, строго после `after`.
+    /// COUNT здесь не учитывается — число созданных экземпляров знает вызывающий код.
+    pub fn next_after(&self, dtstart: NaiveDate, after: NaiveDate) -> Option<NaiveDate> {
+        // Давние серии не перебираем с начала: сразу переходим к периоду, где лежит `after`
+        let skip = self.periods_between(dtstart, after) / self.interval;
+
+        for period in skip..skip.saturating_add(MAX_PERIODS) {
+            let start = self.period_start(dtstart, period.checked_mul(self.interval)?)?;
+
+            let mut candidates = self.expand(dtstart, start);
+            candidates.sort();
+            candidates.dedup();
+
+            for date in candidates {
+                if self.until.is_some_and(|until| date > until) {
+                    return None;
+                }
+                if date >= dtstart && date > after {
+                    return Some(date);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Исчерпан ли COUNT, если уже создано `created` экземпляров
+    pub fn is_exhausted(&self, created: i32) -> bool {
+        self.count.is_some_and(|count| i64::from(created) >= i64::from(count))
+    }
+
+    /// Сколько целых периодов частоты прошло от `dtstart` до `date`
+    fn periods_between(&self, dtstart: NaiveDate, date: NaiveDate) -> u32 {
+        let periods = match self.frequency {
+            Frequency::Daily => (date - dtstart).num_days(),
+            Frequency::Weekly => {
+                let monday = |d: NaiveDate| d - Duration::days(d.weekday().num_days_from_monday().into());
+                (monday(date) - monday(dtstart)).num_weeks()
+            }
+            Frequency::Monthly => {
+                i64::from(date.year() - dtstart.year()) * 12 + i64::from(date.month()) - i64::from(dtstart.month())
+            }
+            Frequency::Yearly => i64::from(date.year() - dtstart.year()),
+        };
+        u32::try_from(periods).unwrap_or(0)
+    }
+
+    /// Начало периода с номером `offset`, считая от периода `dtstart`
+    fn period_start(&self, dtstart: NaiveDate, offset: u32) -> Option<NaiveDate> {
+        match self.frequency {
+            Frequency::Daily => dtstart.checked_add_signed(Duration::days(offset.into())),
+            Frequency::Weekly => {
+                let monday = dtstart - Duration::days(dtstart.weekday().num_days_from_monday().into());
+                monday.checked_add_signed(Duration::weeks(offset.into()))
+            }
+            Frequency::Monthly => dtstart.with_day(1)?.checked_add_months(Months::new(offset)),
+            Frequency::Yearly => NaiveDate::from_ymd_opt(dtstart.year().checked_add(offset as i32)?, 1, 1),
+        }
+    }
+
+    /// Все даты правила внутри периода, начинающегося с `start`
+    fn expand(&self, dtstart: NaiveDate, start: NaiveDate) -> Vec<NaiveDate> {
+        let dates = match self.frequency {
+            Frequency::Daily => vec![start],
+            Frequency::Weekly => {
+                let weekdays: Vec<Weekday> = if self.by_day.is_empty() {
+                    vec![dtstart.weekday()]
+                } else {
+                    self.by_day.iter().map(|day| day.weekday).collect()
+                };
+                weekdays.into_iter()
+                    .map(|weekday| start + Duration::days(weekday.num_days_from_monday().into()))
+                    .collect()
+            }
+            Frequency::Monthly => self.expand_month(dtstart, start.year(), start.month()),
+            Frequency::Yearly => {
+                let months: Vec<u32> = if self.by_month.is_empty() {
+                    vec![dtstart.month()]
+                } else {
+                    self.by_month.clone()
+                };
+                months.into_iter()
+                    .flat_map(|month| self.expand_month(dtstart, start.year(), month))
+                    .collect()
+            }
+        };
+
+        dates.into_iter()
+            .filter(|date| self.by_month.is_empty() || self.by_month.contains(&date.month()))
+            // У DAILY уточнения только отсеивают дни
+            .filter(|date| {
+                self.frequency != Frequency::Daily
+                    || self.by_day.is_empty()
+                    || self.by_day.iter().any(|day| day.weekday == date.weekday())
+            })
+            .filter(|date| {
+                self.frequency != Frequency::Daily
+                    || self.by_month_day.is_empty()
+                    || self.by_month_day.iter().any(|day| {
+                        let first = date.with_day(1).unwrap_or(*date);
+                        month_day(first, days_in_month(first), *day) == Some(*date)
+                    })
+            })
+            .collect()
+    }
+
+    fn expand_month(&self, dtstart: NaiveDate, year: i32, month: u32) -> Vec<NaiveDate> {
+        let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+            return Vec::new();
+        };
+        let last_day = days_in_month(first);
+
+        let by_month_day: Vec<NaiveDate> = self.by_month_day.iter()
+            .filter_map(|day| month_day(first, last_day, *day))
+            .collect();
+
+        let by_day: Vec<NaiveDate> = self.by_day.iter()
+            .flat_map(|day| weekdays_in_month(first, last_day, *day))
+            .collect();
+
+        match (self.by_month_day.is_empty(), self.by_day.is_empty()) {
+            // Без уточнений — тот же день месяца, что у первого экземпляра
+            (true, true) => first.with_day(dtstart.day()).into_iter().collect(),
+            (false, true) => by_month_day,
+            (true, false) => by_day,
+            // Заданы оба — нужны даты, подходящие под оба условия
+            (false, false) => by_month_day.into_iter().filter(|date| by_day.contains(date)).collect(),
+        }
+    }
+}
+
+fn parse_by_day(value: &str) -> Result<ByDay, String> {
+    let value = value.trim().to_ascii_uppercase();
+    // split_at режет по байтам, поэтому не-ASCII отсекаем до него
+    if value.len() < 2 || !value.is_ascii() {
+        return Err(format!("Invalid BYDAY: {}", value));
+    }
+
+    let (ordinal, weekday) = value.split_at(value.len() - 2);
+    let weekday = match weekday {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return Err(format!("Invalid BYDAY: {}", value)),
+    };
+
+    let ordinal = match ordinal {
+        "" => None,
+        ordinal => Some(ordinal.trim_start_matches('+').parse::<i32>().ok()
+            .filter(|n| *n != 0 && (-5..=5).contains(n))
+            .ok_or_else(|| format!("Invalid BYDAY: {}", value))?),
+    };
+
+    Ok(ByDay { ordinal, weekday })
+}
+
+/// День месяца из BYMONTHDAY; отрицательный считается с конца (-1 — последний день)
+fn month_day(first: NaiveDate, last_day: u32, day: i32) -> Option<NaiveDate> {
+    let day = if day > 0 { day } else { last_day as i32 + day + 1 };
+    u32::try_from(day).ok().and_then(|day| first.with_day(day))
+}
+
+fn days_in_month(first: NaiveDate) -> u32 {
+    first.checked_add_months(Months::new(1))
+        .map(|next| (next - first).num_days() as u32)
+        .unwrap_or(31)
+}
+
+/// Все подходящие дни недели месяца или только N-й (с конца, если N < 0)
+fn weekdays_in_month(first: NaiveDate, last_day: u32, day: ByDay) -> Vec<NaiveDate> {
+    let offset = (7 + day.weekday.num_days_from_monday() - first.weekday().num_days_from_monday()) % 7;
+    let all: Vec<NaiveDate> = (offset + 1..=last_day)
+        .step_by(7)
+        .filter_map(|d| first.with_day(d))
+        .collect();
+
+    match day.ordinal {
+        None => all,
+        Some(n) if n > 0 => all.get(n as usize - 1).copied().into_iter().collect(),
+        Some(n) => all.len().checked_sub(n.unsigned_abs() as usize)
+            .and_then(|i| all.get(i).copied())
+            .into_iter()
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn rule(rule: &str) -> RRule {
+        RRule::parse(rule).unwrap()
+    }
+
+    #[test]
+    fn last_day_of_month_across_february() {
+        let rule = rule("FREQ=MONTHLY;BYMONTHDAY=-1");
+
+        assert_eq!(rule.next_after(date(2024, 1, 31), date(2024, 1, 31)), Some(date(2024, 2, 29)));
+        assert_eq!(rule.next_after(date(2024, 1, 31), date(2024, 2, 29)), Some(date(2024, 3, 31)));
+        assert_eq!(rule.next_after(date(2023, 1, 31), date(2023, 1, 31)), Some(date(2023, 2, 28)));
+    }
+
+    #[test]
+    fn second_tuesday() {
+        let rule = rule("FREQ=MONTHLY;BYDAY=2TU");
+        let dtstart = date(2024, 1, 9);
+
+        assert_eq!(rule.next_after(dtstart, dtstart), Some(date(2024, 2, 13)));
+        assert_eq!(rule.next_after(dtstart, date(2024, 2, 13)), Some(date(2024, 3, 12)));
+    }
+
+    #[test]
+    fn weekly_on_several_days() {
+        let rule = rule("FREQ=WEEKLY;BYDAY=MO,WE,FR");
+        let dtstart = date(2024, 1, 1);
+
+        assert_eq!(rule.next_after(dtstart, date(2024, 1, 1)), Some(date(2024, 1, 3)));
+        assert_eq!(rule.next_after(dtstart, date(2024, 1, 3)), Some(date(2024, 1, 5)));
+        assert_eq!(rule.next_after(dtstart, date(2024, 1, 5)), Some(date(2024, 1, 8)));
+    }
+
+    #[test]
+    fn count_exhaustion() {
+        let rule = rule("FREQ=DAILY;COUNT=3");
+
+        assert!(!rule.is_exhausted(2));
+        assert!(rule.is_exhausted(3));
+        assert!(!self::rule("FREQ=DAILY").is_exhausted(1000));
+        assert!(RRule::parse("FREQ=DAILY;COUNT=3;UNTIL=20240101").is_err());
+    }
+
+    #[test]
+    fn until_is_inclusive() {
+        let rule = rule("FREQ=DAILY;UNTIL=20240115T235959Z");
+        let dtstart = date(2024, 1, 10);
+
+        assert_eq!(rule.next_after(dtstart, date(2024, 1, 14)), Some(date(2024, 1, 15)));
+        assert_eq!(rule.next_after(dtstart, date(2024, 1, 15)), None);
+    }
+
+    #[test]
+    fn from_completion_and_fixed_schedules() {
+        let rule = rule("FREQ=DAILY;INTERVAL=3");
+        let dtstart = date(2024, 1, 1);
+
+        // По календарю опоздание с выполнением срок не сдвигает
+        assert_eq!(rule.next_after(dtstart, dtstart), Some(date(2024, 1, 4)));
+        // После выполнения — отсчет от дня выполнения
+        let completed = date(2024, 1, 10);
+        assert_eq!(rule.next_after(completed, completed), Some(date(2024, 1, 13)));
+    }
+
+    #[test]
+    fn rule_without_matches_gives_up() {
+        let rule = rule("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30");
+
+        assert_eq!(rule.next_after(date(2024, 1, 1), date(2024, 1, 1)), None);
+    }
+
+    #[test]
+    fn non_ascii_by_day_is_rejected() {
+        assert!(RRule::parse("FREQ=WEEKLY;BYDAY=ПН").is_err());
+        assert!(RRule::parse("FREQ=MONTHLY;BYDAY=1é").is_err());
+    }
+}
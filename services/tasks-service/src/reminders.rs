@@ -0,0 +1,345 @@
+use axum::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use std::{env, sync::Arc};
+use uuid::Uuid;
+
+use crate::notifications::{self, Category, NewNotification};
+
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+const BATCH_SIZE: i64 = 100;
+const MAX_ATTEMPTS: i32 = 5;
+/// Напоминание в статусе sending дольше этого — процесс упал во время отправки
+const STALE_SENDING_MINUTES: i64 = 10;
+/// Сколько дней после срока задачи хранится история отправок
+const HISTORY_RETENTION_DAYS: i64 = 30;
+
+/// Когда напомнить о задаче: начало дня срока (UTC) минус `reminder_days` и `reminder_hours`.
+/// В `user_settings` нет часового пояса, поэтому день срока всегда считается в UTC
+const REMIND_AT_SQL: &str = "(t.due_date::timestamp AT TIME ZONE 'UTC')
+    - make_interval(days => COALESCE(t.reminder_days, 0), hours => COALESCE(t.reminder_hours, 0))";
+
+/// Напоминание, готовое к отправке
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DueReminder {
+    /// Не меняется между попытками — получатель вебхука может по нему отсеять повторы
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub user_id: Uuid,
+    pub title: String,
+    pub due_date: NaiveDate,
+    pub remind_at: DateTime<Utc>,
+    #[serde(skip)]
+    pub channel: String,
+    #[serde(skip)]
+    pub attempts: i32,
+}
+
+/// Способ доставки напоминаний. Каналы включаются через `REMINDER_CHANNELS`
+/// (через запятую, по умолчанию `inbox`); каждый канал доставляет независимо.
+#[async_trait]
+pub trait ReminderChannel: Send + Sync {
+    /// Имя канала, под ним хранится история отправок
+    fn name(&self) -> &'static str;
+    async fn send(&self, reminder: &DueReminder) -> Result<(), String>;
+}
+
+/// Пишет напоминания в лог — для разработки
+pub struct LogChannel;
+
+#[async_trait]
+impl ReminderChannel for LogChannel {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    async fn send(&self, reminder: &DueReminder) -> Result<(), String> {
+        tracing::info!(
+            user_id = %reminder.user_id,
+            task_id = %reminder.task_id,
+            due_date = %reminder.due_date,
+            title = %reminder.title,
+            "Task reminder"
+        );
+        Ok(())
+    }
+}
+
+/// Уведомление во входящих пользователя
+pub struct InboxChannel {
+    pool: PgPool,
+}
+
+#[async_trait]
+impl ReminderChannel for InboxChannel {
+    fn name(&self) -> &'static str {
+        "inbox"
+    }
+
+    async fn send(&self, reminder: &DueReminder) -> Result<(), String> {
+        notifications::notify(&self.pool, NewNotification {
+            user_id: reminder.user_id,
+            category: Category::Reminder,
+            title: reminder.title.clone(),
+            body: format!("Срок задачи — {}", reminder.due_date.format("%d.%m.%Y")),
+            task_id: Some(reminder.task_id),
+        })
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+    }
+}
+
+/// POST JSON на `REMINDER_WEBHOOK_URL` (бот, почтовый шлюз и т.п.)
+pub struct WebhookChannel {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl WebhookChannel {
+    pub fn from_env() -> Self {
+        let url = env::var("REMINDER_WEBHOOK_URL")
+            .expect("REMINDER_WEBHOOK_URL must be set when the webhook reminder channel is enabled");
+
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .expect("Failed to build HTTP client"),
+            url,
+        }
+    }
+}
+
+#[async_trait]
+impl ReminderChannel for WebhookChannel {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn send(&self, reminder: &DueReminder) -> Result<(), String> {
+        self.client.post(&self.url)
+            .json(reminder)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+pub fn channels_from_env(pool: &PgPool) -> Vec<Arc<dyn ReminderChannel>> {
+    let names = env::var("REMINDER_CHANNELS").unwrap_or_else(|_| "inbox".to_string());
+
+    names.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| -> Option<Arc<dyn ReminderChannel>> {
+            match name {
+                "inbox" => Some(Arc::new(InboxChannel { pool: pool.clone() })),
+                "log" => Some(Arc::new(LogChannel)),
+                "webhook" => Some(Arc::new(WebhookChannel::from_env())),
+                unknown => {
+                    tracing::warn!(channel = %unknown, "Unknown reminder channel, ignoring");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Запускает планировщик напоминаний. Его можно держать на нескольких репликах:
+/// строки истории уникальны по (задача, момент, канал), а отправку каждая реплика
+/// забирает себе через `FOR UPDATE SKIP LOCKED`.
+pub fn spawn_scheduler(pool: PgPool, channels: Vec<Arc<dyn ReminderChannel>>) {
+    if channels.is_empty() {
+        tracing::warn!("No reminder channels configured, reminders are disabled");
+        return;
+    }
+
+    tracing::info!(
+        channels = ?channels.iter().map(|channel| channel.name()).collect::<Vec<_>>(),
+        "Reminder scheduler started"
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = tick(&pool, &channels).await {
+                tracing::error!(error = %e, "Reminder scheduler tick failed");
+            }
+        }
+    });
+}
+
+async fn tick(pool: &PgPool, channels: &[Arc<dyn ReminderChannel>]) -> Result<(), sqlx::Error> {
+    let names: Vec<&str> = channels.iter().map(|channel| channel.name()).collect();
+
+    schedule(pool, &names).await?;
+    skip_stale(pool).await?;
+
+    loop {
+        let batch = claim(pool).await?;
+        if batch.is_empty() {
+            break;
+        }
+
+        for reminder in &batch {
+            match channels.iter().find(|channel| channel.name() == reminder.channel) {
+                Some(channel) => {
+                    let result = channel.send(reminder).await;
+                    finish(pool, reminder, result).await?;
+                }
+                // Канал выключили, пока напоминание ждало повтора
+                None => finish(pool, reminder, Err(format!("channel {} is disabled", reminder.channel))).await?,
+            }
+        }
+
+        if (batch.len() as i64) < BATCH_SIZE {
+            break;
+        }
+    }
+
+    purge(pool).await
+}
+
+/// Заводит строку истории для каждого наступившего напоминания и канала. Уникальный ключ
+/// делает это идемпотентным: повторный проход и рестарт не создадут второе напоминание.
+/// Задачи, срок которых прошел больше суток назад, не напоминаются (например, после простоя).
+async fn schedule(pool: &PgPool, channels: &[&str]) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+
+    let scheduled = sqlx::query(&format!(
+        "INSERT INTO task_reminders (id, task_id, channel, remind_at, status, attempts, next_attempt_at, created_at)
+         SELECT gen_random_uuid(), t.id, c.channel, {remind_at}, 'pending', 0, $1, $1
+         FROM tasks t
+         CROSS JOIN unnest($2::text[]) AS c(channel)
+         WHERE t.status = 'active'
+           AND (t.reminder_days IS NOT NULL OR t.reminder_hours IS NOT NULL)
+           AND t.due_date >= ($1 - INTERVAL '1 day')::date
+           AND {remind_at} <= $1
+         ON CONFLICT (task_id, remind_at, channel) DO NOTHING",
+        remind_at = REMIND_AT_SQL,
+    ))
+    .bind(now)
+    .bind(channels)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    if scheduled > 0 {
+        tracing::debug!(scheduled, "Task reminders scheduled");
+    }
+    Ok(())
+}
+
+/// Напоминания, которые больше не нужны: задачу выполнили, сдвинули срок
+/// или пользователь отключил уведомления в настройках
+async fn skip_stale(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!(
+        "UPDATE task_reminders r SET status = 'skipped'
+         FROM tasks t
+         LEFT JOIN user_settings s ON s.user_id = t.user_id
+         WHERE r.task_id = t.id
+           AND r.status = 'pending'
+           AND (t.status <> 'active' OR s.notifications_enabled = false OR r.remind_at <> {remind_at})",
+        remind_at = REMIND_AT_SQL,
+    ))
+    .execute(pool)
+    .await?;
+
+    // Процесс упал посреди отправки. Повторять нельзя: письмо или вебхук могли уже уйти
+    sqlx::query(
+        "UPDATE task_reminders SET status = 'failed', last_error = 'interrupted while sending'
+         WHERE status = 'sending' AND claimed_at < $1"
+    )
+    .bind(Utc::now() - Duration::minutes(STALE_SENDING_MINUTES))
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Забирает пачку напоминаний на отправку. Статус sending фиксируется до отправки,
+/// поэтому после рестарта то же напоминание не уйдет второй раз.
+async fn claim(pool: &PgPool) -> Result<Vec<DueReminder>, sqlx::Error> {
+    let now = Utc::now();
+
+    sqlx::query_as::<_, DueReminder>(
+        "UPDATE task_reminders r SET status = 'sending', attempts = r.attempts + 1, claimed_at = $1
+         FROM tasks t
+         WHERE t.id = r.task_id
+           AND r.id IN (
+               SELECT id FROM task_reminders
+               WHERE status = 'pending' AND next_attempt_at <= $1
+               ORDER BY remind_at
+               LIMIT $2
+               FOR UPDATE SKIP LOCKED
+           )
+         RETURNING r.id, r.task_id, t.user_id, t.title, t.due_date, r.remind_at, r.channel, r.attempts"
+    )
+    .bind(now)
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await
+}
+
+async fn finish(pool: &PgPool, reminder: &DueReminder, result: Result<(), String>) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+
+    match result {
+        Ok(()) => {
+            sqlx::query("UPDATE task_reminders SET status = 'sent', sent_at = $2, last_error = NULL WHERE id = $1")
+                .bind(reminder.id)
+                .bind(now)
+                .execute(pool)
+                .await?;
+
+            tracing::info!(task_id = %reminder.task_id, channel = %reminder.channel, "Task reminder sent");
+        }
+        Err(error) => {
+            let exhausted = reminder.attempts >= MAX_ATTEMPTS;
+            // 1, 2, 4, 8... минут между попытками
+            let backoff = Duration::minutes(1 << (reminder.attempts - 1).clamp(0, 10));
+
+            sqlx::query(
+                "UPDATE task_reminders SET status = $2, last_error = $3, next_attempt_at = $4 WHERE id = $1"
+            )
+            .bind(reminder.id)
+            .bind(if exhausted { "failed" } else { "pending" })
+            .bind(&error)
+            .bind(now + backoff)
+            .execute(pool)
+            .await?;
+
+            tracing::warn!(
+                task_id = %reminder.task_id,
+                channel = %reminder.channel,
+                attempts = reminder.attempts,
+                exhausted,
+                error = %error,
+                "Task reminder delivery failed"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// История нужна, пока напоминание может быть запланировано повторно,
+/// то есть пока срок задачи не прошел
+async fn purge(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "DELETE FROM task_reminders r USING tasks t
+         WHERE r.task_id = t.id
+           AND r.status IN ('sent', 'failed', 'skipped')
+           AND t.due_date < $1::date"
+    )
+    .bind(Utc::now() - Duration::days(HISTORY_RETENTION_DAYS))
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
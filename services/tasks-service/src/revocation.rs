@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgListener, PgPool};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use uuid::Uuid;
+
+/// Канал, в который auth-service публикует отозванные jti и id сессий
+const REVOCATION_CHANNEL: &str = "token_revoked";
+/// Страховочная пересинхронизация на случай пропущенных уведомлений
+const RESYNC_INTERVAL: Duration = Duration::from_secs(60);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Кэш общего денайлиста `token_denylist` в памяти.
+/// Проверка токена не ходит в базу: кэш наполняется при старте и обновляется через LISTEN/NOTIFY.
+#[derive(Clone, Default)]
+pub struct Denylist {
+    entries: Arc<RwLock<HashMap<Uuid, DateTime<Utc>>>>,
+}
+
+impl Denylist {
+    pub fn is_revoked(&self, ids: &[Uuid]) -> bool {
+        let now = Utc::now();
+        let entries = self.entries.read().unwrap();
+        ids.iter()
+            .any(|id| entries.get(id).is_some_and(|expires_at| *expires_at > now))
+    }
+
+    fn insert(&self, id: Uuid, expires_at: DateTime<Utc>) {
+        self.entries.write().unwrap().insert(id, expires_at);
+    }
+
+    async fn reload(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        let rows = sqlx::query_as::<_, (Uuid, DateTime<Utc>)>(
+            "SELECT id, expires_at FROM token_denylist WHERE expires_at > $1"
+        )
+        .bind(now)
+        .fetch_all(pool)
+        .await?;
+
+        // Сливаем, а не заменяем: уведомление могло прийти, пока шел запрос
+        let mut entries = self.entries.write().unwrap();
+        entries.retain(|_, expires_at| *expires_at > now);
+        entries.extend(rows);
+
+        tracing::debug!(entries = entries.len(), "Token denylist synchronized");
+        Ok(())
+    }
+
+    /// Запускает фоновую синхронизацию кэша с базой
+    pub fn spawn_sync(&self, pool: PgPool) {
+        let denylist = self.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = denylist.listen(&pool).await {
+                    tracing::error!(error = %e, "Token denylist sync failed, reconnecting");
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+    }
+
+    async fn listen(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener.listen(REVOCATION_CHANNEL).await?;
+
+        // Загружаем уже после подписки, чтобы не потерять отзывы между загрузкой и LISTEN
+        self.reload(pool).await?;
+
+        let mut resync = tokio::time::interval(RESYNC_INTERVAL);
+        resync.tick().await;
+
+        loop {
+            tokio::select! {
+                notification = listener.recv() => {
+                    let notification = notification?;
+                    match parse_payload(notification.payload()) {
+                        Some((id, expires_at)) => {
+                            tracing::info!(id = %id, "Token revoked");
+                            self.insert(id, expires_at);
+                        }
+                        None => tracing::warn!(payload = %notification.payload(), "Malformed revocation notification"),
+                    }
+                }
+                _ = resync.tick() => self.reload(pool).await?,
+            }
+        }
+    }
+}
+
+/// Формат уведомления: `<uuid>:<expires_at unix>`
+fn parse_payload(payload: &str) -> Option<(Uuid, DateTime<Utc>)> {
+    let (id, expires_at) = payload.split_once(':')?;
+    let id = id.parse::<Uuid>().ok()?;
+    let expires_at = DateTime::<Utc>::from_timestamp(expires_at.parse::<i64>().ok()?, 0)?;
+    Some((id, expires_at))
+}